@@ -0,0 +1,20 @@
+use std::env;
+
+/// `BACKERROR_DISABLE` and `BACKERROR_FORCE` are read here rather than in the macro, so that cargo
+/// rebuilds the macro crate, and so the crates using it, when they change
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(backerror_disable)");
+    println!("cargo::rustc-check-cfg=cfg(backerror_force)");
+
+    let is_set = |name| {
+        println!("cargo::rerun-if-env-changed={name}");
+        env::var(name).is_ok_and(|value| !value.is_empty() && value != "0")
+    };
+    let (disable, force) = (is_set("BACKERROR_DISABLE"), is_set("BACKERROR_FORCE"));
+    // `BACKERROR_DISABLE` wins if both are set
+    if disable {
+        println!("cargo::rustc-cfg=backerror_disable");
+    } else if force {
+        println!("cargo::rustc-cfg=backerror_force");
+    }
+}
@@ -0,0 +1,340 @@
+use proc_macro2::{Ident, Span};
+use syn::meta::ParseNestedMeta;
+use syn::punctuated::Punctuated;
+use syn::{Attribute, LitBool, LitStr, Path, Token, Type, parenthesized, parse_quote};
+
+/// arguments of `#[backerror(...)]`
+#[derive(Default)]
+pub(crate) struct Args {
+    /// `auto_from`, `#[from]` for the single-field tuple variants of error types
+    pub auto_from: bool,
+    /// `accessors`, `is_{variant}` and `as_{variant}` methods of an enum
+    pub accessors: bool,
+    /// `also_from(Arc, Box)`
+    pub also_from: Vec<Ident>,
+    /// `backtrace = false`, the default of the variants without their own
+    pub backtrace: Option<LitBool>,
+    /// `crate = "..."`, the path of the `backerror` crate
+    pub krate: Option<Path>,
+    /// `debug_expansion`, print the generated code to stderr
+    pub debug_expansion: bool,
+    /// `force`, the type derives `thiserror::Error` through a path which isn't recognized, e.g. an
+    /// alias
+    pub force: bool,
+    /// `located_variants`, a `location` field and a `{variant}_at` constructor for the variants
+    /// without `#[from]` field
+    pub located_variants: bool,
+    /// `name = "..."`, the `ErrorName` of the type
+    pub name: Option<LitStr>,
+    /// `no_from(io::Error, ...)`, the `#[from]` types with a hand-written `From` impl
+    pub no_from: Vec<Type>,
+    /// `release = "on"` or `"off"`, overriding the `release_off` feature for the type
+    pub release: Option<bool>,
+    /// `result_alias = "..."`, the name of a `Result` alias with the error type
+    pub result_alias: Option<Ident>,
+    /// `url = "..."`, the default of the variants without their own
+    pub url: Option<LitStr>,
+    /// `wrap = "..."`, the wrapper type of the `#[from]` fields instead of `LocatedError`
+    pub wrap: Option<Path>,
+    /// `wrap_source`, wrap `#[source]` fields too, with located constructors
+    pub wrap_source: bool,
+}
+
+impl Args {
+    /// parse one argument
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("auto_from") {
+            self.auto_from = true;
+            Ok(())
+        } else if meta.path.is_ident("accessors") {
+            self.accessors = true;
+            Ok(())
+        } else if meta.path.is_ident("also_from") {
+            meta.parse_nested_meta(|nested| match nested.path.get_ident() {
+                Some(wrapper) if wrapper == "Arc" || wrapper == "Box" => {
+                    self.also_from.push(wrapper.clone());
+                    Ok(())
+                }
+                _ => Err(nested.error("expected `Arc` or `Box`")),
+            })
+        } else if meta.path.is_ident("backtrace") {
+            self.backtrace = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("crate") {
+            let path: LitStr = meta.value()?.parse()?;
+            self.krate = Some(path.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("debug_expansion") {
+            self.debug_expansion = true;
+            Ok(())
+        } else if meta.path.is_ident("force") {
+            self.force = true;
+            Ok(())
+        } else if meta.path.is_ident("located_variants") {
+            self.located_variants = true;
+            Ok(())
+        } else if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("no_from") {
+            let content;
+            parenthesized!(content in meta.input);
+            let types = Punctuated::<Type, Token![,]>::parse_terminated(&content)?;
+            self.no_from.extend(types);
+            Ok(())
+        } else if meta.path.is_ident("release") {
+            let value: LitStr = meta.value()?.parse()?;
+            self.release = match value.value().as_str() {
+                "on" => Some(true),
+                "off" => Some(false),
+                _ => return Err(syn::Error::new_spanned(value, "expected \"on\" or \"off\"")),
+            };
+            Ok(())
+        } else if meta.path.is_ident("result_alias") {
+            let name: LitStr = meta.value()?.parse()?;
+            self.result_alias = Some(name.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("url") {
+            self.url = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("wrap") {
+            let path: LitStr = meta.value()?.parse()?;
+            self.wrap = Some(path.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("wrap_source") {
+            self.wrap_source = true;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported backerror argument"))
+        }
+    }
+
+    /// the path of the `backerror` crate in the generated code, `backerror` unless overridden
+    pub fn krate(&self) -> Path {
+        self.krate
+            .clone()
+            .unwrap_or_else(|| parse_quote!(backerror))
+    }
+
+    /// Whether the type is enhanced in this build: always in debug builds, and in release builds
+    /// unless the `release_off` feature is set, which `release = "on"` or `"off"` overrides.
+    /// `BACKERROR_DISABLE` or `BACKERROR_FORCE` set when building override everything.
+    pub fn enabled(&self) -> bool {
+        if cfg!(backerror_disable) {
+            return false;
+        }
+        if cfg!(any(backerror_force, debug_assertions)) {
+            return true;
+        }
+        self.release.unwrap_or(cfg!(not(feature = "release_off")))
+    }
+
+    /// whether `attrs` derive `thiserror::Error`, or `force` is given
+    pub fn derives_thiserror(&self, attrs: &Vec<Attribute>) -> bool {
+        self.force || crate::check_derive_thiserror(attrs)
+    }
+
+    /// whether `ty` is listed in `no_from`, as it's written in the `#[from]` field
+    pub fn no_from(&self, ty: &Type) -> bool {
+        self.no_from
+            .iter()
+            .any(|no_from| crate::same_type(no_from, ty))
+    }
+
+    /// the wrapper type of the `#[from]` fields, `backerror::LocatedError` unless overridden
+    pub fn wrapper(&self) -> Path {
+        let krate = self.krate();
+        self.wrap
+            .clone()
+            .unwrap_or_else(|| parse_quote!(#krate::LocatedError))
+    }
+
+    /// the arguments which only apply to `backerror::LocatedError`, given along with `wrap`
+    pub fn conflicts_with_wrap(&self) -> Option<syn::Error> {
+        self.wrap.as_ref()?;
+        let message = "`url` and `backtrace` require `backerror::LocatedError`, not a `wrap` type";
+        match (&self.url, &self.backtrace) {
+            (Some(url), _) => Some(syn::Error::new_spanned(url, message)),
+            (None, Some(backtrace)) => Some(syn::Error::new_spanned(backtrace, message)),
+            (None, None) => None,
+        }
+    }
+
+    /// the arguments which don't apply to a `#[derive(Snafu)]` type, whose source fields are only
+    /// wrapped with `From`
+    pub fn unsupported_with_snafu(&self) -> Option<syn::Error> {
+        let message = |name| format!("`{name}` isn't supported with `#[derive(Snafu)]`");
+        if let Some(url) = &self.url {
+            return Some(syn::Error::new_spanned(url, message("url")));
+        }
+        if let Some(backtrace) = &self.backtrace {
+            return Some(syn::Error::new_spanned(backtrace, message("backtrace")));
+        }
+        if let Some(ty) = self.no_from.first() {
+            return Some(syn::Error::new_spanned(ty, message("no_from")));
+        }
+        if let Some(ident) = self.also_from.first() {
+            return Some(syn::Error::new_spanned(ident, message("also_from")));
+        }
+        [
+            (self.accessors, "accessors"),
+            (self.located_variants, "located_variants"),
+            (self.wrap_source, "wrap_source"),
+        ]
+        .into_iter()
+        .find(|(given, _)| *given)
+        .map(|(_, name)| syn::Error::new(Span::call_site(), message(name)))
+    }
+}
+
+/// arguments of `#[backerror(...)]` on an enum variant
+#[derive(Clone, Default)]
+pub(crate) struct VariantArgs {
+    /// `help = "..."`, attached to the errors converted into the variant
+    pub help: Option<LitStr>,
+    /// `url = "..."`, likewise
+    pub url: Option<LitStr>,
+    /// `backtrace = false`, wrap the errors converted into the variant without a backtrace
+    pub backtrace: Option<LitBool>,
+    /// `skip`, leave the `#[from]` fields of the variant as they are
+    pub skip: bool,
+    /// `skip_from`, no `From` impl, the `#[from]` fields become `#[source]` fields with a
+    /// located constructor
+    pub skip_from: bool,
+    /// the `#[cfg(...)]` attributes of the variant, repeated on the `From` impls generated for it
+    pub cfgs: Vec<Attribute>,
+}
+
+impl VariantArgs {
+    /// parse and remove the `#[backerror(...)]` attributes of a variant, and keep its `#[cfg(...)]`
+    /// attributes
+    pub fn take(attrs: &mut Vec<Attribute>) -> syn::Result<Self> {
+        let mut args = VariantArgs::default();
+        let mut result = Ok(());
+        args.cfgs = crate::cfg_attrs(attrs).cloned().collect();
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("backerror") {
+                return true;
+            }
+            if result.is_ok() {
+                result = attr.parse_nested_meta(|meta| args.parse(meta));
+            }
+            false
+        });
+        result.map(|()| args)
+    }
+
+    /// these arguments, with the defaults of the item for the ones not given
+    pub fn or_defaults(&self, args: &Args) -> VariantArgs {
+        VariantArgs {
+            url: self.url.clone().or_else(|| args.url.clone()),
+            backtrace: self.backtrace.clone().or_else(|| args.backtrace.clone()),
+            ..self.clone()
+        }
+    }
+
+    /// whether the backtrace is disabled with `backtrace = false`
+    pub fn without_backtrace(&self) -> bool {
+        self.backtrace
+            .as_ref()
+            .is_some_and(|backtrace| !backtrace.value)
+    }
+
+    /// the first argument given, if any
+    pub fn first(&self) -> Option<&LitStr> {
+        self.help.as_ref().or(self.url.as_ref())
+    }
+
+    /// the arguments which only apply to `backerror::LocatedError`, given along with `wrap`
+    pub fn conflicts_with_wrap(&self, args: &Args) -> Option<syn::Error> {
+        args.wrap.as_ref()?;
+        let message =
+            "`help`, `url` and `backtrace` require `backerror::LocatedError`, not a `wrap` type";
+        match (self.first(), &self.backtrace) {
+            (Some(arg), _) => Some(syn::Error::new_spanned(arg, message)),
+            (None, Some(backtrace)) => Some(syn::Error::new_spanned(backtrace, message)),
+            (None, None) => None,
+        }
+    }
+
+    /// the arguments which don't apply to the variant `ident` of a `#[derive(Snafu)]` enum, only
+    /// `skip` does
+    pub fn unsupported_with_snafu(&self, ident: &Ident) -> Option<syn::Error> {
+        let message = "only `skip` is supported on the variants of a `#[derive(Snafu)]` enum";
+        match (self.first(), &self.backtrace) {
+            (Some(arg), _) => Some(syn::Error::new_spanned(arg, message)),
+            (None, Some(backtrace)) => Some(syn::Error::new_spanned(backtrace, message)),
+            (None, None) if self.skip_from => Some(syn::Error::new_spanned(ident, message)),
+            (None, None) => None,
+        }
+    }
+
+    /// parse one argument
+    fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("help") {
+            self.help = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("url") {
+            self.url = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("backtrace") {
+            self.backtrace = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("skip") {
+            self.skip = true;
+            Ok(())
+        } else if meta.path.is_ident("skip_from") {
+            self.skip_from = true;
+            Ok(())
+        } else {
+            Err(meta.error("unsupported backerror variant argument"))
+        }
+    }
+}
+
+/// arguments of `#[backerror(...)]` on a field
+#[derive(Default)]
+pub(crate) struct FieldArgs {
+    /// `skip`, leave the field as it is
+    pub skip: bool,
+}
+
+impl FieldArgs {
+    /// parse the `#[backerror(...)]` attributes of a field, which are kept until [`Self::strip`]
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut args = FieldArgs::default();
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("backerror"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    args.skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported backerror field argument"))
+                }
+            })?;
+        }
+        Ok(args)
+    }
+
+    /// whether the field has `#[backerror(skip)]`, invalid arguments are reported by
+    /// `enhance_fields`
+    pub fn skipped(attrs: &[Attribute]) -> bool {
+        Self::parse(attrs).is_ok_and(|args| args.skip)
+    }
+
+    /// remove the `#[backerror(...)]` attributes of `fields`, returning whether there were any
+    pub fn strip(fields: &mut syn::Fields) -> bool {
+        let mut stripped = false;
+        for field in fields.iter_mut() {
+            field.attrs.retain(|attr| {
+                let backerror = attr.path().is_ident("backerror");
+                stripped |= backerror;
+                !backerror
+            });
+        }
+        stripped
+    }
+}
@@ -1,16 +1,117 @@
 #![allow(unused_imports, dead_code)]
 use proc_macro::TokenStream;
 use proc_macro2::Ident;
-use quote::{ToTokens, quote};
+use quote::{ToTokens, format_ident, quote, quote_spanned};
 use syn::{
-    Item, ItemEnum, ItemStruct, Meta, Path, Token, parse_macro_input, punctuated::Punctuated,
+    Expr, Item, ItemEnum, ItemFn, ItemMod, ItemStruct, Meta, Path, ReturnType, Token,
+    parse_macro_input, parse_quote,
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
 };
 
-/// backerror
-#[cfg(not(any(not(feature = "release_off"), debug_assertions)))]
-#[proc_macro_attribute]
-pub fn backerror(_args: TokenStream, input: TokenStream) -> TokenStream {
-    input
+mod args;
+
+use args::{Args, FieldArgs, VariantArgs};
+
+/// `#[backerror]` in release mode with `release_off`, or with `release = "off"`, which leaves the
+/// fields as they are
+fn backerror_release(
+    backerror_args: &Args,
+    args2: proc_macro2::TokenStream,
+    input: TokenStream,
+) -> TokenStream {
+    let mut item = parse_macro_input!(input as Item);
+    if let Item::Mod(item_mod) = item {
+        return backerror_mod(item_mod, args2, backerror_args).into();
+    }
+    // the same errors as in debug mode
+    match &item {
+        Item::Enum(ItemEnum { attrs, ident, .. })
+        | Item::Struct(ItemStruct { attrs, ident, .. })
+            if !backerror_args.derives_thiserror(attrs) =>
+        {
+            return missing_derive(ident, item.to_token_stream().into());
+        }
+        _ => {}
+    }
+
+    // `context` and the constructors of `wrap_source` are still generated, so that their callers
+    // build in release mode
+    let context = context_impl(&item, backerror_args);
+    let alias = result_alias(&item, backerror_args);
+    let name = error_name_impl(&item, backerror_args);
+    let mut constructors = None;
+    let mut invalid = backerror_args.conflicts_with_wrap();
+    match &mut item {
+        Item::Enum(item_enum) => {
+            if backerror_args.auto_from {
+                auto_from(item_enum);
+            }
+            let mut fns = Vec::new();
+            let mut seen = Vec::new();
+            let field_types = single_field_types(item_enum);
+            for variant in item_enum.variants.iter_mut() {
+                // variant and field arguments only apply to the generated `From` impls and
+                // constructors, but they're still checked
+                let mut variant_args = VariantArgs::take(&mut variant.attrs).unwrap_or_else(|e| {
+                    combine_error(&mut invalid, e);
+                    VariantArgs::default()
+                });
+                let from = has_from(&variant.fields);
+                variant_args.skip_from |= from_with_context(&variant.fields);
+                if let Some(e) = variant_args.conflicts_with_wrap(backerror_args) {
+                    combine_error(&mut invalid, e);
+                }
+                check_field_args(&variant.fields, &mut invalid);
+                dedup_from(variant, variant_args.skip_from, &mut seen, &mut invalid);
+                release_no_from(&mut variant.fields, backerror_args);
+                let constructor = ((backerror_args.wrap_source || variant_args.skip_from)
+                    && !variant_args.skip)
+                    .then(|| source_constructor(variant, &item_enum.vis, None, backerror_args))
+                    .flatten();
+                FieldArgs::strip(&mut variant.fields);
+                if backerror_args.located_variants
+                    && !from
+                    && !variant_args.skip
+                    && constructor.is_none()
+                {
+                    fns.extend(located_variant(variant, &item_enum.vis, &mut invalid));
+                }
+                fns.extend(constructor);
+            }
+            if backerror_args.accessors {
+                fns.extend(accessors(item_enum, &field_types));
+            }
+            constructors = constructors_impl(&item_enum.ident, &item_enum.generics, fns);
+        }
+        Item::Struct(item_struct) => {
+            check_field_args(&item_struct.fields, &mut invalid);
+            let from_with_context = struct_from_with_context(item_struct);
+            release_no_from(&mut item_struct.fields, backerror_args);
+            let constructor = (backerror_args.wrap_source || from_with_context)
+                .then(|| struct_constructor(item_struct, None, backerror_args))
+                .flatten();
+            FieldArgs::strip(&mut item_struct.fields);
+            constructors = constructors_impl(
+                &item_struct.ident,
+                &item_struct.generics,
+                constructor.into_iter().collect(),
+            );
+        }
+        _ => {}
+    }
+    let invalid = invalid.map(|e| e.to_compile_error());
+    quote!(#item #context #alias #name #constructors #invalid).into()
+}
+
+/// In release mode, the `#[from]` fields of `no_from` types aren't wrapped, so `thiserror` would
+/// implement the same `From` as the hand-written impl, they become `#[source]` fields instead
+fn release_no_from(fields: &mut syn::Fields, args: &Args) {
+    for field in fields.iter_mut() {
+        if check_attr_from(&field.attrs) && args.no_from(&field.ty) {
+            from_to_source(field);
+        }
+    }
 }
 
 /// Helper attribute macro to enhance `thiserror::Error`, which adds `backerror::LocatedError` to the error type.
@@ -31,202 +132,3327 @@ pub fn backerror(_args: TokenStream, input: TokenStream) -> TokenStream {
 /// pub struct MyError(#[from] std::io::Error);
 ///
 /// ```
-#[cfg(any(not(feature = "release_off"), debug_assertions))]
+///
+/// `#[backerror]` goes above `#[derive(Error)]`, an enum or struct without it is a compile error.
+/// Structs are enhanced too, transparent or not. A `#[from]` field along with context fields, e.g.
+/// `path` in `ReadError { #[from] source: std::io::Error, path: PathBuf }`, which `thiserror`
+/// doesn't allow, becomes a `#[source]` field with a located `new` constructor instead.
+/// Both thiserror 1 and 2 are supported, including the `#[error(fmt = path::to::fmt)]` form of
+/// thiserror 2, whose function gets the located fields through `Deref`.
+///
+/// On an inline module, `#[backerror(...)]` is added with the same arguments to each
+/// `#[derive(Error)]` enum and struct inside, including the ones of nested modules, except the
+/// ones with their own `#[backerror(...)]`:
+/// ```ignore
+/// #[backerror]
+/// pub mod errors {
+///     #[derive(Debug, Error)]
+///     pub enum LoadError { ... }
+/// }
+/// ```
+/// The module has to be inline, attribute macros on `mod errors;` and inner attributes, e.g.
+/// `#![backerror]` at the top of `errors.rs`, are unstable.
+///
+/// On a `#[derive(Snafu)]` enum or struct, the source fields are wrapped instead, and converted
+/// with `#[snafu(source(from(T, ...)))]`, so that the context selectors locate them:
+/// ```ignore
+/// #[backerror]
+/// #[derive(Debug, Snafu)]
+/// pub enum ConfigError {
+///     #[snafu(display("could not read {path}"))]
+///     Read { source: std::io::Error, path: String },
+/// }
+/// ```
+/// Only `crate`, `wrap`, `release` and `result_alias` apply to them, and `skip` to their variants
+/// and fields.
+///
+/// Arguments:
+/// * `accessors`: generate `is_{variant}()` methods, and `as_{variant}()` methods returning the
+///   field of the variants with a single field, through the `backerror::LocatedError` if it's
+///   wrapped, e.g. `is_io()` and `as_io() -> Option<&std::io::Error>` for `Io(#[from] std::io::Error)`,
+///   or the trait object of a `Box<dyn Error + ...>` field.
+/// * `also_from(Arc, Box)`: also implement `From<Arc<T>>` and/or `From<Box<T>>` for each `#[from] T`.
+///   `From<Arc<T>>` requires `T: Clone` unless the `Arc` is the only reference.
+/// * `auto_from`: handle the single-field tuple variants of an enum whose type looks like an
+///   error type as if the field had `#[from]`, e.g. `Io(std::io::Error)`. The type is taken as an
+///   error type if its name ends with `Error` or it's a `Box<dyn Error + ...>`, since the macro
+///   can't check its impls. Only the first variant of a type is converted, and the variants with
+///   `skip` or `skip_from` and the fields with `#[source]` are left as they are.
+/// * `backtrace = false`: capture only the location of the errors converted into the type, not a
+///   backtrace, e.g. for frequent, expected errors, see `backerror::LocatedError::without_backtrace`.
+/// * `crate = "..."`: the path of the `backerror` crate in the generated code, e.g.
+///   `crate = "my_facade::backerror"` when it's renamed or re-exported.
+/// * `debug_expansion`: print the code generated for the type to the standard error when it's
+///   compiled, e.g. to see why a field isn't wrapped or which `From` impls conflict, without
+///   `cargo expand`. On a module, each of its types prints its own.
+/// * `force`: enhance the type although its derive isn't recognized as `thiserror::Error`, e.g.
+///   `#[derive(TError)]` after `use thiserror::Error as TError;`. On a module, the aliases
+///   imported in it are recognized without it.
+/// * `located_variants`: add a `location: &'static Location<'static>` field to the variants of an
+///   enum without a `#[from]` field, and a `#[track_caller]` constructor `{variant}_at` taking
+///   their other fields in order, e.g. `MyError::parse_at(line)` for `Parse { line: usize }`, so
+///   that errors created by hand are located too. The field is the last one of a tuple variant.
+///   The variants with `#[error(transparent)]` or `#[error(fmt = ...)]` are left as they are.
+/// * `name = "..."`: implement `backerror::ErrorName` for the type, the name shown instead of
+///   its type name in the "by Type" label of the errors wrapped by the `From` impls of
+///   `#[backerror]` types with a `#[from]` field of the type.
+/// * `no_from(io::Error, ...)`: no `From` impl for these `#[from]` types, e.g. when it's
+///   hand-written to add context. The field is still wrapped, the hand-written impl converts the
+///   error with `.into()`, which also builds in release mode where it isn't. The types are written
+///   as in the fields, e.g. `no_from(std::io::Error)` for `#[from] std::io::Error`.
+/// * `release = "on"` (or `"off"`): keep the type enhanced in release builds despite the
+///   `release_off` feature, e.g. for critical errors, or leave it as it is in release builds
+///   without `release_off`. Debug builds always enhance it. With `release_passthrough`,
+///   `backerror::LocatedError` itself doesn't capture anything in release builds either way.
+/// * `result_alias = "..."`: also define a `Result` alias with the error type, e.g.
+///   `pub type AppResult<T> = Result<T, AppError>` for `result_alias = "AppResult"`, with the
+///   visibility of the error type.
+/// * `url = "..."`: attach a documentation or runbook URL to the errors converted into the type,
+///   unless the variant has its own, see `backerror::LocatedError::with_url`.
+/// * `wrap = "..."`: wrap the `#[from]` fields into another type than `backerror::LocatedError`,
+///   e.g. `wrap = "my_crate::TracedError"` for `#[from] TracedError<T>` fields. The type takes the
+///   error as its only type parameter and implements `From<T>` with `#[track_caller]`. `help`,
+///   `url` and `backtrace` only apply to `backerror::LocatedError`.
+/// * `wrap_source`: also wrap the `#[source]` fields of an enum into `backerror::LocatedError`.
+///   Without a `From` impl to capture the location, a variant with a `#[source]` field gets a
+///   `#[track_caller]` constructor instead, `new_{variant}` taking its fields in order, e.g.
+///   `MyError::new_parse(err, line)` for `Parse { #[source] source: ParseIntError, line: usize }`.
+///   A field named `source` is the source of a variant without `#[source]` attribute, as for
+///   `thiserror`. A variant with context fields next to a `#[from]` field, which `thiserror`
+///   doesn't allow, gets such a constructor instead of a `From` impl without `wrap_source`.
+///
+/// Variant arguments, `#[backerror(...)]` on a variant with a `#[from]` field:
+/// * `help = "..."`: attach a help text to the errors converted into the variant,
+///   see `backerror::LocatedError::with_help`.
+/// * `url = "..."`: attach a documentation or runbook URL, overriding the one of the enum.
+/// * `backtrace = false` (or `true`): likewise, for the backtrace.
+/// * `skip_from`: no `From` impl for the variant, e.g. when another variant already converts the
+///   same type. Its `#[from]` fields become `#[source]` fields, with a `new_{variant}`
+///   constructor like `wrap_source`.
+/// * `skip`: leave the `#[from]` fields of the variant as they are, e.g. when they're already
+///   located. `#[backerror(skip)]` on a field leaves just that field.
+///
+/// Environment variables, set when building:
+/// * `BACKERROR_DISABLE=1` and `BACKERROR_FORCE=1` leave every type as it is or enhance every
+///   type, whatever the build, the features and `release`, e.g. to compare the overhead in a
+///   profiling build. `BACKERROR_DISABLE` wins if both are set.
 #[proc_macro_attribute]
-pub fn backerror(_args: TokenStream, input: TokenStream) -> TokenStream {
+pub fn backerror(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut backerror_args = Args::default();
+    let args_parser = syn::meta::parser(|meta| backerror_args.parse(meta));
+    let args2 = proc_macro2::TokenStream::from(args.clone());
+    parse_macro_input!(args with args_parser);
     let input2 = input.clone();
     let item = parse_macro_input!(input2 as Item);
+    // a module passes `debug_expansion` on to its types, which print their own expansion
+    let ident = match &item {
+        Item::Enum(item_enum) => Some(item_enum.ident.clone()),
+        Item::Struct(item_struct) => Some(item_struct.ident.clone()),
+        _ => None,
+    };
+    let ret = backerror_item(item, &backerror_args, args2, input);
+    if let (true, Some(ident)) = (backerror_args.debug_expansion, ident) {
+        eprintln!("{}", debug_expansion(&ident, ret.clone().into()));
+    }
+    ret
+}
 
-    match item {
-        Item::Enum(item_enum) => backerror_enum(item_enum, input),
-        Item::Struct(item_struct) => backerror_struct(item_struct, input),
-        _ => input,
+/// the expansion of `#[backerror]` on `item`
+fn backerror_item(
+    item: Item,
+    backerror_args: &Args,
+    args2: proc_macro2::TokenStream,
+    input: TokenStream,
+) -> TokenStream {
+    if !backerror_args.force && check_snafu_item(&item) {
+        let alias = result_alias(&item, backerror_args);
+        let name = error_name_impl(&item, backerror_args);
+        let ret = backerror_snafu(item, backerror_args);
+        return quote!(#ret #alias #name).into();
     }
+    if !backerror_args.enabled() {
+        return backerror_release(backerror_args, args2, input);
+    }
+
+    let context = context_impl(&item, backerror_args);
+    let alias = result_alias(&item, backerror_args);
+    let name = error_name_impl(&item, backerror_args);
+    let mut ret = match item {
+        Item::Enum(item_enum) => backerror_enum(item_enum, input, backerror_args),
+        Item::Struct(item_struct) => backerror_struct(item_struct, input, backerror_args),
+        Item::Mod(item_mod) => backerror_mod(item_mod, args2, backerror_args).into(),
+        item => {
+            // keep the item, so that its uses don't add more errors
+            let message = format!(
+                "`#[backerror]` must be applied to the definition of an error enum or struct, not to {}",
+                item_kind(&item)
+            );
+            let mut ret: TokenStream = syn::Error::new_spanned(&item, message)
+                .to_compile_error()
+                .into();
+            ret.extend(input);
+            ret
+        }
+    };
+    ret.extend(context.map(TokenStream::from));
+    ret.extend(alias.map(TokenStream::from));
+    ret.extend(name.map(TokenStream::from));
+    ret
 }
 
-/// enum error
-///
+/// the expansion of `#[backerror(debug_expansion)]` on `ident`, one item per line
+fn debug_expansion(ident: &Ident, expansion: proc_macro2::TokenStream) -> String {
+    let items = match syn::parse2::<syn::File>(expansion.clone()) {
+        Ok(file) => file
+            .items
+            .iter()
+            .map(|item| item.to_token_stream().to_string())
+            .collect::<Vec<_>>()
+            .join("\n\n"),
+        Err(_) => expansion.to_string(),
+    };
+    format!("`#[backerror]` expansion of `{ident}`:\n{items}")
+}
+
+/// Derive a parallel `Located{Name}` error type, with the `#[from]` fields wrapped into
+/// `backerror::LocatedError` like `#[backerror]` does, instead of rewriting the type itself, e.g.
+/// for tooling which expands attribute macros in another order.
 /// ```ignore
-/// #[backerror]
-/// #[derive(Debug, Error)]
-/// pub enum MyError {
-///     #[error("{0}")]
-///     IoError(#[from] std::io::Error),
+/// #[derive(Debug, Error, BackError)]
+/// pub enum AppError {
+///     #[error("io: {0}")]
+///     Io(#[from] std::io::Error),
+///     #[error("not found")]
+///     NotFound,
+/// }
+///
+/// fn load(path: &str) -> Result<String, LocatedAppError> {
+///     Ok(std::fs::read_to_string(path)?) // `LocatedAppError::Io` located here
 /// }
 /// ```
-fn backerror_enum(mut item_enum: ItemEnum, input: TokenStream) -> TokenStream {
-    // check whether the enum derives thiserror::Error
-    if !check_derive_thiserror(&item_enum.attrs) {
-        return input;
+///
+/// `LocatedAppError` has the variants, fields and `#[error(...)]` attributes of `AppError`, it
+/// derives `Debug` and `Error`, and it converts from the `#[from]` types, like a `#[backerror]`
+/// type with the default arguments. It also converts from an `AppError`, whose `#[from]` fields
+/// are located at the conversion.
+///
+/// A derive doesn't see the other derives of `AppError`, `LocatedAppError` derives
+/// `::thiserror::Error`, and `::displaydoc::Display` if `AppError` has docs but no
+/// `#[error(...)]` attributes, both crates must be dependencies of the crate using it.
+///
+/// The other attributes of `AppError`, e.g. of other derives, aren't copied. In release mode with
+/// `release_off`, or with `BACKERROR_DISABLE`, `LocatedAppError` is a plain copy, like
+/// `#[backerror]` types.
+#[proc_macro_derive(BackError)]
+pub fn derive_backerror(input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as Item);
+    let located = match located_copy(&item) {
+        Ok(located) => located,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let copy = located.to_token_stream();
+    let mut ret = match located.clone() {
+        _ if !Args::default().enabled() => copy.into(),
+        Item::Enum(item_enum) => backerror_enum(item_enum, copy.into(), &Args::default()),
+        Item::Struct(item_struct) => backerror_struct(item_struct, copy.into(), &Args::default()),
+        _ => copy.into(),
+    };
+    // the fields of the copy after `backerror_enum`, followed by its impls, to tell which ones are
+    // wrapped
+    let first_item = |input: syn::parse::ParseStream| -> syn::Result<Item> {
+        let item: Item = input.parse()?;
+        input.parse::<proc_macro2::TokenStream>()?;
+        Ok(item)
+    };
+    let located = syn::parse::Parser::parse(first_item, ret.clone()).unwrap_or(located);
+    ret.extend(context_impl(&located, &Args::default()).map(TokenStream::from));
+    ret.extend(TokenStream::from(located_from_impl(&item, &located)));
+    ret
+}
+
+/// `item` named `Located{Name}`, with only the attributes of `thiserror`, for `#[derive(BackError)]`
+fn located_copy(item: &Item) -> Result<Item, syn::Error> {
+    let (attrs, ident) = match item {
+        Item::Enum(ItemEnum { attrs, ident, .. })
+        | Item::Struct(ItemStruct { attrs, ident, .. }) => (attrs, ident),
+        _ => {
+            let message = "`#[derive(BackError)]` requires an error enum or struct";
+            return Err(syn::Error::new_spanned(item, message));
+        }
+    };
+    // a derive doesn't see the other derives of the item, only their helper attributes: the
+    // `#[error(...)]` attributes of `thiserror`, or else the docs of a displaydoc type
+    let (error, display): (Path, Option<Path>) = if has_error_attr(item) {
+        (parse_quote!(::thiserror::Error), None)
+    } else if attrs.iter().any(|attr| attr.path().is_ident("doc")) || has_variant_docs(item) {
+        (
+            parse_quote!(::thiserror::Error),
+            Some(parse_quote!(::displaydoc::Display)),
+        )
+    } else {
+        let message = format!(
+            "`#[derive(BackError)]` requires a `thiserror::Error` type on `{ident}`, with `#[error(...)]` attributes or displaydoc docs"
+        );
+        return Err(syn::Error::new_spanned(ident, message));
+    };
+
+    let located_ident = format_ident!("Located{ident}");
+    // the docs of a displaydoc type are its `Display`, they're kept instead
+    let mut located_attrs: Vec<syn::Attribute> = match &display {
+        Some(display) => vec![parse_quote!(#[derive(Debug, #display, #error)])],
+        None => {
+            let doc = format!(
+                " [`{ident}`], with its `#[from]` errors located, see `backerror::LocatedError`"
+            );
+            vec![
+                parse_quote!(#[doc = #doc]),
+                parse_quote!(#[derive(Debug, #error)]),
+            ]
+        }
+    };
+    located_attrs.extend(
+        attrs
+            .iter()
+            .filter(|attr| {
+                (display.is_some() || !attr.path().is_ident("doc")) && thiserror_attr(attr)
+            })
+            .cloned(),
+    );
+    let retain_fields = |fields: &mut syn::Fields| {
+        for field in fields.iter_mut() {
+            field.attrs.retain(thiserror_attr);
+        }
+    };
+    Ok(match item.clone() {
+        Item::Enum(mut copy) => {
+            for variant in copy.variants.iter_mut() {
+                variant.attrs.retain(thiserror_attr);
+                retain_fields(&mut variant.fields);
+            }
+            copy.ident = located_ident;
+            copy.attrs = located_attrs;
+            Item::Enum(copy)
+        }
+        Item::Struct(mut copy) => {
+            retain_fields(&mut copy.fields);
+            copy.ident = located_ident;
+            copy.attrs = located_attrs;
+            Item::Struct(copy)
+        }
+        item => item,
+    })
+}
+
+/// whether `attr` is kept on the copy of `#[derive(BackError)]`, which only derives `Debug`,
+/// `Error` and a displaydoc `Display`: the attributes of `thiserror` and `displaydoc`, docs, `cfg`
+/// and lints
+fn thiserror_attr(attr: &syn::Attribute) -> bool {
+    const KEPT: [&str; 14] = [
+        "error",
+        "from",
+        "source",
+        "backtrace",
+        "displaydoc",
+        "ignore_extra_doc_attributes",
+        "prefix_enum_doc_attributes",
+        "doc",
+        "cfg",
+        "non_exhaustive",
+        "allow",
+        "expect",
+        "warn",
+        "deny",
+    ];
+    KEPT.iter().any(|name| attr.path().is_ident(name))
+}
+
+/// whether `item` or one of its variants has an `#[error(...)]` attribute of `thiserror`
+fn has_error_attr(item: &Item) -> bool {
+    let is_error = |attr: &syn::Attribute| attr.path().is_ident("error");
+    match item {
+        Item::Enum(item_enum) => {
+            item_enum.attrs.iter().any(is_error)
+                || item_enum
+                    .variants
+                    .iter()
+                    .any(|variant| variant.attrs.iter().any(is_error))
+        }
+        Item::Struct(item_struct) => item_struct.attrs.iter().any(is_error),
+        _ => false,
     }
+}
 
-    let mut error_types = Vec::new();
+/// whether a variant of `item` has docs, the `Display` of a displaydoc enum
+fn has_variant_docs(item: &Item) -> bool {
+    match item {
+        Item::Enum(item_enum) => item_enum
+            .variants
+            .iter()
+            .any(|variant| variant.attrs.iter().any(|attr| attr.path().is_ident("doc"))),
+        _ => false,
+    }
+}
 
-    for variant in item_enum.variants.iter_mut() {
-        let fields = &mut variant.fields;
-        enhance_fields(fields, &mut error_types);
+fn find_derive(attrs: &[syn::Attribute], is_derive: impl Fn(&Path) -> bool) -> Option<Path> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .find(|path| is_derive(path))
+}
+
+/// `From<Name> for Located{Name}` of `#[derive(BackError)]`, moving each field, and locating the
+/// ones which are wrapped in `located`
+fn located_from_impl(item: &Item, located: &Item) -> proc_macro2::TokenStream {
+    let (ident, generics, located_ident, arms) = match (item, located) {
+        (Item::Enum(item_enum), Item::Enum(located_enum)) => {
+            let ident = &item_enum.ident;
+            let located_ident = &located_enum.ident;
+            let arms: Vec<_> = item_enum
+                .variants
+                .iter()
+                .zip(&located_enum.variants)
+                .map(|(variant, located_variant)| {
+                    let variant_ident = &variant.ident;
+                    let cfgs: Vec<_> = cfg_attrs(&variant.attrs).collect();
+                    let arm = located_arm(
+                        &variant.fields,
+                        &located_variant.fields,
+                        quote!(#ident::#variant_ident),
+                        quote!(#located_ident::#variant_ident),
+                    );
+                    quote!(#(#cfgs)* #arm,)
+                })
+                .collect();
+            (ident, &item_enum.generics, located_ident, arms)
+        }
+        (Item::Struct(item_struct), Item::Struct(located_struct)) => {
+            let ident = &item_struct.ident;
+            let located_ident = &located_struct.ident;
+            let arm = located_arm(
+                &item_struct.fields,
+                &located_struct.fields,
+                quote!(#ident),
+                quote!(#located_ident),
+            );
+            (
+                ident,
+                &item_struct.generics,
+                located_ident,
+                vec![quote!(#arm,)],
+            )
+        }
+        _ => return proc_macro2::TokenStream::new(),
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    quote! {
+        impl #impl_generics From<#ident #ty_generics> for #located_ident #ty_generics #where_clause {
+            #[track_caller]
+            fn from(error: #ident #ty_generics) -> Self {
+                match error {
+                    #(#arms)*
+                }
+            }
+        }
     }
+}
 
-    if let Ok(impls) = generate_from_impl(&item_enum.ident, &error_types) {
-        let ret = quote! {
-            #item_enum
-            #impls
+/// match arm of `located_from_impl`, from `path` with `fields` to `located_path` with
+/// `located_fields`, locating the fields whose type differs
+fn located_arm(
+    fields: &syn::Fields,
+    located_fields: &syn::Fields,
+    path: proc_macro2::TokenStream,
+    located_path: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mut names = Vec::new();
+    let mut values = Vec::new();
+    for (index, (field, located_field)) in fields.iter().zip(located_fields).enumerate() {
+        let name = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| format_ident!("field{index}"));
+        let value = if same_type(&field.ty, &located_field.ty) {
+            quote!(#name)
+        } else {
+            let located = located_error(
+                &field.ty,
+                quote!(#name),
+                &VariantArgs::default(),
+                &Args::default(),
+            );
+            match &field.ident {
+                Some(_) => quote!(#name: #located),
+                None => located,
+            }
         };
+        names.push(name);
+        values.push(value);
+    }
+    match fields {
+        syn::Fields::Named(_) => quote!(#path { #(#names),* } => #located_path { #(#values),* }),
+        syn::Fields::Unnamed(_) => quote!(#path(#(#names),*) => #located_path(#(#values),*)),
+        syn::Fields::Unit => quote!(#path => #located_path),
+    }
+}
 
-        ret.into()
-    } else {
-        input
+/// Print the error returned by `main` like `backerror::Report` instead of its `Debug` output, and
+/// exit with a failure code:
+/// ```ignore
+/// #[backerror::main]
+/// fn main() -> Result<(), AppError> {
+///     let config = load_config()?;
+///     ...
+/// }
+/// ```
+/// The report starts with `Error: `, in color if the standard error is a terminal and `NO_COLOR`
+/// isn't set, and lists the chain of the error with the locations of its located layers, and the
+/// backtrace of the innermost one. The error is any `Error + 'static`, e.g. a `#[backerror]`
+/// type, or a `backerror::Error`.
+///
+/// The function runs as it is in a `main` returning a `std::process::ExitCode`, so that it keeps
+/// its other attributes, e.g. `#[tokio::main]` below `#[backerror::main]`.
+///
+/// `crate = "..."` is the path of the `backerror` crate in the generated code, as for
+/// `#[backerror]`, e.g. `#[my_facade::main(crate = "my_facade::backerror")]`.
+#[proc_macro_attribute]
+pub fn main(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut main_args = Args::default();
+    let args_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("crate") {
+            main_args.parse(meta)
+        } else {
+            Err(meta.error("`#[backerror::main]` takes no arguments, or `crate = \"...\"`"))
+        }
+    });
+    parse_macro_input!(args with args_parser);
+    let item_fn = parse_macro_input!(input as ItemFn);
+    match main_fn(item_fn, &main_args.krate()) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// nest `item_fn` into a `main` which returns the exit code of its result, see `main`
+fn main_fn(mut item_fn: ItemFn, krate: &Path) -> Result<proc_macro2::TokenStream, syn::Error> {
+    if !item_fn.sig.inputs.is_empty() || matches!(item_fn.sig.output, ReturnType::Default) {
+        let message =
+            "`#[backerror::main]` function must take no arguments and return `Result<_, E>`";
+        return Err(syn::Error::new_spanned(&item_fn.sig, message));
     }
+    let vis = std::mem::replace(&mut item_fn.vis, syn::Visibility::Inherited);
+    let ident = std::mem::replace(&mut item_fn.sig.ident, format_ident!("__backerror_main"));
+    Ok(quote! {
+        #vis fn #ident() -> ::std::process::ExitCode {
+            #item_fn
+            #krate::__main_result(__backerror_main())
+        }
+    })
 }
 
-/// transparent struct
+/// Record the name of the annotated function in the `backerror::LocatedError`s it returns, see
+/// `backerror::LocatedError::function`.
+/// ```ignore
+/// #[backerror::locate]
+/// fn load(path: &str) -> Result<Config, LocatedError<std::io::Error>> {
+///     let text = std::fs::read_to_string(path)?; // `function()` is `Some("app::config::load")`
+///     ...
+/// }
+/// ```
+/// The function must return `Result<_, backerror::LocatedError<_>>`, the body runs in a closure
+/// (or an `async` block), so `return` and `?` behave as before.
 ///
+/// `#[locate(each)]` locates the error of each `?` of the function instead, for a function which
+/// returns any other error type, e.g. `Box<dyn Error + Send + Sync>`:
 /// ```ignore
-/// #[backerror]
-/// #[derive(Debug, Error)]
-/// #[error(transparent)]
-/// pub struct MyError(#[from] std::io::Error);
+/// #[backerror::locate(each)]
+/// fn load(path: &str) -> Result<Config, Box<dyn Error + Send + Sync>> {
+///     let text = std::fs::read_to_string(path)?; // a `LocatedError<std::io::Error>`
+///     ...
+/// }
 /// ```
-fn backerror_struct(mut item_struct: ItemStruct, input: TokenStream) -> TokenStream {
-    // check whether the struct derives thiserror::Error
-    if !check_derive_thiserror(&item_struct.attrs) || !check_transparent_struct(&item_struct.attrs)
-    {
+/// The `?` of a `Result` whose error implements `Error` converts a `LocatedError` of that error,
+/// located at the `?`, into the error of the function. Any other `?`, e.g. of an `Option`, is left
+/// as it is, as well as the ones in closures, `async` blocks and macro invocations.
+///
+/// `crate = "..."` is the path of the `backerror` crate in the generated code, as for
+/// `#[backerror]`, e.g. `#[locate(each, crate = "my_facade::backerror")]`.
+///
+/// The function is left as it is where `#[backerror]` leaves the types as they are: in release
+/// builds with the `release_off` feature, and with `BACKERROR_DISABLE`, unless `BACKERROR_FORCE`.
+#[proc_macro_attribute]
+pub fn locate(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut each = false;
+    let mut locate_args = Args::default();
+    let args_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("each") {
+            each = true;
+            Ok(())
+        } else if meta.path.is_ident("crate") {
+            locate_args.parse(meta)
+        } else {
+            Err(meta.error("`#[locate]` takes no arguments, `each` or `crate = \"...\"`"))
+        }
+    });
+    parse_macro_input!(args with args_parser);
+    if !locate_args.enabled() {
         return input;
     }
+    let krate = locate_args.krate();
+    let item_fn = parse_macro_input!(input as ItemFn);
+    if each {
+        return locate_each_fn(item_fn, krate).into();
+    }
+    match locate_fn(item_fn, &krate) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
 
-    let mut error_types = Vec::new();
+/// wrap the body of `item_fn`, recording its name in the returned error
+fn locate_fn(mut item_fn: ItemFn, krate: &Path) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let ReturnType::Type(_, output) = &item_fn.sig.output else {
+        let message = "`#[locate]` function must return `Result<_, backerror::LocatedError<_>>`";
+        return Err(syn::Error::new_spanned(&item_fn.sig, message));
+    };
+    // the output type is given for `?` in the body, unless it's an `impl Trait`, which can't be
+    // named there
+    let output = (!contains_impl(output.to_token_stream())).then_some(output);
+
+    let ident = &item_fn.sig.ident;
+    let block = &item_fn.block;
+    let result = match (item_fn.sig.asyncness.is_some(), output) {
+        (true, Some(output)) => {
+            quote!(#krate::__expect_output::<#output, _>(async #block).await)
+        }
+        (true, None) => quote!(async #block.await),
+        (false, Some(output)) => quote!((|| -> #output #block)()),
+        (false, None) => quote!((|| #block)()),
+    };
+    let body = quote!({
+        let result = #result;
+        result.map_err(|error| {
+            error.with_function(::core::concat!(
+                ::core::module_path!(),
+                "::",
+                ::core::stringify!(#ident)
+            ))
+        })
+    });
+    item_fn.block = Box::new(syn::parse2(body)?);
+    Ok(item_fn.into_token_stream())
+}
+
+/// rewrite each `?` of the body of `item_fn`, see `LocateEach`
+fn locate_each_fn(mut item_fn: ItemFn, krate: Path) -> proc_macro2::TokenStream {
+    let ident = &item_fn.sig.ident;
+    let function = quote!(::core::concat!(
+        ::core::module_path!(),
+        "::",
+        ::core::stringify!(#ident)
+    ));
+    LocateEach { function, krate }.visit_block_mut(&mut item_fn.block);
+    item_fn.into_token_stream()
+}
+
+/// Rewrite each `expr?` of a function, locating the error at the `?`, see `backerror::__LocateKind`
+/// for the method picked by the type of `expr`.
+///
+/// The `?` of a closure, an `async` block, or a nested item returns from it, not from the
+/// function, so they are left as they are.
+struct LocateEach {
+    /// the name of the function, recorded in the errors
+    function: proc_macro2::TokenStream,
+    /// the path of the `backerror` crate
+    krate: Path,
+}
 
-    let fields = &mut item_struct.fields;
-    enhance_fields(fields, &mut error_types);
+impl VisitMut for LocateEach {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if matches!(expr, Expr::Closure(_) | Expr::Async(_)) {
+            return;
+        }
+        visit_mut::visit_expr_mut(self, expr);
 
-    if let Ok(impls) = generate_from_impl(&item_struct.ident, &error_types) {
-        let ret = quote! {
-            #item_struct
-            #impls
+        let Expr::Try(expr_try) = expr else {
+            return;
+        };
+        let inner = &expr_try.expr;
+        let function = &self.function;
+        let krate = &self.krate;
+        // the location of the `locate` call is the one of the `?`
+        let span = expr_try.question_token.span;
+        let result = Ident::new("result", span);
+        let locate = quote_spanned! {span=>
+            (&#result).__locate_kind().locate(#result, #function)
         };
+        let located: Expr = parse_quote! {
+            match #inner {
+                #result => {
+                    use #krate::{__LocateKind as _, __PassKind as _};
+                    #locate
+                }
+            }
+        };
+        *expr_try.expr = located;
+    }
 
-        ret.into()
-    } else {
-        input
+    fn visit_item_mut(&mut self, _item: &mut Item) {}
+}
+
+/// whether `tokens` contain an `impl` keyword, e.g. `Result<impl Display, E>`
+fn contains_impl(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => contains_impl(group.stream()),
+        _ => false,
+    })
+}
+
+/// `context` method of an error enum or struct, see `backerror::Context`.
+///
+/// The method is generated by `backerror::__context_impl!`, which is empty without the `std`
+/// feature of `backerror`.
+fn context_impl(item: &Item, args: &Args) -> Option<proc_macro2::TokenStream> {
+    let (attrs, ident, generics) = match item {
+        Item::Enum(item) => (&item.attrs, &item.ident, &item.generics),
+        Item::Struct(item) => (&item.attrs, &item.ident, &item.generics),
+        _ => return None,
+    };
+    if !args.derives_thiserror(attrs) {
+        return None;
     }
+    let krate = args.krate();
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Some(quote! {
+        #krate::__context_impl!(impl #impl_generics #ident #ty_generics #where_clause);
+    })
 }
 
-fn generate_from_impl(
-    ident: &Ident,
-    error_types: &Vec<String>,
-) -> Result<proc_macro2::TokenStream, syn::Error> {
-    if error_types.is_empty() {
-        return Err(syn::Error::new(
-            proc_macro2::Span::call_site(),
-            "no attribute found",
-        ));
+/// `#[backerror]` on an inline module, which adds `#[backerror(...)]` with the same arguments to
+/// each `#[derive(Error)]` enum and struct inside, which expands it in debug and release mode alike
+fn backerror_mod(
+    mut item_mod: ItemMod,
+    args_tokens: proc_macro2::TokenStream,
+    args: &Args,
+) -> proc_macro2::TokenStream {
+    if let Some(alias) = &args.result_alias {
+        let message = "`result_alias` applies to a single error type, not to a module";
+        let error = syn::Error::new_spanned(alias, message).to_compile_error();
+        return quote!(#error #item_mod);
+    }
+    if let Some(name) = &args.name {
+        let message = "`name` applies to a single error type, not to a module";
+        let error = syn::Error::new_spanned(name, message).to_compile_error();
+        return quote!(#error #item_mod);
+    }
+    let Some((_, items)) = &mut item_mod.content else {
+        let message =
+            "`#[backerror]` must be applied to an inline module, e.g. `mod errors { ... }`";
+        let error = syn::Error::new_spanned(&item_mod, message).to_compile_error();
+        return quote!(#error #item_mod);
+    };
+    let krate = args.krate();
+    let attr: syn::Attribute = if args_tokens.is_empty() {
+        parse_quote!(#[#krate::backerror])
+    } else {
+        parse_quote!(#[#krate::backerror(#args_tokens)])
+    };
+    let forced: syn::Attribute = if args_tokens.is_empty() {
+        parse_quote!(#[#krate::backerror(force)])
+    } else {
+        parse_quote!(#[#krate::backerror(#args_tokens, force)])
+    };
+    if annotate_errors(items, &attr, &forced) == 0 {
+        let message = "no `#[derive(Error)]` enum or struct in this module";
+        let error = syn::Error::new_spanned(&item_mod.ident, message).to_compile_error();
+        return quote!(#error #item_mod);
     }
+    item_mod.into_token_stream()
+}
 
-    let mut impls = Vec::new();
-    for e in error_types {
-        let from_ty: Path = syn::parse_str(e)?;
-        let block = quote! {
-            impl From<#from_ty> for #ident {
-                #[track_caller]
-                fn from(e: #from_ty) -> Self {
-                    #ident::from(backerror::LocatedError::from(e))
-                }
+/// Add `attr` to the `#[derive(Error)]` enums and structs of `items`, and of their inline modules,
+/// returning how many. Items and modules with their own `#[backerror]` keep it.
+///
+/// The ones deriving an alias of `thiserror::Error` imported in the same module, e.g.
+/// `#[derive(TError)]` after `use thiserror::Error as TError;`, get `forced`, with `force`.
+fn annotate_errors(items: &mut [Item], attr: &syn::Attribute, forced: &syn::Attribute) -> usize {
+    let aliases = thiserror_aliases(items);
+    let mut count = 0;
+    for item in items.iter_mut() {
+        let attrs = match item {
+            Item::Enum(item) => &mut item.attrs,
+            Item::Struct(item) => &mut item.attrs,
+            Item::Mod(ItemMod {
+                attrs,
+                content: Some((_, items)),
+                ..
+            }) if !has_backerror_attr(attrs) => {
+                count += annotate_errors(items, attr, forced);
+                continue;
             }
+            _ => continue,
         };
-        impls.push(block);
+        if has_backerror_attr(attrs) {
+            continue;
+        }
+        let attr = if check_derive_thiserror(attrs) || check_derive_snafu(attrs) {
+            attr
+        } else if find_derive(attrs, |path| {
+            aliases.iter().any(|alias| path.is_ident(alias))
+        })
+        .is_some()
+        {
+            forced
+        } else {
+            continue;
+        };
+        // above `#[derive(Error)]`
+        attrs.insert(0, attr.clone());
+        count += 1;
     }
+    count
+}
 
-    Ok(quote! {
-        #(#impls)*
+/// whether `attrs` has `#[backerror]` or `#[backerror::backerror]`, with arguments or not
+fn has_backerror_attr(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "backerror")
     })
 }
 
-/// enhance fiels from `#[from] T` to `#[from] backerror::LocatedError<T>`
-fn enhance_fields(fields: &mut syn::Fields, errors: &mut Vec<String>) {
-    match fields {
-        syn::Fields::Unnamed(fs) => {
-            for field in fs.unnamed.iter_mut() {
-                if check_attr_from(&field.attrs) {
-                    let orig_ty = field.ty.clone().into_token_stream().to_string();
-                    let ty = format!("backerror::LocatedError<{}>", orig_ty);
-                    if let Ok(new_type) = syn::parse_str(&ty) {
-                        errors.push(orig_ty);
-                        field.ty = new_type;
-                    } else {
-                        println!("failed to parse {}", ty);
-                    }
-                } else {
-                    // println!("transparent struct field without #[from]");
-                }
-            }
+/// `type {result_alias}<T> = Result<T, Error>` of the error type, with its generic parameters after
+/// `T`, and its lifetimes before
+fn result_alias(item: &Item, args: &Args) -> Option<proc_macro2::TokenStream> {
+    let alias = args.result_alias.as_ref()?;
+    let (vis, ident, generics) = match item {
+        Item::Enum(item) => (&item.vis, &item.ident, &item.generics),
+        Item::Struct(item) => (&item.vis, &item.ident, &item.generics),
+        _ => return None,
+    };
+    // `T`, unless the error type has its own `T`
+    let ok = if generics.type_params().any(|param| param.ident == "T") {
+        format_ident!("__T")
+    } else {
+        format_ident!("T")
+    };
+    let lifetimes = generics.lifetimes().map(|param| &param.lifetime);
+    let params = generics.params.iter().filter_map(|param| match param {
+        syn::GenericParam::Type(param) => Some(param.ident.to_token_stream()),
+        syn::GenericParam::Const(param) => {
+            let (ident, ty) = (&param.ident, &param.ty);
+            Some(quote!(const #ident: #ty))
         }
-        syn::Fields::Unit | syn::Fields::Named(_) => {
-            // do nothing
+        syn::GenericParam::Lifetime(_) => None,
+    });
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let doc = format!(" `Result` with [`{ident}`] as the error type");
+    Some(quote! {
+        #[doc = #doc]
+        #vis type #alias<#(#lifetimes,)* #ok, #(#params),*> =
+            ::core::result::Result<#ok, #ident #ty_generics>;
+    })
+}
+
+/// `impl ErrorName` of the error type with `name = "..."`
+fn error_name_impl(item: &Item, args: &Args) -> Option<proc_macro2::TokenStream> {
+    let name = args.name.as_ref()?;
+    let (ident, generics) = match item {
+        Item::Enum(item) => (&item.ident, &item.generics),
+        Item::Struct(item) => (&item.ident, &item.generics),
+        _ => return None,
+    };
+    let krate = args.krate();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Some(quote! {
+        impl #impl_generics #krate::ErrorName for #ident #ty_generics #where_clause {
+            fn error_name() -> &'static str {
+                #name
+            }
         }
+    })
+}
+
+/// item kind for diagnostics, e.g. `a type alias`
+fn item_kind(item: &Item) -> &'static str {
+    match item {
+        Item::Const(_) => "a constant",
+        Item::Enum(_) => "an enum",
+        Item::ExternCrate(_) => "an extern crate",
+        Item::Fn(_) => "a function",
+        Item::ForeignMod(_) => "an extern block",
+        Item::Impl(_) => "an impl block",
+        Item::Macro(_) => "a macro invocation",
+        Item::Mod(_) => "a module",
+        Item::Static(_) => "a static",
+        Item::Struct(_) => "a struct",
+        Item::Trait(_) => "a trait",
+        Item::TraitAlias(_) => "a trait alias",
+        Item::Type(_) => "a type alias",
+        Item::Union(_) => "a union",
+        Item::Use(_) => "a use declaration",
+        _ => "this item",
     }
 }
 
-/// check `#[derive(Error)]`
-fn check_derive_thiserror(attrs: &Vec<syn::Attribute>) -> bool {
-    for attr in attrs {
-        if attr.path().is_ident("derive") {
-            if let Ok(nested) =
-                attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
-            {
-                for meta in nested {
-                    match meta {
-                        Meta::Path(path) => {
-                            // #[derive(Error)]
-                            if path.is_ident("Error") {
-                                return true;
-                            }
-                            // #[derive(thiserror::Error)]
-                            let path = path.into_token_stream().to_string();
-                            if path.contains("thiserror") && path.contains("Error") {
-                                return true;
-                            }
-                        }
+/// enum error
+///
+/// ```ignore
+/// #[backerror]
+/// #[derive(Debug, Error)]
+/// pub enum MyError {
+///     #[error("{0}")]
+///     IoError(#[from] std::io::Error),
+/// }
+/// ```
+fn backerror_enum(mut item_enum: ItemEnum, input: TokenStream, args: &Args) -> TokenStream {
+    // check whether the enum derives thiserror::Error
+    if !args.derives_thiserror(&item_enum.attrs) {
+        return missing_derive(&item_enum.ident, input);
+    }
+    if args.auto_from {
+        auto_from(&mut item_enum);
+    }
 
-                        _ => {}
-                    }
-                }
-            }
+    let mut error_types = Vec::new();
+    let mut constructors = Vec::new();
+    let mut invalid = args.conflicts_with_wrap();
+    let mut seen = Vec::new();
+    let field_types = single_field_types(&item_enum);
+
+    for variant in item_enum.variants.iter_mut() {
+        let mut variant_args = VariantArgs::take(&mut variant.attrs).unwrap_or_else(|e| {
+            combine_error(&mut invalid, e);
+            VariantArgs::default()
+        });
+        let from = has_from(&variant.fields);
+        // `thiserror` only allows a backtrace next to a `#[from]` field, with context fields the
+        // variant gets a located constructor instead, like with `skip_from`
+        variant_args.skip_from |= from_with_context(&variant.fields);
+        dedup_from(variant, variant_args.skip_from, &mut seen, &mut invalid);
+
+        let mut variant_types = Vec::new();
+        if variant_args.skip {
+            // the fields are left as they are, but their arguments are still checked
+            check_field_args(&variant.fields, &mut invalid);
+        } else if let Err(e) = enhance_fields(&mut variant.fields, &mut variant_types, args) {
+            combine_error(&mut invalid, e);
         }
+        let constructor = ((args.wrap_source || variant_args.skip_from) && !variant_args.skip)
+            .then(|| {
+                let variant_args = variant_args.or_defaults(args);
+                source_constructor(variant, &item_enum.vis, Some(&variant_args), args)
+            })
+            .flatten();
+        if let (Some(arg), true) = (
+            variant_args.first(),
+            variant_types.is_empty() && constructor.is_none(),
+        ) {
+            let message = if variant_args.skip {
+                "`help` and `url` don't apply to a skipped variant"
+            } else {
+                "`help` and `url` require a `#[from]` field, or a `#[source]` field with `wrap_source`"
+            };
+            combine_error(&mut invalid, syn::Error::new_spanned(arg, message));
+        }
+        if let Some(e) = variant_args.conflicts_with_wrap(args) {
+            combine_error(&mut invalid, e);
+        }
+        FieldArgs::strip(&mut variant.fields);
+        if args.located_variants && !from && !variant_args.skip && constructor.is_none() {
+            constructors.extend(located_variant(variant, &item_enum.vis, &mut invalid));
+        }
+        constructors.extend(constructor);
+        error_types.extend(
+            variant_types
+                .into_iter()
+                .map(|ty| (ty, variant_args.clone())),
+        );
     }
-    return false;
+
+    check_no_from(&error_types, args, &mut invalid);
+    if args.accessors {
+        constructors.extend(accessors(&item_enum, &field_types));
+    }
+    let impls = generate_from_impl(&item_enum.ident, &item_enum.generics, &error_types, args);
+    let constructors = constructors_impl(&item_enum.ident, &item_enum.generics, constructors);
+
+    let invalid = invalid.map(|e| e.to_compile_error());
+    let ret = quote! {
+        #item_enum
+        #impls
+        #constructors
+        #invalid
+    };
+
+    ret.into()
 }
 
-/// check `#[error(transparent)]`
-fn check_transparent_struct(attrs: &Vec<syn::Attribute>) -> bool {
-    for attr in attrs {
-        if attr.path().is_ident("error") {
-            if let Ok(nested) =
-                attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
-            {
-                for meta in nested {
-                    match meta {
-                        Meta::Path(path) => {
-                            // #[error(transparent)]
-                            if path.is_ident("transparent") {
-                                return true;
-                            }
-                        }
+/// whether one of `fields` is a `#[from]` field
+fn has_from(fields: &syn::Fields) -> bool {
+    fields.iter().any(|field| check_attr_from(&field.attrs))
+}
 
-                        _ => {}
-                    }
-                }
-            }
+/// Add `#[from]` to the field of the single-field tuple variants of `item_enum` whose type looks
+/// like an error type, for `auto_from`, e.g. `Io(std::io::Error)`.
+///
+/// Variants with `#[backerror(skip)]` or `skip_from`, fields with `#[source]`, `#[backtrace]` or
+/// `#[backerror(skip)]`, and types already converted by another variant are left as they are.
+fn auto_from(item_enum: &mut ItemEnum) {
+    let mut seen: Vec<String> = item_enum
+        .variants
+        .iter()
+        .flat_map(|variant| &variant.fields)
+        .filter(|field| check_attr_from(&field.attrs))
+        .map(|field| field.ty.to_token_stream().to_string())
+        .collect();
+    for variant in item_enum.variants.iter_mut() {
+        let syn::Fields::Unnamed(fields) = &mut variant.fields else {
+            continue;
+        };
+        if fields.unnamed.len() != 1 {
+            continue;
+        }
+        let skipped = VariantArgs::take(&mut variant.attrs.clone())
+            .is_ok_and(|variant_args| variant_args.skip || variant_args.skip_from);
+        let field = &mut fields.unnamed[0];
+        let key = field.ty.to_token_stream().to_string();
+        let annotated = field.attrs.iter().any(|attr| {
+            ["from", "source", "backtrace"]
+                .iter()
+                .any(|name| attr.path().is_ident(name))
+        });
+        if skipped
+            || annotated
+            || FieldArgs::skipped(&field.attrs)
+            || !error_like_type(&field.ty)
+            || seen.contains(&key)
+        {
+            continue;
         }
+        field.attrs.push(parse_quote!(#[from]));
+        seen.push(key);
     }
-    return false;
 }
 
-/// check `#[from]`
-fn check_attr_from(attrs: &Vec<syn::Attribute>) -> bool {
-    for attr in attrs {
-        if attr.path().is_ident("from") {
-            return true;
+/// whether `ty` looks like an error type, since the macro can't check that it implements `Error`:
+/// a path ending with `Error`, e.g. `std::io::Error` or `ParseIntError`, or a `Box<dyn Error>`
+fn error_like_type(ty: &syn::Type) -> bool {
+    if let Some(dyn_ty) = boxed_dyn(ty) {
+        return dyn_ty.to_token_stream().to_string().contains("Error");
+    }
+    match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => type_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|last| last.ident.to_string().ends_with("Error")),
+        syn::Type::Group(group) => error_like_type(&group.elem),
+        syn::Type::Paren(paren) => error_like_type(&paren.elem),
+        _ => false,
+    }
+}
+
+/// whether `fields` have a `#[from]` field along with context fields, e.g.
+/// `Read { #[from] source: io::Error, path: PathBuf }`, other than a backtrace
+fn from_with_context(fields: &syn::Fields) -> bool {
+    has_from(fields)
+        && fields.iter().any(|field| {
+            let backtrace = field.ident.as_ref().is_some_and(|ident| ident == "backtrace")
+                || field
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("backtrace"))
+                || matches!(&field.ty, syn::Type::Path(type_path)
+                    if type_path.path.segments.last().is_some_and(|last| last.ident == "Backtrace"));
+            !check_attr_from(&field.attrs) && !backtrace
+        })
+}
+
+/// Add a `location` field to `variant`, a variant without `#[from]` field, for
+/// `located_variants`, and return its `{variant}_at` constructor, which takes the other fields in
+/// order and captures the location of its caller.
+///
+/// The field is named `location` in a variant with named fields, and the last one of a tuple
+/// variant. A unit variant gets a named `location` field.
+fn located_variant(
+    variant: &mut syn::Variant,
+    vis: &syn::Visibility,
+    invalid: &mut Option<syn::Error>,
+) -> Option<proc_macro2::TokenStream> {
+    // `#[error(transparent)]` requires a single field, and the function of `#[error(fmt = ...)]`
+    // takes the fields in order
+    if check_transparent_struct(&variant.attrs) || check_error_fmt(&variant.attrs) {
+        return None;
+    }
+    let mut names = Vec::new();
+    let mut params = Vec::new();
+    for (index, field) in variant.fields.iter().enumerate() {
+        let name = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| format_ident!("field{index}"));
+        if name == "location" {
+            let message = "`located_variants` adds a `location` field, the variant already has one";
+            combine_error(invalid, syn::Error::new_spanned(&name, message));
+            return None;
+        }
+        let ty = &field.ty;
+        params.push(quote!(#name: #ty));
+        names.push(name);
+    }
+
+    let ident = &variant.ident;
+    let location = quote!(::core::panic::Location::caller());
+    let construct = match &mut variant.fields {
+        syn::Fields::Named(fields) => {
+            fields.named.push(parse_quote! {
+                #[doc = " where the error was created"]
+                location: &'static ::core::panic::Location<'static>
+            });
+            quote!(Self::#ident { #(#names,)* location: #location })
+        }
+        syn::Fields::Unnamed(fields) => {
+            let field: syn::FieldsUnnamed = parse_quote! {
+                (
+                    #[doc = " where the error was created"]
+                    &'static ::core::panic::Location<'static>
+                )
+            };
+            fields.unnamed.extend(field.unnamed);
+            quote!(Self::#ident(#(#names,)* #location))
+        }
+        syn::Fields::Unit => {
+            variant.fields = syn::Fields::Named(parse_quote! {
+                {
+                    #[doc = " where the error was created"]
+                    location: &'static ::core::panic::Location<'static>
+                }
+            });
+            quote!(Self::#ident { location: #location })
+        }
+    };
+
+    let fn_name = format_ident!("{}_at", snake_case(&ident.to_string()));
+    let doc = format!(" [`Self::{ident}`], located at the caller");
+    let cfgs: Vec<_> = cfg_attrs(&variant.attrs).collect();
+    Some(quote! {
+        #[doc = #doc]
+        #(#cfgs)*
+        #[track_caller]
+        #vis fn #fn_name(#(#params),*) -> Self {
+            #construct
+        }
+    })
+}
+
+/// Turn the `#[from]` fields of `variant` into `#[source]` fields, for `skip_from`, so that it has
+/// a located constructor instead of a `From` impl.
+///
+/// Without `skip_from`, a `#[from]` type which is already converted into another variant of
+/// `seen` is reported, since a type can only have one `From` impl. The field is turned into a
+/// `#[source]` field too, so that the conflicting impls aren't reported again by `rustc`.
+fn dedup_from(
+    variant: &mut syn::Variant,
+    skip_from: bool,
+    seen: &mut Vec<(String, Ident)>,
+    invalid: &mut Option<syn::Error>,
+) {
+    for field in variant.fields.iter_mut() {
+        if !check_attr_from(&field.attrs) {
+            continue;
+        }
+        if !skip_from {
+            let key = field.ty.to_token_stream().to_string();
+            match seen.iter().find(|(seen, _)| *seen == key) {
+                Some((_, first)) => {
+                    let message = format!(
+                        "`#[from]` type is already converted into `{first}`, a type can only have one `From` impl, add `#[backerror(skip_from)]` to one of the variants"
+                    );
+                    combine_error(invalid, syn::Error::new_spanned(&field.ty, message));
+                }
+                None => {
+                    seen.push((key, variant.ident.clone()));
+                    continue;
+                }
+            }
+        }
+        from_to_source(field);
+    }
+}
+
+/// turn a `#[from]` field into a `#[source]` field, which `#[from]` implies for `thiserror`
+fn from_to_source(field: &mut syn::Field) {
+    let source = field
+        .attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("source"));
+    field.attrs.retain(|attr| !attr.path().is_ident("from"));
+    if !source {
+        field.attrs.push(parse_quote!(#[source]));
+    }
+}
+
+/// `new_{variant}` constructor of a variant with `#[source]` fields, see `wrap_source`. Like for
+/// `thiserror`, a field named `source` is the source of a variant without `#[source]` attribute,
+/// e.g. `Read { source: io::Error, path: PathBuf }`, its other fields are left as they are.
+///
+/// With `variant_args`, the `#[source]` fields are wrapped into `backerror::LocatedError`, which
+/// the constructor creates at its caller. Without (in release mode), the fields are kept as they
+/// are and the constructor just passes them on.
+fn source_constructor(
+    variant: &mut syn::Variant,
+    vis: &syn::Visibility,
+    variant_args: Option<&VariantArgs>,
+    args: &Args,
+) -> Option<proc_macro2::TokenStream> {
+    let ident = &variant.ident;
+    let (params, construct) = located_sources(
+        &mut variant.fields,
+        quote!(Self::#ident),
+        variant_args,
+        args,
+    )?;
+    let fn_name = format_ident!("new_{}", snake_case(&ident.to_string()));
+    let doc = format!(" [`Self::{ident}`], with its `#[source]` located at the caller");
+    let cfgs: Vec<_> = cfg_attrs(&variant.attrs).collect();
+    Some(quote! {
+        #[doc = #doc]
+        #(#cfgs)*
+        #[track_caller]
+        #vis fn #fn_name(#(#params),*) -> Self {
+            #construct
+        }
+    })
+}
+
+/// `new` constructor of a struct with `#[source]` fields, like [`source_constructor`]
+fn struct_constructor(
+    item_struct: &mut ItemStruct,
+    variant_args: Option<&VariantArgs>,
+    args: &Args,
+) -> Option<proc_macro2::TokenStream> {
+    let (params, construct) =
+        located_sources(&mut item_struct.fields, quote!(Self), variant_args, args)?;
+    let vis = &item_struct.vis;
+    Some(quote! {
+        /// with its `#[source]` located at the caller
+        #[track_caller]
+        #vis fn new(#(#params),*) -> Self {
+            #construct
+        }
+    })
+}
+
+/// the parameters of a located constructor of `fields`, and the expression building `path` from
+/// them, wrapping the `#[source]` fields with `variant_args`, `None` without `#[source]` fields
+fn located_sources(
+    fields: &mut syn::Fields,
+    path: proc_macro2::TokenStream,
+    variant_args: Option<&VariantArgs>,
+    args: &Args,
+) -> Option<(Vec<proc_macro2::TokenStream>, proc_macro2::TokenStream)> {
+    let mut names = Vec::new();
+    let mut params = Vec::new();
+    let mut values = Vec::new();
+    let mut wrapped = false;
+    let implicit = !fields.iter().any(|field| {
+        field
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("source") || attr.path().is_ident("from"))
+    });
+    for (index, field) in fields.iter_mut().enumerate() {
+        let name = field
+            .ident
+            .clone()
+            .unwrap_or_else(|| format_ident!("field{index}"));
+        let ty = field.ty.clone();
+        let source = (field
+            .attrs
+            .iter()
+            .any(|attr| attr.path().is_ident("source"))
+            || (implicit && field.ident.as_ref().is_some_and(|ident| ident == "source")))
+            && !check_attr_from(&field.attrs)
+            && !FieldArgs::skipped(&field.attrs)
+            && non_error_type(&ty).is_none();
+
+        let value = match variant_args {
+            Some(variant_args) if source => {
+                field.ty = located_type(&ty, args);
+                located_error(&ty, quote!(#name), variant_args, args)
+            }
+            _ => quote!(#name),
+        };
+        wrapped |= source;
+        params.push(quote!(#name: #ty));
+        values.push(value);
+        names.push(name);
+    }
+    if !wrapped {
+        return None;
+    }
+
+    let construct = match fields {
+        syn::Fields::Named(_) => quote!(#path { #(#names: #values),* }),
+        _ => quote!(#path(#(#values),*)),
+    };
+    Some((params, construct))
+}
+
+/// the type of the field of each variant with a single field, before it's wrapped
+fn single_field_types(item_enum: &ItemEnum) -> Vec<Option<syn::Type>> {
+    item_enum
+        .variants
+        .iter()
+        .map(|variant| match variant.fields.len() {
+            1 => variant.fields.iter().next().map(|field| field.ty.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `is_{variant}` methods of the variants of `item_enum`, and `as_{variant}` methods of the ones
+/// with a single field, see `accessors`.
+///
+/// `field_types` are the types of the single fields before they're wrapped, `as_{variant}` derefs
+/// through the wrapper to them. For a `Box<dyn Error + ...>` field, wrapped into
+/// `backerror::BoxedError`, it returns the trait object.
+fn accessors(
+    item_enum: &ItemEnum,
+    field_types: &[Option<syn::Type>],
+) -> Vec<proc_macro2::TokenStream> {
+    let vis = &item_enum.vis;
+    let mut fns = Vec::new();
+    for (variant, field_type) in item_enum.variants.iter().zip(field_types) {
+        let ident = &variant.ident;
+        let name = snake_case(&ident.to_string());
+        let is_fn = format_ident!("is_{name}");
+        let doc = format!(" whether this is [`Self::{ident}`]");
+        let cfgs: Vec<_> = cfg_attrs(&variant.attrs).collect();
+        fns.push(quote! {
+            #[doc = #doc]
+            #(#cfgs)*
+            #vis fn #is_fn(&self) -> bool {
+                ::core::matches!(self, Self::#ident { .. })
+            }
+        });
+
+        let (Some(ty), Some(field)) = (field_type, variant.fields.iter().next()) else {
+            continue;
+        };
+        let pattern = match &field.ident {
+            // `..` for the `location` field of `located_variants`
+            Some(field_ident) => quote!(Self::#ident { #field_ident: field, .. }),
+            None => quote!(Self::#ident(field, ..)),
+        };
+        let wrapped = !same_type(&field.ty, ty);
+        // `&dyn Error + Send` is ambiguous, it needs parentheses
+        let (ty, value) = match (boxed_dyn(ty), wrapped) {
+            (Some(dyn_ty), true) => (quote!((#dyn_ty)), quote!(&*field.0)),
+            (Some(dyn_ty), false) => (quote!((#dyn_ty)), quote!(&**field)),
+            (None, true) if anyhow_error(ty) => (quote!(#ty), quote!(&field.0)),
+            (None, true) => (quote!(#ty), quote!(&**field)),
+            (None, false) => (quote!(#ty), quote!(field)),
+        };
+        let as_fn = format_ident!("as_{name}");
+        let doc = format!(" the field of [`Self::{ident}`], if this is it");
+        fns.push(quote! {
+            #[doc = #doc]
+            #(#cfgs)*
+            #[allow(unreachable_patterns)]
+            #vis fn #as_fn(&self) -> ::core::option::Option<&#ty> {
+                match self {
+                    #pattern => ::core::option::Option::Some(#value),
+                    _ => ::core::option::Option::None,
+                }
+            }
+        });
+    }
+    fns
+}
+
+/// `impl` block of the `constructors` and accessors of the enum or struct `ident`, if any
+fn constructors_impl(
+    ident: &Ident,
+    generics: &syn::Generics,
+    constructors: Vec<proc_macro2::TokenStream>,
+) -> Option<proc_macro2::TokenStream> {
+    if constructors.is_empty() {
+        return None;
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Some(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#constructors)*
+        }
+    })
+}
+
+/// `IoError` to `io_error`, `HTTPError` to `http_error`
+fn snake_case(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut snake = String::new();
+    for (index, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_lower = chars.get(index + 1).is_some_and(|next| next.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                snake.push('_');
+            }
+        }
+        snake.extend(ch.to_lowercase());
+    }
+    snake
+}
+
+/// struct, transparent or not
+///
+/// ```ignore
+/// #[backerror]
+/// #[derive(Debug, Error)]
+/// #[error(transparent)]
+/// pub struct MyError(#[from] std::io::Error);
+///
+/// #[backerror]
+/// #[derive(Debug, Error)]
+/// #[error("failed to read {path}: {source}")]
+/// pub struct ReadError {
+///     #[from]
+///     source: std::io::Error,
+///     path: PathBuf,
+/// }
+/// ```
+///
+/// Like for an enum variant, a `#[from]` field along with context fields, or `#[source]` fields
+/// with `wrap_source`, get a located `new` constructor instead of a `From` impl.
+fn backerror_struct(mut item_struct: ItemStruct, input: TokenStream, args: &Args) -> TokenStream {
+    // check whether the struct derives thiserror::Error
+    if !args.derives_thiserror(&item_struct.attrs) {
+        return missing_derive(&item_struct.ident, input);
+    }
+
+    let mut error_types = Vec::new();
+
+    let from_with_context = struct_from_with_context(&mut item_struct);
+    let mut invalid = args.conflicts_with_wrap();
+    if let Err(e) = enhance_fields(&mut item_struct.fields, &mut error_types, args) {
+        combine_error(&mut invalid, e);
+    }
+    let constructor = (args.wrap_source || from_with_context)
+        .then(|| {
+            let variant_args = VariantArgs::default().or_defaults(args);
+            struct_constructor(&mut item_struct, Some(&variant_args), args)
+        })
+        .flatten();
+    FieldArgs::strip(&mut item_struct.fields);
+    let error_types: Vec<_> = error_types
+        .into_iter()
+        .map(|ty| (ty, VariantArgs::default()))
+        .collect();
+    check_no_from(&error_types, args, &mut invalid);
+
+    let impls = generate_from_impl(
+        &item_struct.ident,
+        &item_struct.generics,
+        &error_types,
+        args,
+    );
+
+    let constructors = constructors_impl(
+        &item_struct.ident,
+        &item_struct.generics,
+        constructor.into_iter().collect(),
+    );
+
+    let invalid = invalid.map(|e| e.to_compile_error());
+    let ret = quote! {
+        #item_struct
+        #impls
+        #constructors
+        #invalid
+    };
+
+    ret.into()
+}
+
+/// turn the `#[from]` field of a struct with context fields, which `thiserror` doesn't allow, into
+/// a `#[source]` field, returning whether it did, so that the struct gets a located constructor
+fn struct_from_with_context(item_struct: &mut ItemStruct) -> bool {
+    if !from_with_context(&item_struct.fields) {
+        return false;
+    }
+    for field in item_struct.fields.iter_mut() {
+        if check_attr_from(&field.attrs) {
+            from_to_source(field);
+        }
+    }
+    true
+}
+
+/// `#[derive(Snafu)]` enum or struct, whose source fields are wrapped into
+/// `backerror::LocatedError` and converted with `#[snafu(source(from(T, ...)))]`, so that the
+/// context selectors still take the source error itself, and locate it where they're used
+///
+/// ```ignore
+/// #[backerror]
+/// #[derive(Debug, Snafu)]
+/// pub enum ConfigError {
+///     #[snafu(display("could not read {path}"))]
+///     Read { source: std::io::Error, path: String },
+/// }
+///
+/// std::fs::read_to_string(path).context(ReadSnafu { path })?; // located here
+/// ```
+///
+/// `#[snafu(transparent)]` variants and the source fields with their own
+/// `#[snafu(source(from(...)))]` are left as they are. In release mode with `release_off`, only
+/// the `#[backerror(...)]` attributes are removed.
+fn backerror_snafu(mut item: Item, args: &Args) -> proc_macro2::TokenStream {
+    let mut invalid = args.unsupported_with_snafu();
+    if let Some(e) = args.conflicts_with_wrap() {
+        combine_error(&mut invalid, e);
+    }
+    let enabled = args.enabled();
+    match &mut item {
+        Item::Enum(item_enum) => {
+            for variant in item_enum.variants.iter_mut() {
+                let skip = match VariantArgs::take(&mut variant.attrs) {
+                    Ok(variant_args) => {
+                        if let Some(e) = variant_args.unsupported_with_snafu(&variant.ident) {
+                            combine_error(&mut invalid, e);
+                        }
+                        variant_args.skip
+                    }
+                    Err(e) => {
+                        combine_error(&mut invalid, e);
+                        true
+                    }
+                };
+                let enabled = enabled && !skip && !check_snafu_transparent(&variant.attrs);
+                snafu_fields(&mut variant.fields, enabled, args, &mut invalid);
+            }
+        }
+        Item::Struct(item_struct) => {
+            let enabled = enabled && !check_snafu_transparent(&item_struct.attrs);
+            snafu_fields(&mut item_struct.fields, enabled, args, &mut invalid);
+        }
+        _ => {}
+    }
+    let invalid = invalid.map(|e| e.to_compile_error());
+    quote!(#item #invalid)
+}
+
+/// wrap the source field of `fields` if `enabled`, unless it has `#[backerror(skip)]`, and remove
+/// the `#[backerror(...)]` attributes
+fn snafu_fields(
+    fields: &mut syn::Fields,
+    enabled: bool,
+    args: &Args,
+    invalid: &mut Option<syn::Error>,
+) {
+    for field in fields.iter_mut() {
+        match FieldArgs::parse(&field.attrs) {
+            Ok(FieldArgs { skip: false }) if enabled => snafu_source(field, args),
+            Ok(_) => {}
+            Err(e) => combine_error(invalid, e),
+        }
+    }
+    FieldArgs::strip(fields);
+}
+
+/// wrap `field` if it's a snafu source, i.e. it's named `source` or has `#[snafu(source)]`,
+/// unless it has `#[snafu(source(false))]` or converts the source with
+/// `#[snafu(source(from(...)))]` already
+fn snafu_source(field: &mut syn::Field, args: &Args) {
+    let mut source = field.ident.as_ref().is_some_and(|ident| ident == "source");
+    for meta in field.attrs.iter().flat_map(snafu_metas) {
+        match meta {
+            Meta::Path(path) if path.is_ident("source") => source = true,
+            Meta::List(list) if list.path.is_ident("source") => {
+                match list.tokens.into_iter().next() {
+                    Some(proc_macro2::TokenTree::Ident(value)) if value == "true" => source = true,
+                    // `source(false)`, or `source(from(...))`
+                    _ => return,
+                }
+            }
+            _ => {}
+        }
+    }
+    if !source || non_error_type(&field.ty).is_some() {
+        return;
+    }
+
+    // `source` and `source(true)` are replaced with `source(from(...))`
+    let mut attrs = Vec::new();
+    for attr in field.attrs.drain(..) {
+        if !attr.path().is_ident("snafu") {
+            attrs.push(attr);
+            continue;
+        }
+        let Ok(metas) = attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        else {
+            attrs.push(attr);
+            continue;
+        };
+        let metas: Vec<Meta> = metas
+            .into_iter()
+            .filter(|meta| !meta.path().is_ident("source"))
+            .collect();
+        if !metas.is_empty() {
+            attrs.push(parse_quote!(#[snafu(#(#metas),*)]));
+        }
+    }
+    let ty = field.ty.clone();
+    let located = located_type(&ty, args);
+    attrs.push(parse_quote! {
+        #[snafu(source(from(#ty, <#located as ::core::convert::From<#ty>>::from)))]
+    });
+    field.attrs = attrs;
+    field.ty = located;
+}
+
+/// the metas of a `#[snafu(...)]` attribute, none for other attributes
+fn snafu_metas(attr: &syn::Attribute) -> Vec<Meta> {
+    if !attr.path().is_ident("snafu") {
+        return Vec::new();
+    }
+    attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+        .map(|metas| metas.into_iter().collect())
+        .unwrap_or_default()
+}
+
+/// check `#[snafu(transparent)]`, whose display and source are the ones of the source error
+fn check_snafu_transparent(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .flat_map(snafu_metas)
+        .any(|meta| matches!(meta, Meta::Path(path) if path.is_ident("transparent")))
+}
+
+/// whether `item` is an enum or struct with `#[derive(Snafu)]` instead of `thiserror::Error`
+fn check_snafu_item(item: &Item) -> bool {
+    let attrs = match item {
+        Item::Enum(item_enum) => &item_enum.attrs,
+        Item::Struct(item_struct) => &item_struct.attrs,
+        _ => return false,
+    };
+    check_derive_snafu(attrs) && !check_derive_thiserror(attrs)
+}
+
+/// check `#[derive(Snafu)]` or `#[derive(snafu::Snafu)]`
+fn check_derive_snafu(attrs: &[syn::Attribute]) -> bool {
+    find_derive(attrs, |path| {
+        let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+        match segments.as_slice() {
+            [name] => path.leading_colon.is_none() && name == "Snafu",
+            [krate, name] => krate == "snafu" && name == "Snafu",
+            _ => false,
+        }
+    })
+    .is_some()
+}
+
+/// `From` impls of the `#[from]` types of `ident`, carrying over its generics, lifetimes and
+/// where clause, `None` without `#[from]` fields
+fn generate_from_impl(
+    ident: &Ident,
+    generics: &syn::Generics,
+    error_types: &Vec<(syn::Type, VariantArgs)>,
+    args: &Args,
+) -> Option<proc_macro2::TokenStream> {
+    if error_types.is_empty() {
+        return None;
+    }
+
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut impls = Vec::new();
+    for (from_ty, variant_args) in error_types {
+        let variant_args = &variant_args.or_defaults(args);
+        // a variant which is configured out has no impls either
+        let cfgs = &variant_args.cfgs;
+        // a hand-written impl of `no_from` creates the `LocatedError` itself
+        if !args.no_from(from_ty) {
+            let located = located_error(from_ty, quote!(e), variant_args, args);
+            impls.push(quote! {
+                #(#cfgs)*
+                impl #impl_generics From<#from_ty> for #ident #ty_generics #where_clause {
+                    #[track_caller]
+                    fn from(e: #from_ty) -> Self {
+                        #ident::from(#located)
+                    }
+                }
+            });
+        }
+
+        for wrapper in &args.also_from {
+            let block = if wrapper == "Box" {
+                let located = located_error(from_ty, quote!(*e), variant_args, args);
+                quote! {
+                    #(#cfgs)*
+                    impl #impl_generics From<::std::boxed::Box<#from_ty>>
+                        for #ident #ty_generics #where_clause
+                    {
+                        #[track_caller]
+                        fn from(e: ::std::boxed::Box<#from_ty>) -> Self {
+                            #ident::from(#located)
+                        }
+                    }
+                }
+            } else {
+                let located = located_error(
+                    from_ty,
+                    quote!(::std::sync::Arc::unwrap_or_clone(e)),
+                    variant_args,
+                    args,
+                );
+                quote! {
+                    #(#cfgs)*
+                    impl #impl_generics From<::std::sync::Arc<#from_ty>>
+                        for #ident #ty_generics #where_clause
+                    {
+                        #[track_caller]
+                        fn from(e: ::std::sync::Arc<#from_ty>) -> Self {
+                            #ident::from(#located)
+                        }
+                    }
+                }
+            };
+            impls.push(block);
+        }
+    }
+
+    Some(quote! {
+        #(#impls)*
+    })
+}
+
+/// report the types of `no_from` which aren't the type of a `#[from]` field of `error_types`
+fn check_no_from(
+    error_types: &[(syn::Type, VariantArgs)],
+    args: &Args,
+    invalid: &mut Option<syn::Error>,
+) {
+    for no_from in &args.no_from {
+        if !error_types.iter().any(|(ty, _)| same_type(ty, no_from)) {
+            let message = "no `#[from]` field of this type, the types of `no_from` are written as in the fields";
+            combine_error(invalid, syn::Error::new_spanned(no_from, message));
+        }
+    }
+}
+
+/// the `#[cfg(...)]` attributes of a variant, which the items generated for it need as well
+fn cfg_attrs(attrs: &[syn::Attribute]) -> impl Iterator<Item = &syn::Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("cfg"))
+}
+
+/// whether `a` and `b` are written the same, e.g. `std::io::Error` isn't the same as `io::Error`
+fn same_type(a: &syn::Type, b: &syn::Type) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
+
+/// `backerror::LocatedError` wrapping `expr` of type `ty`, through `backerror::BoxedError` for
+/// `Box<dyn Error>`, with the help text and URL of the variant, and without a backtrace if it's
+/// disabled.
+///
+/// A `wrap` type is only created with `From`, the other arguments are reported along with `wrap`.
+fn located_error(
+    ty: &syn::Type,
+    expr: proc_macro2::TokenStream,
+    variant_args: &VariantArgs,
+    args: &Args,
+) -> proc_macro2::TokenStream {
+    let krate = args.krate();
+    let wrapper = args.wrapper();
+    let (expr, named) = match boxed_dyn(ty) {
+        Some(_) => (quote!(#krate::BoxedError(#expr)), false),
+        None if anyhow_error(ty) => (quote!(#krate::AnyhowError(#expr)), false),
+        None => (expr, true),
+    };
+    if args.wrap.is_some() {
+        return quote!(#wrapper::from(#expr));
+    }
+    // the `ErrorName` of `ty`, resolved here where `ty` is concrete
+    let name = named.then(|| quote!(.__with_error_name(#krate::__error_name!(#ty))));
+
+    let wrap = if variant_args.without_backtrace() {
+        quote!(without_backtrace)
+    } else {
+        quote!(from)
+    };
+    let help = variant_args.help.iter();
+    let url = variant_args.url.iter();
+    quote!(#wrapper::#wrap(#expr) #name #(.with_help(#help))* #(.with_url(#url))*)
+}
+
+/// type of a wrapped field of type `ty`, `backerror::LocatedError<T>`, or
+/// `backerror::LocatedError<backerror::BoxedError<dyn Error + ...>>` for `Box<dyn Error + ...>`,
+/// or `backerror::LocatedError<backerror::AnyhowError>` for `anyhow::Error`
+fn located_type(ty: &syn::Type, args: &Args) -> syn::Type {
+    let krate = args.krate();
+    let wrapper = args.wrapper();
+    match boxed_dyn(ty) {
+        Some(dyn_ty) => parse_quote!(#wrapper<#krate::BoxedError<#dyn_ty>>),
+        None if anyhow_error(ty) => parse_quote!(#wrapper<#krate::AnyhowError>),
+        None => parse_quote!(#wrapper<#ty>),
+    }
+}
+
+/// whether `ty` is `anyhow::Error`, which isn't an error type itself, see `backerror::AnyhowError`
+fn anyhow_error(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => {
+            let segments: Vec<_> = type_path.path.segments.iter().collect();
+            match segments[..] {
+                [krate, error] => {
+                    krate.ident == "anyhow" && error.ident == "Error" && error.arguments.is_none()
+                }
+                _ => false,
+            }
+        }
+        syn::Type::Group(group) => anyhow_error(&group.elem),
+        syn::Type::Paren(paren) => anyhow_error(&paren.elem),
+        _ => false,
+    }
+}
+
+/// enhance fiels from `#[from] T` to `#[from] backerror::LocatedError<T>`
+///
+/// A `#[from]` field which is obviously not an error type, e.g. `Vec<std::io::Error>`, is left
+/// alone and reported, instead of a cascade of trait errors on `LocatedError<Vec<_>>`.
+/// Its `#[from]` is removed so that `thiserror` doesn't report it again.
+///
+/// `Box<dyn Error + Send + Sync>` is not an error type itself, it's wrapped into
+/// `backerror::LocatedError<backerror::BoxedError<dyn Error + Send + Sync>>`.
+fn enhance_fields(
+    fields: &mut syn::Fields,
+    errors: &mut Vec<syn::Type>,
+    args: &Args,
+) -> Result<(), syn::Error> {
+    let mut invalid = None;
+    // tuple fields, e.g. `Io(#[from] io::Error)`, and named fields, e.g. `{ #[from] source: io::Error }`
+    for field in fields.iter_mut() {
+        match FieldArgs::parse(&field.attrs) {
+            Ok(FieldArgs { skip: false }) => {}
+            Ok(FieldArgs { skip: true }) => continue,
+            Err(e) => {
+                combine_error(&mut invalid, e);
+                continue;
+            }
+        }
+        if !check_attr_from(&field.attrs) {
+            continue;
+        }
+        if let Some(found) = non_error_type(&field.ty) {
+            let message = format!(
+                "`#[from]` field must be an error type to be wrapped into `backerror::LocatedError`, found {found}"
+            );
+            combine_error(&mut invalid, syn::Error::new_spanned(&field.ty, message));
+            field.attrs.retain(|attr| !attr.path().is_ident("from"));
+            continue;
+        }
+
+        let orig_ty = field.ty.clone();
+        field.ty = located_type(&orig_ty, args);
+        errors.push(orig_ty);
+    }
+    match invalid {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// the trait object of `Box<dyn Trait>`, e.g. `dyn Error + Send + Sync`
+fn boxed_dyn(ty: &syn::Type) -> Option<&syn::Type> {
+    match ty {
+        syn::Type::Path(type_path) if type_path.qself.is_none() => {
+            let last = type_path.path.segments.last()?;
+            if last.ident != "Box" {
+                return None;
+            }
+            let syn::PathArguments::AngleBracketed(args) = &last.arguments else {
+                return None;
+            };
+            match args.args.first()? {
+                syn::GenericArgument::Type(inner @ syn::Type::TraitObject(_))
+                    if args.args.len() == 1 =>
+                {
+                    Some(inner)
+                }
+                _ => None,
+            }
+        }
+        syn::Type::Group(group) => boxed_dyn(&group.elem),
+        syn::Type::Paren(paren) => boxed_dyn(&paren.elem),
+        _ => None,
+    }
+}
+
+/// describe `ty` if it can't be an error type: a collection, `Option`, `Result` or a non-path type
+fn non_error_type(ty: &syn::Type) -> Option<String> {
+    const CONTAINERS: [&str; 8] = [
+        "Vec", "VecDeque", "Option", "Result", "HashMap", "BTreeMap", "HashSet", "BTreeSet",
+    ];
+    match ty {
+        syn::Type::Path(type_path) => {
+            let last = type_path.path.segments.last()?;
+            CONTAINERS
+                .iter()
+                .any(|container| last.ident == container)
+                .then(|| format!("`{}`", last.ident))
+        }
+        syn::Type::Group(group) => non_error_type(&group.elem),
+        syn::Type::Paren(paren) => non_error_type(&paren.elem),
+        syn::Type::Array(_) => Some("an array".to_string()),
+        syn::Type::Slice(_) => Some("a slice".to_string()),
+        syn::Type::Tuple(_) => Some("a tuple".to_string()),
+        syn::Type::Reference(_) => Some("a reference".to_string()),
+        syn::Type::Ptr(_) => Some("a pointer".to_string()),
+        _ => None,
+    }
+}
+
+/// collect `error` into `errors`
+fn combine_error(errors: &mut Option<syn::Error>, error: syn::Error) {
+    match errors {
+        Some(errors) => errors.combine(error),
+        None => *errors = Some(error),
+    }
+}
+
+/// check the `#[backerror(...)]` arguments of `fields` which aren't enhanced
+fn check_field_args(fields: &syn::Fields, invalid: &mut Option<syn::Error>) {
+    for field in fields {
+        if let Err(e) = FieldArgs::parse(&field.attrs) {
+            combine_error(invalid, e);
+        }
+    }
+}
+
+/// error at `ident` of an item without `#[derive(thiserror::Error)]`, keeping the item so that its
+/// uses don't add more errors
+fn missing_derive(ident: &Ident, input: TokenStream) -> TokenStream {
+    let message = format!(
+        "`#[backerror]` requires `#[derive(thiserror::Error)]` on `{ident}`, placed below `#[backerror]`, or `#[backerror(force)]` if it's derived through an alias"
+    );
+    let mut ret: TokenStream = syn::Error::new_spanned(ident, message)
+        .to_compile_error()
+        .into();
+    ret.extend(input);
+    ret
+}
+
+/// check `#[derive(Error)]`
+fn check_derive_thiserror(attrs: &Vec<syn::Attribute>) -> bool {
+    for attr in attrs {
+        if attr.path().is_ident("derive") {
+            if let Ok(nested) =
+                attr.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+            {
+                for meta in nested {
+                    match meta {
+                        Meta::Path(path) => {
+                            // #[derive(Error)], #[derive(thiserror::Error)], #[derive(::thiserror::Error)]
+                            if is_thiserror_path(&path) {
+                                return true;
+                            }
+                        }
+
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    return false;
+}
+
+/// check that `path` is `Error`, or `Error` of the `thiserror` (or `thiserror_core`) crate, also
+/// re-exported by another crate, e.g. `my_facade::thiserror::Error`
+fn is_thiserror_path(path: &Path) -> bool {
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    match segments.as_slice() {
+        [name] => path.leading_colon.is_none() && name == "Error",
+        [.., krate, name] => (krate == "thiserror" || krate == "thiserror_core") && name == "Error",
+        _ => false,
+    }
+}
+
+/// the aliases of `thiserror::Error` imported by the `use` items of `items`, e.g. `TError` for
+/// `use thiserror::Error as TError;`
+fn thiserror_aliases(items: &[Item]) -> Vec<Ident> {
+    fn walk(tree: &syn::UseTree, thiserror: bool, aliases: &mut Vec<Ident>) {
+        match tree {
+            syn::UseTree::Path(path) => walk(
+                &path.tree,
+                path.ident == "thiserror" || path.ident == "thiserror_core",
+                aliases,
+            ),
+            syn::UseTree::Rename(rename) if thiserror && rename.ident == "Error" => {
+                aliases.push(rename.rename.clone());
+            }
+            syn::UseTree::Group(group) => {
+                for tree in &group.items {
+                    walk(tree, thiserror, aliases);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut aliases = Vec::new();
+    for item in items {
+        if let Item::Use(item_use) = item {
+            walk(&item_use.tree, false, &mut aliases);
+        }
+    }
+    aliases
+}
+
+/// check `#[error(transparent)]`
+///
+/// Only a bare `transparent` counts, `#[error("...")]` display attributes (including a literal
+/// `"transparent"`) and malformed attributes do not. Other metas combined with `transparent`
+/// are skipped.
+fn check_transparent_struct(attrs: &Vec<syn::Attribute>) -> bool {
+    check_error_meta(attrs, "transparent", false)
+}
+
+/// check `#[error(fmt = path::to::fmt)]` of thiserror 2, which formats the error with a function
+/// taking the fields in order, followed by the formatter
+fn check_error_fmt(attrs: &Vec<syn::Attribute>) -> bool {
+    check_error_meta(attrs, "fmt", true)
+}
+
+/// check `#[error(name)]`, or `#[error(name = value)]` with `assign`, among the other metas of
+/// thiserror 1 and 2
+fn check_error_meta(attrs: &Vec<syn::Attribute>, name: &str, assign: bool) -> bool {
+    for attr in attrs {
+        if attr.path().is_ident("error") {
+            let mut found = false;
+            let parsed = attr.parse_nested_meta(|meta| {
+                if meta.input.peek(Token![=]) {
+                    // `name = value`
+                    meta.value()?.parse::<syn::Expr>()?;
+                    found |= assign && meta.path.is_ident(name);
+                } else if meta.input.peek(syn::token::Paren) {
+                    // skip `name(...)`
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    content.parse::<proc_macro2::TokenStream>()?;
+                } else {
+                    found |= !assign && meta.path.is_ident(name);
+                }
+                Ok(())
+            });
+            if parsed.is_ok() && found {
+                return true;
+            }
         }
     }
     return false;
 }
+
+/// check `#[from]`
+fn check_attr_from(attrs: &Vec<syn::Attribute>) -> bool {
+    for attr in attrs {
+        if attr.path().is_ident("from") {
+            return true;
+        }
+    }
+    return false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_derive_thiserror() {
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(Debug, Error)])];
+        assert!(check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(Debug, thiserror::Error)])];
+        assert!(check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(::thiserror::Error)])];
+        assert!(check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(Debug, MyError)])];
+        assert!(!check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(ErrorSomething)])];
+        assert!(!check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(other::Error)])];
+        assert!(!check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(thiserror::ErrorSomething)])];
+        assert!(!check_derive_thiserror(&attrs));
+
+        // re-exported by a facade crate
+        let attrs: Vec<syn::Attribute> =
+            vec![parse_quote!(#[derive(Debug, my_facade::thiserror::Error)])];
+        assert!(check_derive_thiserror(&attrs));
+
+        let attrs: Vec<syn::Attribute> = vec![parse_quote!(#[derive(Debug, TError)])];
+        assert!(!check_derive_thiserror(&attrs));
+        let force = Args {
+            force: true,
+            ..Args::default()
+        };
+        assert!(force.derives_thiserror(&attrs));
+    }
+
+    #[test]
+    fn test_thiserror_aliases() {
+        let item_mod: ItemMod = parse_quote!(
+            mod errors {
+                use ::thiserror::{Error as ThisError, Error};
+                use my_facade::thiserror::Error as FacadeError;
+                use std::io::Error as IoError;
+                use thiserror::Error as TError;
+
+                #[derive(Debug, TError)]
+                pub enum ReadError {
+                    #[error("io: {0}")]
+                    Io(#[from] std::io::Error),
+                }
+
+                #[derive(Debug, Clone)]
+                pub struct Plain;
+            }
+        );
+        let (_, mut items) = item_mod.content.unwrap();
+        let aliases: Vec<String> = thiserror_aliases(&items)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(aliases, ["ThisError", "FacadeError", "TError"]);
+
+        let attr: syn::Attribute = parse_quote!(#[backerror::backerror]);
+        let forced: syn::Attribute = parse_quote!(#[backerror::backerror(force)]);
+        assert_eq!(annotate_errors(&mut items, &attr, &forced), 1);
+        let tokens = quote!(#(#items)*).to_string();
+        assert!(
+            tokens.contains("# [backerror :: backerror (force)] # [derive (Debug , TError)]"),
+            "{tokens}"
+        );
+    }
+
+    #[test]
+    fn test_non_error_type() {
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        assert_eq!(non_error_type(&ty), None);
+
+        let ty: syn::Type = parse_quote!(Box<MyError>);
+        assert_eq!(non_error_type(&ty), None);
+
+        let ty: syn::Type = parse_quote!(Vec<std::io::Error>);
+        assert_eq!(non_error_type(&ty).as_deref(), Some("`Vec`"));
+
+        let ty: syn::Type = parse_quote!(&'static str);
+        assert_eq!(non_error_type(&ty).as_deref(), Some("a reference"));
+    }
+
+    /// `syn::Type` only implements `PartialEq` with the `extra-traits` feature
+    fn tokens(ty: Option<&syn::Type>) -> Option<String> {
+        ty.map(|ty| ty.to_token_stream().to_string())
+    }
+
+    #[test]
+    fn test_boxed_dyn() {
+        let ty: syn::Type = parse_quote!(Box<dyn std::error::Error + Send + Sync>);
+        let dyn_ty: syn::Type = parse_quote!(dyn std::error::Error + Send + Sync);
+        assert_eq!(tokens(boxed_dyn(&ty)), tokens(Some(&dyn_ty)));
+
+        let ty: syn::Type = parse_quote!(std::boxed::Box<dyn Error + 'static>);
+        assert!(boxed_dyn(&ty).is_some());
+
+        let ty: syn::Type = parse_quote!(Box<MyError>);
+        assert!(boxed_dyn(&ty).is_none());
+
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        assert!(boxed_dyn(&ty).is_none());
+    }
+
+    #[test]
+    fn test_enhance_boxed_dyn() {
+        let mut item: ItemStruct = parse_quote!(
+            struct AppError(#[from] Box<dyn std::error::Error + Send + Sync>);
+        );
+        let mut errors = Vec::new();
+        enhance_fields(&mut item.fields, &mut errors, &Args::default()).unwrap();
+
+        let field = item.fields.iter().next().unwrap();
+        let expected: syn::Type = parse_quote!(
+            backerror::LocatedError<backerror::BoxedError<dyn std::error::Error + Send + Sync>>
+        );
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&expected)));
+        let from_ty: syn::Type = parse_quote!(Box<dyn std::error::Error + Send + Sync>);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(tokens(errors.first()), tokens(Some(&from_ty)));
+
+        let ident: Ident = parse_quote!(AppError);
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|ty| (ty, VariantArgs::default()))
+            .collect();
+        let impls =
+            generate_from_impl(&ident, &Default::default(), &errors, &Args::default()).unwrap();
+        assert!(impls.to_string().contains("backerror :: BoxedError (e)"));
+    }
+
+    #[test]
+    fn test_cfg_variant() {
+        let mut item: ItemEnum = parse_quote!(
+            pub enum AppError {
+                #[cfg(feature = "db")]
+                #[backerror(skip_from)]
+                #[error("{0}")]
+                Db(#[from] std::io::Error),
+            }
+        );
+        let field_types = single_field_types(&item);
+        let variant = item.variants.iter_mut().next().unwrap();
+        let variant_args = VariantArgs::take(&mut variant.attrs).unwrap();
+        assert_eq!(variant_args.cfgs.len(), 1);
+        // the attribute stays on the variant
+        assert_eq!(variant.attrs.len(), 2);
+
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        let ident: Ident = parse_quote!(AppError);
+        let args = Args {
+            also_from: vec![parse_quote!(Box)],
+            ..Args::default()
+        };
+        let impls = generate_from_impl(
+            &ident,
+            &Default::default(),
+            &vec![(ty, variant_args.clone())],
+            &args,
+        )
+        .unwrap()
+        .to_string();
+        assert_eq!(
+            impls
+                .matches("# [cfg (feature = \"db\")] impl From")
+                .count(),
+            2,
+            "{impls}"
+        );
+
+        dedup_from(variant, variant_args.skip_from, &mut Vec::new(), &mut None);
+        let constructor =
+            source_constructor(variant, &item.vis, Some(&variant_args), &Args::default());
+        let constructor = constructor.unwrap().to_string();
+        assert!(
+            constructor.contains("# [cfg (feature = \"db\")] # [track_caller] pub fn new_db"),
+            "{constructor}"
+        );
+
+        let fns = accessors(&item, &field_types);
+        for f in fns {
+            assert!(f.to_string().contains("# [cfg (feature = \"db\")]"), "{f}");
+        }
+    }
+
+    #[test]
+    fn test_variant_help() {
+        let mut item: ItemEnum = parse_quote!(
+            enum AppError {
+                #[backerror(help = "check the path")]
+                #[error("{0}")]
+                Io(#[from] std::io::Error),
+            }
+        );
+        let variant = item.variants.iter_mut().next().unwrap();
+        let args = VariantArgs::take(&mut variant.attrs).unwrap();
+        assert_eq!(
+            args.help.as_ref().map(|help| help.value()).as_deref(),
+            Some("check the path")
+        );
+        assert_eq!(variant.attrs.len(), 1);
+
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        let ident: Ident = parse_quote!(AppError);
+        let impls = generate_from_impl(
+            &ident,
+            &Default::default(),
+            &vec![(ty, args)],
+            &Args::default(),
+        )
+        .unwrap();
+        assert!(
+            impls
+                .to_string()
+                .contains(". with_help (\"check the path\")")
+        );
+
+        let mut attrs: Vec<syn::Attribute> = vec![parse_quote!(#[backerror(bogus = "x")])];
+        assert!(VariantArgs::take(&mut attrs).is_err());
+    }
+
+    #[test]
+    fn test_url_default() {
+        let args = Args {
+            url: Some(parse_quote!("https://docs/app")),
+            ..Args::default()
+        };
+        let own = VariantArgs {
+            url: Some(parse_quote!("https://docs/io")),
+            ..VariantArgs::default()
+        };
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        let ident: Ident = parse_quote!(AppError);
+
+        let errors = vec![(ty.clone(), VariantArgs::default()), (ty, own)];
+        let impls = generate_from_impl(&ident, &Default::default(), &errors, &args)
+            .unwrap()
+            .to_string();
+        assert!(
+            impls.contains(". with_url (\"https://docs/app\")"),
+            "{impls}"
+        );
+        assert!(
+            impls.contains(". with_url (\"https://docs/io\")"),
+            "{impls}"
+        );
+
+        let mut attrs: Vec<syn::Attribute> = vec![parse_quote!(#[backerror(url = 1)])];
+        assert!(VariantArgs::take(&mut attrs).is_err());
+    }
+
+    #[test]
+    fn test_locate_fn() {
+        let krate: Path = parse_quote!(backerror);
+        let item_fn: ItemFn = parse_quote!(
+            fn load(path: &str) -> Result<String, LocatedError<std::io::Error>> {
+                Ok(std::fs::read_to_string(path)?)
+            }
+        );
+        let tokens = locate_fn(item_fn, &krate).unwrap().to_string();
+        assert!(tokens.contains("(| | -> Result < String"), "{tokens}");
+        assert!(tokens.contains("stringify ! (load)"), "{tokens}");
+
+        let item_fn: ItemFn = parse_quote!(
+            async fn name() -> Result<impl Display, LocatedError<Error>> {
+                Ok("a")
+            }
+        );
+        let tokens = locate_fn(item_fn, &krate).unwrap().to_string();
+        assert!(tokens.contains("let result = async {"), "{tokens}");
+
+        let item_fn: ItemFn = parse_quote!(
+            async fn read() -> Result<Vec<u8>, LocatedError<Error>> {
+                Ok(Vec::new())
+            }
+        );
+        let tokens = locate_fn(item_fn, &krate).unwrap().to_string();
+        assert!(
+            tokens.contains("__expect_output :: < Result < Vec < u8 >"),
+            "{tokens}"
+        );
+
+        let item_fn: ItemFn = parse_quote!(
+            fn unit() {}
+        );
+        assert!(locate_fn(item_fn, &krate).is_err());
+    }
+
+    #[test]
+    fn test_locate_each_fn() {
+        let item_fn: ItemFn = parse_quote!(
+            fn load(path: &str) -> Result<u32, Box<dyn Error>> {
+                let text = std::fs::read_to_string(path)?;
+                let parse = |text: &str| -> Result<u32, ParseIntError> { Ok(text.parse()?) };
+                fn inner() -> Option<u32> {
+                    Some(1)?.checked_add(1)
+                }
+                Ok(parse(text.trim())? + inner().unwrap_or_default())
+            }
+        );
+        let tokens = locate_each_fn(item_fn, parse_quote!(backerror)).to_string();
+        // the `?` of the closure and of `inner` aren't rewritten
+        assert_eq!(tokens.matches("__locate_kind").count(), 2, "{tokens}");
+        assert!(
+            tokens.contains("match std :: fs :: read_to_string (path) { result => { use backerror"),
+            "{tokens}"
+        );
+        assert!(tokens.contains("stringify ! (load)"), "{tokens}");
+        assert!(tokens.contains("Ok (text . parse () ?)"), "{tokens}");
+    }
+
+    #[test]
+    fn test_main_crate_path() {
+        let krate: Path = parse_quote!(facade::backerror);
+        let item_fn: ItemFn = parse_quote!(
+            fn main() -> Result<(), AppError> {
+                Ok(())
+            }
+        );
+        let tokens = main_fn(item_fn, &krate).unwrap().to_string();
+        assert!(
+            tokens.contains("facade :: backerror :: __main_result (__backerror_main ())"),
+            "{tokens}"
+        );
+
+        let item_fn: ItemFn = parse_quote!(
+            async fn read() -> Result<Vec<u8>, LocatedError<Error>> {
+                Ok(Vec::new())
+            }
+        );
+        let tokens = locate_fn(item_fn, &krate).unwrap().to_string();
+        assert!(
+            tokens.contains("facade :: backerror :: __expect_output"),
+            "{tokens}"
+        );
+
+        let item_fn: ItemFn = parse_quote!(
+            fn load(path: &str) -> Result<String, Box<dyn Error>> {
+                Ok(std::fs::read_to_string(path)?)
+            }
+        );
+        let tokens = locate_each_fn(item_fn, krate).to_string();
+        assert!(tokens.contains("use facade :: backerror :: {"), "{tokens}");
+    }
+
+    #[test]
+    fn test_backerror_mod() {
+        let item_mod: ItemMod = parse_quote!(
+            mod errors {
+                #[derive(Debug, Error)]
+                pub enum LoadError {}
+
+                #[backerror(backtrace = true)]
+                #[derive(Debug, Error)]
+                pub enum HotError {}
+
+                #[derive(Debug)]
+                pub struct NotAnError;
+
+                mod nested {
+                    #[derive(Debug, thiserror::Error)]
+                    #[error(transparent)]
+                    pub struct Io(#[from] std::io::Error);
+                }
+            }
+        );
+        let args: proc_macro2::TokenStream = parse_quote!(backtrace = false);
+        let backerror_args = Args {
+            backtrace: Some(parse_quote!(false)),
+            ..Args::default()
+        };
+        let tokens = backerror_mod(item_mod, args, &backerror_args).to_string();
+        assert_eq!(
+            tokens
+                .matches("# [backerror :: backerror (backtrace = false)]")
+                .count(),
+            2,
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("# [backerror (backtrace = true)] # [derive (Debug , Error)]"),
+            "{tokens}"
+        );
+
+        let item_mod: ItemMod = parse_quote!(
+            mod errors;
+        );
+        let tokens = backerror_mod(item_mod, Default::default(), &Args::default()).to_string();
+        assert!(tokens.contains("inline module"), "{tokens}");
+
+        let item_mod: ItemMod = parse_quote!(
+            mod errors {
+                pub struct NotAnError;
+            }
+        );
+        let tokens = backerror_mod(item_mod, Default::default(), &Args::default()).to_string();
+        assert!(tokens.contains("no `#[derive(Error)]`"), "{tokens}");
+    }
+
+    #[test]
+    fn test_located_copy() {
+        let item: Item = parse_quote!(
+            /// app errors
+            #[derive(Debug, thiserror::Error, BackError, Serialize)]
+            #[serde(tag = "kind")]
+            pub enum AppError {
+                #[error("io: {0}")]
+                #[serde(rename = "io")]
+                Io(#[from] std::io::Error),
+                #[error("denied: {reason}")]
+                Denied { reason: String },
+                #[error("not found")]
+                NotFound,
+            }
+        );
+        let mut located = located_copy(&item).unwrap();
+        let Item::Enum(located_enum) = &mut located else {
+            panic!()
+        };
+        assert_eq!(located_enum.ident, "LocatedAppError");
+        let attrs = &located_enum.attrs;
+        let attrs = quote!(#(#attrs)*).to_string();
+        assert!(
+            attrs.contains("# [derive (Debug , :: thiserror :: Error)]"),
+            "{attrs}"
+        );
+        assert!(
+            !attrs.contains("serde") && !attrs.contains("app errors"),
+            "{attrs}"
+        );
+        let variants = located_enum.variants.to_token_stream().to_string();
+        assert!(!variants.contains("serde"), "{variants}");
+
+        // what `backerror_enum` does to the copy
+        for variant in located_enum.variants.iter_mut() {
+            enhance_fields(&mut variant.fields, &mut Vec::new(), &Args::default()).unwrap();
+        }
+        let tokens = located_from_impl(&item, &located).to_string();
+        assert!(
+            tokens.contains("impl From < AppError > for LocatedAppError"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains(
+                "AppError :: Io (field0) => LocatedAppError :: Io (backerror :: LocatedError :: from (field0) . __with_error_name (backerror :: __error_name ! (std :: io :: Error)))"
+            ),
+            "{tokens}"
+        );
+        assert!(
+            tokens
+                .contains("AppError :: Denied { reason } => LocatedAppError :: Denied { reason }"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("AppError :: NotFound => LocatedAppError :: NotFound"),
+            "{tokens}"
+        );
+
+        let item: Item = parse_quote!(
+            #[derive(Debug)]
+            pub enum AppError {}
+        );
+        assert!(located_copy(&item).is_err());
+    }
+
+    #[test]
+    fn test_result_alias() {
+        let args = Args {
+            result_alias: Some(parse_quote!(AppResult)),
+            ..Args::default()
+        };
+        let item: Item = parse_quote!(
+            pub enum AppError {}
+        );
+        let alias = result_alias(&item, &args).unwrap().to_string();
+        assert!(
+            alias.contains(
+                "pub type AppResult < T , > = :: core :: result :: Result < T , AppError >"
+            ),
+            "{alias}"
+        );
+
+        let item: Item = parse_quote!(
+            pub(crate) struct ParseError<'a, T: Debug, const N: usize>(&'a [T; N]);
+        );
+        let alias = result_alias(&item, &args).unwrap().to_string();
+        assert!(
+            alias.contains("pub (crate) type AppResult < 'a , __T , T , const N : usize > = :: core :: result :: Result < __T , ParseError < 'a , T , N > >"),
+            "{alias}"
+        );
+
+        assert!(result_alias(&item, &Args::default()).is_none());
+    }
+
+    #[test]
+    fn test_debug_expansion() {
+        let ident: Ident = parse_quote!(AppError);
+        let expansion = quote! {
+            enum AppError {
+                Io(backerror::LocatedError<std::io::Error>),
+            }
+            impl From<std::io::Error> for AppError {}
+        };
+        assert_eq!(
+            debug_expansion(&ident, expansion),
+            "`#[backerror]` expansion of `AppError`:\n\
+             enum AppError { Io (backerror :: LocatedError < std :: io :: Error >) , }\n\n\
+             impl From < std :: io :: Error > for AppError { }"
+        );
+
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        syn::parse::Parser::parse2(parser, quote!(debug_expansion)).unwrap();
+        assert!(args.debug_expansion);
+    }
+
+    #[test]
+    fn test_release_arg() {
+        use syn::parse::Parser;
+        let parse = |tokens: proc_macro2::TokenStream| {
+            let mut args = Args::default();
+            syn::meta::parser(|meta| args.parse(meta))
+                .parse2(tokens)
+                .map(|_| args)
+        };
+        let on = parse(quote!(release = "on")).unwrap();
+        assert_eq!(on.release, Some(true));
+        assert_eq!(on.enabled(), !cfg!(backerror_disable));
+        let off = parse(quote!(release = "off")).unwrap();
+        assert_eq!(off.release, Some(false));
+        assert_eq!(
+            off.enabled(),
+            cfg!(all(
+                any(backerror_force, debug_assertions),
+                not(backerror_disable)
+            ))
+        );
+        let err = parse(quote!(release = "maybe")).err().unwrap();
+        assert_eq!(err.to_string(), "expected \"on\" or \"off\"");
+    }
+
+    #[test]
+    fn test_located_variant() {
+        let mut item: ItemEnum = parse_quote!(
+            pub enum AppError {
+                #[error("parse error at line {line}")]
+                Parse { line: usize },
+                #[error("timeout after {0}ms")]
+                Timeout(u64),
+                #[error("not found")]
+                NotFound,
+                #[error("moved")]
+                Moved { location: String },
+                #[error(transparent)]
+                Other(Box<dyn std::error::Error>),
+            }
+        );
+        let mut invalid = None;
+        let fns: Vec<String> = item
+            .variants
+            .iter_mut()
+            .filter_map(|variant| located_variant(variant, &parse_quote!(pub), &mut invalid))
+            .map(|f| f.to_string())
+            .collect();
+        assert_eq!(fns.len(), 3);
+        assert!(
+            fns[0].contains("pub fn parse_at (line : usize) -> Self { Self :: Parse { line , location : :: core :: panic :: Location :: caller () } }"),
+            "{}",
+            fns[0]
+        );
+        assert!(
+            fns[1].contains("pub fn timeout_at (field0 : u64) -> Self { Self :: Timeout (field0 , :: core :: panic :: Location :: caller ()) }"),
+            "{}",
+            fns[1]
+        );
+        assert!(
+            fns[2].contains("pub fn not_found_at () -> Self"),
+            "{}",
+            fns[2]
+        );
+        assert!(invalid.is_some());
+
+        let variants: Vec<_> = item.variants.iter().map(|v| v.fields.len()).collect();
+        assert_eq!(variants, [2, 2, 1, 1, 1]);
+        let not_found = item.variants[2].fields.iter().next().unwrap();
+        assert_eq!(not_found.ident.as_ref().unwrap(), "location");
+    }
+
+    #[test]
+    fn test_enhance_named_fields() {
+        let mut item: ItemStruct = parse_quote!(
+            struct MyError {
+                #[from]
+                source: std::io::Error,
+            }
+        );
+        let mut errors = Vec::new();
+        enhance_fields(&mut item.fields, &mut errors, &Args::default()).unwrap();
+
+        let field = item.fields.iter().next().unwrap();
+        let expected: syn::Type = parse_quote!(backerror::LocatedError<std::io::Error>);
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&expected)));
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_enhance_named_variant() {
+        let mut item: ItemEnum = parse_quote!(
+            enum MyError {
+                #[error("io: {source}")]
+                Io {
+                    #[from]
+                    source: std::io::Error,
+                    backtrace: std::backtrace::Backtrace,
+                },
+            }
+        );
+        let mut errors = Vec::new();
+        let variant = item.variants.iter_mut().next().unwrap();
+        enhance_fields(&mut variant.fields, &mut errors, &Args::default()).unwrap();
+
+        let types: Vec<_> = variant
+            .fields
+            .iter()
+            .map(|field| tokens(Some(&field.ty)))
+            .collect();
+        let located: syn::Type = parse_quote!(backerror::LocatedError<std::io::Error>);
+        let backtrace: syn::Type = parse_quote!(std::backtrace::Backtrace);
+        assert_eq!(types, [tokens(Some(&located)), tokens(Some(&backtrace))]);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_without_backtrace() {
+        let args = Args {
+            backtrace: Some(parse_quote!(false)),
+            ..Args::default()
+        };
+        let own = VariantArgs {
+            backtrace: Some(parse_quote!(true)),
+            ..VariantArgs::default()
+        };
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        let ident: Ident = parse_quote!(AppError);
+
+        let errors = vec![(ty.clone(), VariantArgs::default())];
+        let impls = generate_from_impl(&ident, &Default::default(), &errors, &args)
+            .unwrap()
+            .to_string();
+        assert!(
+            impls.contains("backerror :: LocatedError :: without_backtrace (e)"),
+            "{impls}"
+        );
+
+        let errors = vec![(ty, own)];
+        let impls = generate_from_impl(&ident, &Default::default(), &errors, &args)
+            .unwrap()
+            .to_string();
+        assert!(
+            impls.contains("backerror :: LocatedError :: from (e)"),
+            "{impls}"
+        );
+    }
+
+    #[test]
+    fn test_wrap() {
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        syn::parse::Parser::parse2(parser, quote!(wrap = "my_crate::TracedError")).unwrap();
+
+        let mut item: ItemEnum = parse_quote!(
+            enum AppError {
+                Io(#[from] std::io::Error),
+            }
+        );
+        let variant = item.variants.iter_mut().next().unwrap();
+        let mut errors = Vec::new();
+        enhance_fields(&mut variant.fields, &mut errors, &args).unwrap();
+        let traced: syn::Type = parse_quote!(my_crate::TracedError<std::io::Error>);
+        let field = variant.fields.iter().next().unwrap();
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&traced)));
+
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|ty| (ty, VariantArgs::default()))
+            .collect();
+        let impls = generate_from_impl(&item.ident, &item.generics, &errors, &args).unwrap();
+        let impls = impls.to_string();
+        assert!(
+            impls.contains("AppError :: from (my_crate :: TracedError :: from (e))"),
+            "{impls}"
+        );
+
+        let help = VariantArgs {
+            help: Some(parse_quote!("check the path")),
+            ..VariantArgs::default()
+        };
+        assert!(help.conflicts_with_wrap(&args).is_some());
+        assert!(help.conflicts_with_wrap(&Args::default()).is_none());
+    }
+
+    #[test]
+    fn test_dedup_from() {
+        let mut item: ItemEnum = parse_quote!(
+            enum StoreError {
+                Read(#[from] std::io::Error),
+                Write(#[from] std::io::Error),
+                Copy(#[from] std::io::Error),
+            }
+        );
+        let mut variants = item.variants.iter_mut();
+        let mut seen = Vec::new();
+        let mut invalid = None;
+
+        let read = variants.next().unwrap();
+        dedup_from(read, false, &mut seen, &mut invalid);
+        assert!(invalid.is_none());
+        assert!(check_attr_from(&read.fields.iter().next().unwrap().attrs));
+
+        // `skip_from` has no `From` impl to conflict with
+        let write = variants.next().unwrap();
+        dedup_from(write, true, &mut seen, &mut invalid);
+        assert!(invalid.is_none());
+        let field = write.fields.iter().next().unwrap();
+        assert!(!check_attr_from(&field.attrs));
+        assert!(field.attrs[0].path().is_ident("source"));
+
+        let copy = variants.next().unwrap();
+        dedup_from(copy, false, &mut seen, &mut invalid);
+        let message = invalid.unwrap().to_string();
+        assert!(
+            message.contains("already converted into `Read`"),
+            "{message}"
+        );
+        assert!(!check_attr_from(&copy.fields.iter().next().unwrap().attrs));
+    }
+
+    #[test]
+    fn test_no_from() {
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        let input = quote!(no_from(std::io::Error, Box<dyn std::error::Error + Send + Sync>));
+        syn::parse::Parser::parse2(parser, input).unwrap();
+        assert_eq!(args.no_from.len(), 2);
+
+        let io: syn::Type = parse_quote!(std::io::Error);
+        let utf8: syn::Type = parse_quote!(std::str::Utf8Error);
+        let ident: Ident = parse_quote!(AppError);
+        let errors = vec![
+            (io.clone(), VariantArgs::default()),
+            (utf8, VariantArgs::default()),
+        ];
+        let impls = generate_from_impl(&ident, &Default::default(), &errors, &args)
+            .unwrap()
+            .to_string();
+        assert!(!impls.contains("From < std :: io :: Error >"), "{impls}");
+        assert!(
+            impls.contains("From < std :: str :: Utf8Error >"),
+            "{impls}"
+        );
+
+        // the boxed trait object isn't a `#[from]` type
+        let mut invalid = None;
+        check_no_from(&errors, &args, &mut invalid);
+        assert!(invalid.is_some());
+        args.no_from.truncate(1);
+        let mut invalid = None;
+        check_no_from(&errors, &args, &mut invalid);
+        assert!(invalid.is_none());
+        assert!(args.no_from(&io));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut item: ItemEnum = parse_quote!(
+            pub enum AppError {
+                Io(#[from] std::io::Error),
+                Parse {
+                    #[source]
+                    source: std::num::ParseIntError,
+                },
+                NotFound,
+                Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+            }
+        );
+        let field_types = single_field_types(&item);
+        for variant in item.variants.iter_mut() {
+            enhance_fields(&mut variant.fields, &mut Vec::new(), &Args::default()).unwrap();
+        }
+
+        let fns = accessors(&item, &field_types);
+        let fns: Vec<String> = fns.iter().map(|f| f.to_string()).collect();
+        assert_eq!(fns.len(), 7);
+        assert!(
+            fns[0].contains("pub fn is_io (& self) -> bool"),
+            "{}",
+            fns[0]
+        );
+        assert!(
+            fns[1].contains("Option < & std :: io :: Error >"),
+            "{}",
+            fns[1]
+        );
+        assert!(fns[1].contains("Some (& * * field)"), "{}", fns[1]);
+        assert!(
+            fns[3].contains(
+                "Self :: Parse { source : field , .. } => :: core :: option :: Option :: Some (field)"
+            ),
+            "{}",
+            fns[3]
+        );
+        assert!(fns[4].contains("fn is_not_found"), "{}", fns[4]);
+        assert!(
+            fns[6].contains("Option < & (dyn std :: error :: Error + Send + Sync) >"),
+            "{}",
+            fns[6]
+        );
+        assert!(fns[6].contains("Some (& * field . 0)"), "{}", fns[6]);
+    }
+
+    #[test]
+    fn test_anyhow_error() {
+        let ty: syn::Type = parse_quote!(anyhow::Error);
+        assert!(anyhow_error(&ty));
+        let ty: syn::Type = parse_quote!(::anyhow::Error);
+        assert!(anyhow_error(&ty));
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        assert!(!anyhow_error(&ty));
+        let ty: syn::Type = parse_quote!(Error);
+        assert!(!anyhow_error(&ty));
+
+        let mut item: ItemEnum = parse_quote!(
+            enum AppError {
+                Legacy(#[from] anyhow::Error),
+            }
+        );
+        let field_types = single_field_types(&item);
+        let variant = item.variants.iter_mut().next().unwrap();
+        let mut errors = Vec::new();
+        enhance_fields(&mut variant.fields, &mut errors, &Args::default()).unwrap();
+        let located: syn::Type = parse_quote!(backerror::LocatedError<backerror::AnyhowError>);
+        let field = variant.fields.iter().next().unwrap();
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&located)));
+
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|ty| (ty, VariantArgs::default()))
+            .collect();
+        let impls = generate_from_impl(&item.ident, &item.generics, &errors, &Args::default());
+        let impls = impls.unwrap().to_string();
+        assert!(
+            impls.contains("backerror :: LocatedError :: from (backerror :: AnyhowError (e))"),
+            "{impls}"
+        );
+
+        let fns = accessors(&item, &field_types);
+        assert!(
+            fns[1].to_string().contains("Some (& field . 0)"),
+            "{}",
+            fns[1]
+        );
+    }
+
+    #[test]
+    fn test_skip() {
+        let mut item: ItemEnum = parse_quote!(
+            enum AppError {
+                Located(
+                    #[from]
+                    #[backerror(skip)]
+                    backerror::LocatedError<std::io::Error>,
+                ),
+                Bad(#[backerror(bogus)] String),
+            }
+        );
+        let mut variants = item.variants.iter_mut();
+        let args = Args::default();
+
+        let variant = variants.next().unwrap();
+        let mut errors = Vec::new();
+        enhance_fields(&mut variant.fields, &mut errors, &args).unwrap();
+        assert!(errors.is_empty());
+        let located: syn::Type = parse_quote!(backerror::LocatedError<std::io::Error>);
+        let field = variant.fields.iter().next().unwrap();
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&located)));
+        assert!(FieldArgs::strip(&mut variant.fields));
+        let field = variant.fields.iter().next().unwrap();
+        assert_eq!(field.attrs.len(), 1);
+
+        let variant = variants.next().unwrap();
+        assert!(enhance_fields(&mut variant.fields, &mut errors, &args).is_err());
+
+        let mut attrs: Vec<syn::Attribute> = vec![parse_quote!(#[backerror(skip)])];
+        assert!(VariantArgs::take(&mut attrs).unwrap().skip);
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("Io"), "io");
+        assert_eq!(snake_case("IoError"), "io_error");
+        assert_eq!(snake_case("HTTPError"), "http_error");
+        assert_eq!(snake_case("Utf8"), "utf8");
+    }
+
+    #[test]
+    fn test_source_constructor() {
+        let mut item: ItemEnum = parse_quote!(
+            enum MyError {
+                #[error("line {line}")]
+                ParseInt {
+                    #[source]
+                    source: std::num::ParseIntError,
+                    line: usize,
+                },
+                #[error("io")]
+                Io(#[from] std::io::Error),
+            }
+        );
+        let vis = item.vis.clone();
+        let args = Args::default();
+        let mut variants = item.variants.iter_mut();
+
+        let variant = variants.next().unwrap();
+        let constructor = source_constructor(variant, &vis, Some(&VariantArgs::default()), &args);
+        let constructor = constructor.unwrap().to_string();
+        assert!(
+            constructor
+                .contains("fn new_parse_int (source : std :: num :: ParseIntError , line : usize)"),
+            "{constructor}"
+        );
+        assert!(
+            constructor.contains("source : backerror :: LocatedError :: from (source)"),
+            "{constructor}"
+        );
+        let located: syn::Type = parse_quote!(backerror::LocatedError<std::num::ParseIntError>);
+        let field = variant.fields.iter().next().unwrap();
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&located)));
+
+        // `#[from]` fields have a `From` impl instead
+        let variant = variants.next().unwrap();
+        assert!(source_constructor(variant, &vis, Some(&VariantArgs::default()), &args).is_none());
+    }
+
+    #[test]
+    fn test_implicit_source() {
+        let mut item: ItemEnum = parse_quote!(
+            enum MyError {
+                #[error("read {path}")]
+                Read {
+                    source: std::io::Error,
+                    path: String,
+                },
+                #[error("parse")]
+                Parse {
+                    source: String,
+                    #[source]
+                    cause: std::num::ParseIntError,
+                },
+            }
+        );
+        let vis = item.vis.clone();
+        let args = Args::default();
+        let mut variants = item.variants.iter_mut();
+
+        let variant = variants.next().unwrap();
+        let constructor = source_constructor(variant, &vis, Some(&VariantArgs::default()), &args);
+        let constructor = constructor.unwrap().to_string();
+        assert!(
+            constructor.contains("fn new_read (source : std :: io :: Error , path : String)"),
+            "{constructor}"
+        );
+        let fields = variant.fields.to_token_stream().to_string();
+        assert!(
+            fields.contains(
+                "source : backerror :: LocatedError < std :: io :: Error > , path : String"
+            ),
+            "{fields}"
+        );
+
+        // `#[source]` wins over the name
+        let variant = variants.next().unwrap();
+        source_constructor(variant, &vis, Some(&VariantArgs::default()), &args).unwrap();
+        let fields = variant.fields.to_token_stream().to_string();
+        assert!(fields.contains("source : String"), "{fields}");
+    }
+
+    #[test]
+    fn test_from_with_context() {
+        let item: ItemEnum = parse_quote!(
+            enum MyError {
+                Read {
+                    #[from]
+                    source: std::io::Error,
+                    path: String,
+                },
+                Io {
+                    #[from]
+                    source: std::io::Error,
+                    backtrace: std::backtrace::Backtrace,
+                },
+                Parse(#[from] std::num::ParseIntError),
+                Denied {
+                    reason: String,
+                },
+            }
+        );
+        let found: Vec<_> = item
+            .variants
+            .iter()
+            .map(|variant| from_with_context(&variant.fields))
+            .collect();
+        assert_eq!(found, [true, false, false, false]);
+    }
+
+    #[test]
+    fn test_auto_from() {
+        let mut item: ItemEnum = parse_quote!(
+            enum MyError {
+                Io(std::io::Error),
+                Parse(std::num::ParseIntError),
+                Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+                Reparse(std::num::ParseIntError),
+                Message(String),
+                Source(#[source] std::fmt::Error),
+                #[backerror(skip_from)]
+                Format(std::fmt::Error),
+                Pair(std::io::Error, String),
+            }
+        );
+        auto_from(&mut item);
+        let found: Vec<_> = item
+            .variants
+            .iter()
+            .map(|variant| variant.fields.iter().any(|f| check_attr_from(&f.attrs)))
+            .collect();
+        assert_eq!(found, [true, true, true, false, false, false, false, false]);
+
+        assert!(error_like_type(&parse_quote!(io::Error)));
+        assert!(error_like_type(&parse_quote!(Box<dyn Error + Send>)));
+        assert!(!error_like_type(&parse_quote!(Box<String>)));
+        assert!(!error_like_type(&parse_quote!(ErrorKind)));
+    }
+
+    #[test]
+    fn test_crate_path() {
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        syn::parse::Parser::parse2(parser, quote!(crate = "facade::backerror")).unwrap();
+        let krate = args.krate();
+        assert_eq!(krate.to_token_stream().to_string(), "facade :: backerror");
+
+        let mut item: ItemEnum = parse_quote!(
+            enum AppError {
+                Io(#[from] std::io::Error),
+            }
+        );
+        let variant = item.variants.iter_mut().next().unwrap();
+        let mut errors = Vec::new();
+        enhance_fields(&mut variant.fields, &mut errors, &args).unwrap();
+        let located: syn::Type = parse_quote!(facade::backerror::LocatedError<std::io::Error>);
+        let field = variant.fields.iter().next().unwrap();
+        assert_eq!(tokens(Some(&field.ty)), tokens(Some(&located)));
+
+        let errors: Vec<_> = errors
+            .into_iter()
+            .map(|ty| (ty, VariantArgs::default()))
+            .collect();
+        let impls = generate_from_impl(&item.ident, &item.generics, &errors, &args).unwrap();
+        let impls = impls.to_string();
+        assert!(
+            impls.contains("facade :: backerror :: LocatedError :: from (e)"),
+            "{impls}"
+        );
+    }
+
+    #[test]
+    fn test_generic_from_impl() {
+        let item: ItemEnum = parse_quote!(
+            enum MyError<T: std::error::Error>
+            where
+                T: Send,
+            {
+                Inner(#[from] T),
+            }
+        );
+        let ty: syn::Type = parse_quote!(T);
+        let errors = vec![(ty, VariantArgs::default())];
+        let impls = generate_from_impl(&item.ident, &item.generics, &errors, &Args::default());
+        let impls = impls.unwrap().to_string();
+        assert!(
+            impls.contains(
+                "impl < T : std :: error :: Error > From < T > for MyError < T > where T : Send"
+            ),
+            "{impls}"
+        );
+    }
+
+    #[test]
+    fn test_lifetime_from_impl() {
+        let item: ItemEnum = parse_quote!(
+            enum Borrowed<'a> {
+                Key(&'a str),
+                Io(#[from] std::io::Error),
+            }
+        );
+        let ty: syn::Type = parse_quote!(std::io::Error);
+        let errors = vec![(ty, VariantArgs::default())];
+        let impls = generate_from_impl(&item.ident, &item.generics, &errors, &Args::default());
+        let impls = impls.unwrap().to_string();
+        assert!(
+            impls.contains("impl < 'a > From < std :: io :: Error > for Borrowed < 'a >"),
+            "{impls}"
+        );
+    }
+
+    #[test]
+    fn test_check_transparent_struct() {
+        let transparent: Vec<syn::Attribute> = vec![parse_quote!(#[error(transparent)])];
+        assert!(check_transparent_struct(&transparent));
+
+        let combined: Vec<syn::Attribute> = vec![
+            parse_quote!(#[doc = "error"]),
+            parse_quote!(#[error(skipped = "x", transparent, nested(a, "b"))]),
+        ];
+        assert!(check_transparent_struct(&combined));
+
+        let display: Vec<syn::Attribute> = vec![parse_quote!(#[error("msg {0}")])];
+        assert!(!check_transparent_struct(&display));
+
+        let literal: Vec<syn::Attribute> = vec![parse_quote!(#[error("transparent")])];
+        assert!(!check_transparent_struct(&literal));
+
+        let malformed: Vec<syn::Attribute> = vec![parse_quote!(#[error(transparent =)])];
+        assert!(!check_transparent_struct(&malformed));
+
+        let other: Vec<syn::Attribute> = vec![parse_quote!(#[serde(transparent)])];
+        assert!(!check_transparent_struct(&other));
+
+        // thiserror 2
+        let fmt: Vec<syn::Attribute> = vec![parse_quote!(#[error(fmt = Self::fmt_io)])];
+        assert!(!check_transparent_struct(&fmt));
+        assert!(check_error_fmt(&fmt));
+        assert!(!check_error_fmt(&transparent));
+        assert!(!check_error_fmt(&display));
+        let bare: Vec<syn::Attribute> = vec![parse_quote!(#[error(fmt)])];
+        assert!(!check_error_fmt(&bare));
+    }
+
+    #[test]
+    fn test_backerror_snafu() {
+        let item: Item = parse_quote!(
+            #[derive(Debug, Snafu)]
+            pub enum ConfigError {
+                #[snafu(display("could not read {path}"))]
+                Read {
+                    source: std::io::Error,
+                    path: String,
+                },
+                #[snafu(display("invalid port"))]
+                Port {
+                    #[snafu(source, implicit)]
+                    cause: std::num::ParseIntError,
+                },
+                #[backerror(skip)]
+                Parse {
+                    source: std::num::ParseIntError,
+                },
+                Other {
+                    #[snafu(source(from(Box<dyn std::error::Error>, Some)))]
+                    source: Option<Box<dyn std::error::Error>>,
+                },
+                #[snafu(transparent)]
+                Fmt {
+                    source: std::fmt::Error,
+                },
+                Missing {
+                    key: String,
+                },
+            }
+        );
+        assert!(check_snafu_item(&item));
+        let tokens = backerror_snafu(item, &Args::default()).to_string();
+        let enabled = Args::default().enabled();
+        assert_eq!(
+            tokens.contains("# [snafu (source (from (std :: io :: Error , < backerror :: LocatedError < std :: io :: Error > as :: core :: convert :: From < std :: io :: Error >> :: from)))] source : backerror :: LocatedError < std :: io :: Error >"),
+            enabled,
+            "{tokens}"
+        );
+        assert_eq!(
+            tokens.contains(
+                "# [snafu (implicit)] # [snafu (source (from (std :: num :: ParseIntError ,"
+            ),
+            enabled,
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("Parse { source : std :: num :: ParseIntError , }"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("source : Option < Box < dyn std :: error :: Error > >"),
+            "{tokens}"
+        );
+        assert!(
+            tokens.contains("Fmt { source : std :: fmt :: Error , }"),
+            "{tokens}"
+        );
+        assert!(!tokens.contains("backerror (skip)"), "{tokens}");
+
+        let args = Args {
+            accessors: true,
+            ..Args::default()
+        };
+        let item: Item = parse_quote!(
+            #[derive(Debug, snafu::Snafu)]
+            pub struct ReadError {
+                source: std::io::Error,
+            }
+        );
+        let tokens = backerror_snafu(item, &args).to_string();
+        assert!(
+            tokens.contains("`accessors` isn't supported with `#[derive(Snafu)]`"),
+            "{tokens}"
+        );
+    }
+
+    #[test]
+    fn test_error_name_impl() {
+        let mut args = Args::default();
+        let parser = syn::meta::parser(|meta| args.parse(meta));
+        syn::parse::Parser::parse2(parser, quote!(name = "Config")).unwrap();
+        let item: Item = parse_quote!(
+            #[derive(Debug, Error)]
+            pub enum ConfigError<T> {
+                #[error("bad value")]
+                Bad(T),
+            }
+        );
+        let tokens = error_name_impl(&item, &args).unwrap().to_string();
+        assert!(
+            tokens.contains("impl < T > backerror :: ErrorName for ConfigError < T >"),
+            "{tokens}"
+        );
+        assert!(tokens.contains("\"Config\""), "{tokens}");
+        assert!(error_name_impl(&item, &Args::default()).is_none());
+
+        let item_mod: ItemMod = parse_quote!(
+            mod errors {}
+        );
+        let tokens = backerror_mod(item_mod, quote!(name = "Config"), &args).to_string();
+        assert!(
+            tokens.contains("`name` applies to a single error type, not to a module"),
+            "{tokens}"
+        );
+    }
+
+    #[test]
+    fn test_located_copy_displaydoc() {
+        let item: Item = parse_quote!(
+            /// io error: {0}
+            #[derive(Debug, Display, Error, BackError)]
+            pub struct IoError(#[from] std::io::Error);
+        );
+        let located = located_copy(&item).unwrap();
+        let attrs = match &located {
+            Item::Struct(located_struct) => &located_struct.attrs,
+            _ => panic!(),
+        };
+        let attrs = quote!(#(#attrs)*).to_string();
+        assert!(
+            attrs.contains("# [derive (Debug , :: displaydoc :: Display , :: thiserror :: Error)]"),
+            "{attrs}"
+        );
+        assert!(attrs.contains("io error: {0}"), "{attrs}");
+        assert!(!attrs.contains("LocatedError"), "{attrs}");
+    }
+}
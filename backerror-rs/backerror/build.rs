@@ -0,0 +1,27 @@
+use std::env;
+
+fn main() {
+    println!("cargo::rustc-check-cfg=cfg(passthrough)");
+    println!("cargo::rustc-check-cfg=cfg(capture_backtrace)");
+    println!("cargo::rustc-check-cfg=cfg(layered)");
+
+    let release = env::var_os("CARGO_CFG_DEBUG_ASSERTIONS").is_none();
+
+    // `release_passthrough` only applies to builds without debug assertions
+    let passthrough = release && env::var_os("CARGO_FEATURE_RELEASE_PASSTHROUGH").is_some();
+    if passthrough {
+        println!("cargo::rustc-cfg=passthrough");
+    }
+
+    // a passthrough `LocatedError` is transparent, its `source()` is the wrapped error's source
+    if env::var_os("CARGO_FEATURE_LAYERED_SOURCE").is_some() && !passthrough {
+        println!("cargo::rustc-cfg=layered");
+    }
+
+    // like the macro, backtraces are turned off in release builds by `release_off`,
+    // so that `force_backtrace` (enabled by default) can't slow down release builds
+    let release_off = release && env::var_os("CARGO_FEATURE_RELEASE_OFF").is_some();
+    if env::var_os("CARGO_FEATURE_BACKTRACE").is_some() && !passthrough && !release_off {
+        println!("cargo::rustc-cfg=capture_backtrace");
+    }
+}
@@ -0,0 +1,35 @@
+//! Recording errors as OpenTelemetry `exception` span events, with the `opentelemetry` feature.
+//!
+//! An in-memory exporter is used to print the recorded events; a real application would
+//! install an OTLP exporter instead.
+use backerror::{LocatedError, ResultExt};
+use opentelemetry::trace::{Tracer, TracerProvider};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+
+fn read_config(path: &str) -> Result<String, LocatedError<std::io::Error>> {
+    // recorded on the current span when the error is located
+    std::fs::read_to_string(path).wrap_located()
+}
+
+fn main() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let tracer = provider.tracer("example");
+
+    tracer.in_span("read_config", |_cx| {
+        if let Err(err) = read_config("blurb.toml") {
+            println!("{}", err);
+        }
+    });
+
+    for span in exporter.get_finished_spans().unwrap() {
+        for event in span.events.events.iter() {
+            println!("\nspan `{}`, event `{}`:", span.name, event.name);
+            for kv in &event.attributes {
+                println!("  {} = {}", kv.key, kv.value);
+            }
+        }
+    }
+}
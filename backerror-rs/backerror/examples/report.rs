@@ -0,0 +1,22 @@
+//! Printing an error and its causes for humans, e.g. at the end of a CLI tool.
+use backerror::{LocatedError, backerror};
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config")]
+    Read(#[from] std::io::Error),
+}
+
+fn load_config(path: &str) -> Result<String, ConfigError> {
+    Ok(std::fs::read_to_string(path)?)
+}
+
+fn main() {
+    if let Err(err) = load_config("blurb.toml") {
+        let err = LocatedError::wrap(err);
+        eprintln!("Error: {}", err.report());
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,32 @@
+//! Using `backerror` without `#[backerror]`, e.g. for error types defined in other crates.
+use backerror::{LocatedError, ResultExt};
+
+fn read_config(path: &str) -> Result<String, LocatedError<std::io::Error>> {
+    // the location of `wrap_located` is captured
+    std::fs::read_to_string(path).wrap_located()
+}
+
+fn parse_port(s: &str) -> Result<u16, LocatedError<std::num::ParseIntError>> {
+    // `?` converts through `From`, capturing the location of `?`
+    Ok(s.trim().parse::<u16>()?)
+}
+
+fn main() {
+    if let Err(err) = read_config("blurb.toml") {
+        println!("Display output:");
+        println!("{}", err);
+        println!("\nDebug output:");
+        println!("{:?}", err);
+    }
+
+    if let Err(err) = parse_port("http") {
+        let (file, line) = err.site_key();
+        println!("\n{} at {}:{}", *err, file, line);
+    }
+
+    // wrap explicitly
+    if let Err(err) = "http".parse::<u16>() {
+        let err = LocatedError::wrap(err);
+        println!("{}", err);
+    }
+}
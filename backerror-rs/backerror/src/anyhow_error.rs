@@ -0,0 +1,97 @@
+use crate::LocatedError;
+use core::error::Error;
+use core::fmt;
+use core::ops::Deref;
+use core::panic::Location;
+
+/// `anyhow::Error` as an error type, which it isn't itself, so that it can be wrapped into a
+/// [`crate::LocatedError`], e.g. in a codebase migrating from `anyhow`.
+///
+/// `#[backerror]` rewrites a `#[from] anyhow::Error` field into `LocatedError<AnyhowError>`:
+/// ```ignore
+/// #[backerror]
+/// #[derive(Debug, Error)]
+/// pub enum AppError {
+///     #[error("legacy: {0}")]
+///     Legacy(#[from] anyhow::Error),
+/// }
+/// ```
+///
+/// [`fmt::Display`], [`fmt::Debug`] and [`Error::source`] are the ones of the `anyhow::Error`,
+/// and it derefs to it, e.g. for `chain()` or `downcast_ref()`.
+pub struct AnyhowError(pub anyhow::Error);
+
+impl AnyhowError {
+    /// the `anyhow::Error`
+    pub fn into_inner(self) -> anyhow::Error {
+        self.0
+    }
+}
+
+impl From<anyhow::Error> for AnyhowError {
+    fn from(err: anyhow::Error) -> Self {
+        AnyhowError(err)
+    }
+}
+
+impl From<anyhow::Error> for LocatedError<AnyhowError> {
+    #[track_caller]
+    fn from(err: anyhow::Error) -> Self {
+        LocatedError::located_at(AnyhowError(err), Location::caller())
+    }
+}
+
+impl Deref for AnyhowError {
+    type Target = anyhow::Error;
+
+    fn deref(&self) -> &anyhow::Error {
+        &self.0
+    }
+}
+
+impl fmt::Display for AnyhowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for AnyhowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl Error for AnyhowError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnyhowError;
+    use crate::LocatedError;
+
+    fn legacy() -> anyhow::Result<u32> {
+        Err(anyhow::anyhow!("legacy failure"))
+    }
+
+    fn load() -> Result<u32, LocatedError<AnyhowError>> {
+        Ok(legacy()?)
+    }
+
+    #[test]
+    fn located_anyhow_error() {
+        assert_eq!(load().unwrap_err().0.to_string(), "legacy failure");
+
+        let (err, line) = (LocatedError::from(legacy().unwrap_err()), line!());
+        let err: LocatedError<AnyhowError> = err;
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+
+        let io = anyhow::Error::from(std::io::Error::other("boom"));
+        let err = LocatedError::<AnyhowError>::from(io);
+        assert!(err.downcast_ref::<std::io::Error>().is_some());
+    }
+}
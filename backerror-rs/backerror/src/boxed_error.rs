@@ -0,0 +1,101 @@
+use crate::LocatedError;
+use core::error::Error;
+use core::fmt;
+use core::ops::Deref;
+use core::panic::Location;
+
+/// `Box<dyn Error + Send + Sync>` as an error type, which it isn't itself, so that it can be
+/// wrapped into a [`crate::LocatedError`].
+///
+/// `#[backerror]` rewrites a `#[from] Box<dyn Error + Send + Sync>` field into
+/// `LocatedError<BoxedError<dyn Error + Send + Sync>>`:
+/// ```ignore
+/// #[backerror]
+/// #[derive(Debug, Error)]
+/// #[error(transparent)]
+/// pub struct AppError(#[from] Box<dyn std::error::Error + Send + Sync>);
+/// ```
+///
+/// [`fmt::Display`], [`fmt::Debug`] and [`Error::source`] are the ones of the boxed error.
+pub struct BoxedError<T: Error + ?Sized = dyn Error + Send + Sync>(pub Box<T>);
+
+/// [`LocatedError`] of a trait object error, `Box<dyn Error + Send + Sync>` converts into it with
+/// `?`, capturing the location:
+/// ```ignore
+/// fn load() -> Result<Config, LocatedDynError> {
+///     let text = read_config()?; // Result<String, Box<dyn Error + Send + Sync>>
+///     Ok(parse(&text))
+/// }
+/// ```
+/// `LocatedError<E>` keeps `E` inline, so `E` can't be unsized and is boxed in a [`BoxedError`].
+pub type LocatedDynError = LocatedError<BoxedError>;
+
+impl<T: Error + ?Sized> BoxedError<T> {
+    /// the boxed error
+    pub fn into_inner(self) -> Box<T> {
+        self.0
+    }
+}
+
+impl<T: Error + ?Sized> From<Box<T>> for BoxedError<T> {
+    fn from(err: Box<T>) -> Self {
+        BoxedError(err)
+    }
+}
+
+impl From<Box<dyn Error + Send + Sync>> for LocatedDynError {
+    #[track_caller]
+    fn from(err: Box<dyn Error + Send + Sync>) -> Self {
+        LocatedError::located_at(BoxedError(err), Location::caller())
+    }
+}
+
+impl<T: Error + ?Sized> Deref for BoxedError<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Error + ?Sized> fmt::Display for BoxedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<T: Error + ?Sized> fmt::Debug for BoxedError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<T: Error + ?Sized> Error for BoxedError<T> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocatedDynError;
+    use core::error::Error;
+
+    fn read_config() -> Result<String, Box<dyn Error + Send + Sync>> {
+        Err("no config".into())
+    }
+
+    fn load() -> Result<String, LocatedDynError> {
+        Ok(read_config()?)
+    }
+
+    #[test]
+    fn located_dyn_error() {
+        assert_eq!(load().unwrap_err().0.to_string(), "no config");
+
+        let (err, line) = (LocatedDynError::from(read_config().unwrap_err()), line!());
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+    }
+}
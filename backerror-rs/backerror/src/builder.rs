@@ -0,0 +1,190 @@
+use crate::LocatedError;
+use core::error::Error;
+use core::fmt;
+use core::panic::Location;
+
+/// Optional metadata of a [`LocatedError`], set through [`LocatedErrorBuilder`]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Metadata {
+    pub id: Option<String>,
+    pub context: Option<String>,
+    /// `(key, value)` in the order they were first set
+    pub fields: Vec<(String, String)>,
+    /// see [`LocatedError::with_help`]
+    pub help: Option<String>,
+    /// see [`LocatedError::with_url`]
+    pub url: Option<String>,
+    /// see [`LocatedError::with_function`]
+    pub function: Option<&'static str>,
+    /// see [`LocatedError::named`]
+    pub name: Option<&'static str>,
+}
+
+impl Metadata {
+    /// set `key` to `value`, replacing a previous value of `key`
+    pub fn set_field(&mut self, key: String, value: String) {
+        match self.fields.iter_mut().find(|(k, _)| *k == key) {
+            Some(field) => field.1 = value,
+            None => self.fields.push((key, value)),
+        }
+    }
+}
+
+/// Builder of a [`LocatedError`] with metadata, created by [`LocatedError::builder`].
+/// ```ignore
+/// let err = LocatedError::builder(err)
+///     .id(request_id)
+///     .context("while loading the config")
+///     .field("retry", attempt)
+///     .capture_backtrace()
+///     .build();
+/// ```
+#[must_use]
+pub struct LocatedErrorBuilder<E: Error> {
+    inner: E,
+    location: Option<&'static Location<'static>>,
+    capture_backtrace: bool,
+    metadata: Metadata,
+}
+
+impl<E: Error> LocatedError<E> {
+    /// Start building a [`LocatedError`] wrapping `err`, see [`LocatedErrorBuilder`]
+    pub fn builder(err: E) -> LocatedErrorBuilder<E> {
+        LocatedErrorBuilder {
+            inner: err,
+            location: None,
+            capture_backtrace: false,
+            metadata: Metadata::default(),
+        }
+    }
+}
+
+impl<E: Error> LocatedErrorBuilder<E> {
+    /// capture location, the caller of [`LocatedErrorBuilder::build`] if not set
+    pub fn location(mut self, location: &'static Location<'static>) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// capture a backtrace even if the enabled features and environment wouldn't,
+    /// has no effect without the `backtrace` feature
+    pub fn capture_backtrace(mut self) -> Self {
+        self.capture_backtrace = true;
+        self
+    }
+
+    /// identifier of the error, e.g. a request or correlation id
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.metadata.id = Some(id.into());
+        self
+    }
+
+    /// human readable context, e.g. what was being done when the error occurred
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        self.metadata.context = Some(context.into());
+        self
+    }
+
+    /// structured context, e.g. a user id or retry count, see [`LocatedError::with_field`]
+    pub fn field(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        self.metadata.set_field(key.into(), value.to_string());
+        self
+    }
+
+    /// actionable hint for the user, see [`LocatedError::with_help`]
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.metadata.help = Some(help.into());
+        self
+    }
+
+    /// documentation or runbook URL, see [`LocatedError::with_url`]
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.metadata.url = Some(url.into());
+        self
+    }
+
+    #[track_caller]
+    pub fn build(self) -> LocatedError<E> {
+        let location = self.location.unwrap_or(Location::caller());
+        #[cfg_attr(passthrough, allow(unused_mut))]
+        let mut located = LocatedError::located_at(self.inner, location);
+        #[cfg(capture_backtrace)]
+        if self.capture_backtrace {
+            located.force_backtrace();
+        }
+        #[cfg(not(passthrough))]
+        located.set_metadata(self.metadata);
+        located
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocatedError;
+    use core::panic::Location;
+
+    #[test]
+    fn build_with_metadata() {
+        let location = Location::caller();
+        let err = LocatedError::builder(std::io::Error::other("boom"))
+            .id("req-42")
+            .context("loading config")
+            .location(location)
+            .build();
+        assert_eq!(
+            err.id(),
+            if cfg!(passthrough) {
+                None
+            } else {
+                Some("req-42")
+            }
+        );
+        assert_eq!(
+            err.context_message(),
+            if cfg!(passthrough) {
+                None
+            } else {
+                Some("loading config")
+            }
+        );
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), location.line());
+        }
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn build_at_caller() {
+        let builder = LocatedError::builder(std::io::Error::other("boom"));
+        let (err, line) = (builder.build(), line!());
+        err.assert_located_at(file!(), line);
+        assert_eq!(err.id(), None);
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn build_with_fields() {
+        let err = LocatedError::builder(std::io::Error::other("boom"))
+            .field("user", "alice")
+            .field("retry", 1)
+            .field("retry", 2)
+            .build();
+        assert_eq!(
+            err.fields(),
+            [
+                ("user".into(), "alice".into()),
+                ("retry".into(), "2".into())
+            ]
+        );
+        assert_eq!(err.field("retry"), Some("2"));
+    }
+
+    #[test]
+    #[cfg(capture_backtrace)]
+    fn build_with_backtrace() {
+        let err = LocatedError::builder(std::io::Error::other("boom"))
+            .capture_backtrace()
+            .build();
+        assert!(err.stacktrace().is_some());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::LocatedError;
+use core::error::Error;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+/// [`LocatedError`] which is also hashed and compared by its capture site, the original one of
+/// errors rebuilt with [`LocatedError::from_parts`], see [`LocatedError::source_site_key`].
+/// `LocatedError` itself only hashes and compares the inner error.
+/// ```ignore
+/// let mut seen = HashSet::new();
+/// if seen.insert(ByLocation(err)) {
+///     alert(&seen);
+/// }
+/// ```
+pub struct ByLocation<E: Error>(pub LocatedError<E>);
+
+impl<E: Error> ByLocation<E> {
+    /// the wrapped error
+    pub fn into_inner(self) -> LocatedError<E> {
+        self.0
+    }
+}
+
+impl<E: Error> From<LocatedError<E>> for ByLocation<E> {
+    fn from(err: LocatedError<E>) -> Self {
+        ByLocation(err)
+    }
+}
+
+impl<E: Error> Deref for ByLocation<E> {
+    type Target = LocatedError<E>;
+
+    fn deref(&self) -> &LocatedError<E> {
+        &self.0
+    }
+}
+
+impl<E: Error + Hash> Hash for ByLocation<E> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        let location = self.0.loc();
+        (location.file(), location.line()).hash(state);
+    }
+}
+
+impl<E: Error + PartialEq> PartialEq for ByLocation<E> {
+    fn eq(&self, other: &Self) -> bool {
+        let (location, other_location) = (self.0.loc(), other.0.loc());
+        self.0 == other.0
+            && location.file() == other_location.file()
+            && location.line() == other_location.line()
+    }
+}
+
+impl<E: Error + Eq> Eq for ByLocation<E> {}
+
+impl<E: Error> fmt::Debug for ByLocation<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ByLocation;
+    use crate::LocatedError;
+    use std::collections::HashSet;
+    use thiserror::Error;
+
+    #[derive(Debug, Error, PartialEq, Eq, Hash)]
+    #[error("not found")]
+    struct NotFound;
+
+    #[test]
+    // only the inner error is hashed, not the lazily resolved backtrace of the payload
+    #[allow(clippy::mutable_key_type)]
+    fn dedup() {
+        let errors: Vec<_> = (0..2)
+            .map(|_| LocatedError::from(NotFound))
+            .chain([LocatedError::from(NotFound)])
+            .collect();
+
+        let by_value: HashSet<_> = errors.iter().collect();
+        assert_eq!(by_value.len(), 1);
+
+        let by_location: HashSet<_> = errors.into_iter().map(ByLocation).collect();
+        let sites = if cfg!(passthrough) { 1 } else { 2 };
+        assert_eq!(by_location.len(), sites);
+    }
+}
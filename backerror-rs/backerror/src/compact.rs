@@ -0,0 +1,119 @@
+use crate::LocatedError;
+#[cfg(not(passthrough))]
+use crate::format_options::redact_path;
+use crate::loc::Loc;
+use crate::report::layers;
+use core::error::Error;
+use core::fmt::{self, Write as _};
+
+/// Single-line rendering of a [`LocatedError`], e.g. for line-based log shippers:
+/// ```text
+/// failed to load config: No such file or directory (os error 2) | app::ConfigError | src/main.rs:12 | frames=14
+/// ```
+/// The messages of the `source()` chain are joined with `: `, newlines in them are escaped as `\n`.
+/// The type and location are `-` in passthrough builds, `frames` is `0` without a captured
+/// backtrace. Created by [`LocatedError::fmt_compact`].
+pub struct Compact<'a, E: Error + 'static> {
+    error: &'a LocatedError<E>,
+}
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// Render this error on a single line, `msg | type | file:line | frames=N`, see [`Compact`]
+    pub fn fmt_compact(&self) -> Compact<'_, E> {
+        Compact { error: self }
+    }
+}
+
+impl<E: Error + 'static> fmt::Display for Compact<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let layers = layers(self.error);
+        for (index, (message, _)) in layers.iter().enumerate() {
+            if index > 0 {
+                f.write_str(": ")?;
+            }
+            write_escaped(f, message)?;
+        }
+
+        match layers.first().and_then(|(_, located)| located.as_ref()) {
+            Some(located) => {
+                write!(f, " | {} | ", located.name)?;
+                write_location(f, &located.location)?;
+            }
+            None => f.write_str(" | - | -")?,
+        }
+
+        #[cfg(capture_backtrace)]
+        let frames = self
+            .error
+            .stacktrace()
+            .map_or(0, |stacktrace| stacktrace.len());
+        #[cfg(not(capture_backtrace))]
+        let frames = 0;
+        write!(f, " | frames={frames}")
+    }
+}
+
+impl<E: Error + 'static> fmt::Debug for Compact<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// `file:line`, the file is redacted according to [`crate::FormatOptions`]
+#[cfg(not(passthrough))]
+fn write_location(f: &mut fmt::Formatter<'_>, location: &Loc) -> fmt::Result {
+    write!(f, "{}:{}", redact_path(location.file()), location.line())
+}
+
+#[cfg(passthrough)]
+fn write_location(f: &mut fmt::Formatter<'_>, location: &Loc) -> fmt::Result {
+    write!(f, "{}:{}", location.file(), location.line())
+}
+
+/// `s` with `\n` and `\r` escaped, so that it stays on one line
+fn write_escaped(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    for ch in s.chars() {
+        match ch {
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            ch => f.write_char(ch)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocatedError;
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    #[error("failed to read config")]
+    struct ConfigError(#[from] LocatedError<std::io::Error>);
+
+    #[test]
+    fn compact_on_one_line() {
+        let err = LocatedError::from(std::io::Error::other("line 1\nline 2"));
+        let err = ConfigError::from(err);
+        let (err, line) = (LocatedError::without_backtrace(err), line!());
+
+        let compact = err.fmt_compact().to_string();
+        assert!(!compact.contains('\n'), "{compact}");
+        let message = "failed to read config: line 1\\nline 2";
+        let expected = if cfg!(passthrough) {
+            format!("{message} | - | - | frames=0")
+        } else {
+            let name = core::any::type_name::<ConfigError>();
+            format!("{message} | {name} | {}:{line} | frames=0", file!())
+        };
+        assert_eq!(compact, expected);
+    }
+
+    #[test]
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    fn compact_frames() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        let compact = err.fmt_compact().to_string();
+        assert!(!compact.ends_with("frames=0"), "{compact}");
+    }
+}
@@ -0,0 +1,164 @@
+use crate::LocatedError;
+use core::error::Error;
+use core::fmt;
+use core::panic::Location;
+
+/// Human-readable message layered over a typed error by [`LocatedError::context`], like
+/// `anyhow::Context`. [`fmt::Display`] is the message, `source()` is the error underneath:
+/// ```ignore
+/// fn load(path: &str) -> Result<Config, LocatedError<Context<LocatedError<std::io::Error>>>> {
+///     let text = read(path).map_err(|e| e.context(format!("failed to load {path}")))?;
+///     ...
+/// }
+/// ```
+/// `#[backerror]` types get the same `context` method, wrapping the error itself.
+pub struct Context<E: Error> {
+    message: String,
+    error: E,
+}
+
+impl<E: Error> Context<E> {
+    /// `error` with `message` on top
+    pub fn new(message: impl fmt::Display, error: E) -> Self {
+        Context {
+            message: message.to_string(),
+            error,
+        }
+    }
+
+    /// the context message
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// the error underneath
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// the error underneath, dropping the message
+    pub fn into_inner(self) -> E {
+        self.error
+    }
+}
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// Layer `message` over this error, located at the caller of `context`. The report shows the
+    /// message with its location, then this error with its own.
+    #[track_caller]
+    pub fn context(self, message: impl fmt::Display) -> LocatedError<Context<Self>> {
+        LocatedError::located_at(Context::new(message, self), Location::caller())
+    }
+
+    /// [`LocatedError::context`] with a message built by `f`, e.g. `|| format!(...)`, which is
+    /// only called on the error path, see [`crate::ResultExt::with_context`]
+    #[track_caller]
+    pub fn with_context<M: fmt::Display>(
+        self,
+        f: impl FnOnce() -> M,
+    ) -> LocatedError<Context<Self>> {
+        LocatedError::located_at(Context::new(f(), self), Location::caller())
+    }
+}
+
+/// `context` and `with_context` methods of a `#[backerror]` type, like [`LocatedError::context`]
+/// but wrapping the error itself
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __context_impl {
+    ($($header:tt)*) => {
+        $($header)* {
+            /// Layer `message` over this error, located at the caller of `context`, see
+            /// [`backerror::Context`]
+            #[track_caller]
+            pub fn context(
+                self,
+                message: impl ::core::fmt::Display,
+            ) -> $crate::LocatedError<$crate::Context<Self>>
+            where
+                Self: ::core::error::Error + 'static,
+            {
+                $crate::LocatedError::located_at(
+                    $crate::Context::new(message, self),
+                    ::core::panic::Location::caller(),
+                )
+            }
+
+            /// `context` with a message built by `f`, which is only called on the error path
+            #[track_caller]
+            pub fn with_context<M: ::core::fmt::Display>(
+                self,
+                f: impl ::core::ops::FnOnce() -> M,
+            ) -> $crate::LocatedError<$crate::Context<Self>>
+            where
+                Self: ::core::error::Error + 'static,
+            {
+                $crate::LocatedError::located_at(
+                    $crate::Context::new(f(), self),
+                    ::core::panic::Location::caller(),
+                )
+            }
+        }
+    };
+}
+
+impl<E: Error> fmt::Display for Context<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl<E: Error> fmt::Debug for Context<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("message", &self.message)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl<E: Error + 'static> Error for Context<E> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocatedError;
+
+    #[test]
+    fn context_layers_message() {
+        let (err, io_line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let (err, line) = (err.context(format!("failed to read {}", "a.txt")), line!());
+
+        assert_eq!(err.message(), "failed to read a.txt");
+        assert_eq!((*err).to_string(), "failed to read a.txt");
+        assert_eq!(
+            err.error().to_string(),
+            err.downcast_ref::<LocatedError<std::io::Error>>()
+                .unwrap()
+                .to_string()
+        );
+
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+            err.error().assert_located_at(file!(), io_line);
+
+            let report = err.report().to_string();
+            let context = report.find(&format!("{}:{line}", file!())).unwrap();
+            let io = report.find(&format!("{}:{io_line}", file!())).unwrap();
+            assert!(context < io, "{report}");
+        }
+
+        let (err, line) = (err.with_context(|| "retrying"), line!());
+        assert_eq!(err.message(), "retrying");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+
+        let err = err.into_inner().into_inner(); // "retrying"
+        let err = err.into_inner().into_inner().into_inner();
+        assert_eq!(err.to_string(), "boom");
+    }
+}
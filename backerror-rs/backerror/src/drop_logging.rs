@@ -0,0 +1,127 @@
+use crate::SourceLocation;
+use crate::loc::Loc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+/// Called with the capture location of a [`crate::LocatedError`] dropped without being inspected
+pub type DropHook = fn(&SourceLocation);
+
+static DROP_HOOK: RwLock<DropHook> = RwLock::new(warn_dropped);
+
+fn warn_dropped(location: &SourceLocation) {
+    eprintln!("warning: error captured at {location} was dropped without being inspected");
+}
+
+/// Replace the default hook, which prints a warning to stderr.
+/// ```ignore
+/// backerror::set_drop_hook(|location| log::warn!("unhandled error from {location}"));
+/// ```
+pub fn set_drop_hook(hook: DropHook) {
+    *DROP_HOOK.write().unwrap_or_else(PoisonError::into_inner) = hook;
+}
+
+/// Tracks whether the owning error was inspected (formatted, dereferenced, ...) before drop.
+/// The clones of an error share the flag, the last one dropped reports it unless any of them was
+/// inspected.
+#[derive(Clone)]
+pub(crate) struct DropGuard {
+    location: Loc,
+    /// `None` only while dropped
+    inspected: Option<Arc<AtomicBool>>,
+}
+
+impl DropGuard {
+    pub fn new(location: Loc) -> Self {
+        DropGuard {
+            location,
+            inspected: Some(Arc::new(AtomicBool::new(false))),
+        }
+    }
+
+    /// report drops at `location` from now on
+    pub fn relocate(&mut self, location: Loc) {
+        self.location = location;
+    }
+
+    pub fn inspect(&self) {
+        if let Some(inspected) = &self.inspected {
+            inspected.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        // only the last clone gets the flag
+        let Some(inspected) = self.inspected.take().and_then(Arc::into_inner) else {
+            return;
+        };
+        if !inspected.into_inner() {
+            let hook = *DROP_HOOK.read().unwrap_or_else(PoisonError::into_inner);
+            hook(&SourceLocation::from(&self.location));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DROP_HOOK, DropHook};
+    use crate::{LocatedError, SourceLocation};
+    use std::sync::Mutex;
+
+    static DROPPED: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+    fn record(location: &SourceLocation) {
+        if location.file() == file!() {
+            DROPPED.lock().unwrap().push(location.line());
+        }
+    }
+
+    /// puts back the hook replaced by a test when dropped, also if the test fails
+    struct RestoreHook(DropHook);
+
+    impl Drop for RestoreHook {
+        fn drop(&mut self) {
+            super::set_drop_hook(self.0);
+        }
+    }
+
+    #[test]
+    fn warn_uninspected() {
+        let _restore = RestoreHook(*DROP_HOOK.read().unwrap());
+        super::set_drop_hook(record);
+
+        let (swallowed, swallowed_line) = (LocatedError::from(std::io::Error::other("a")), line!());
+        drop(swallowed);
+
+        let (handled, handled_line) = (LocatedError::from(std::io::Error::other("b")), line!());
+        assert!(!handled.to_string().is_empty());
+        drop(handled);
+
+        let (checked, checked_line) = (LocatedError::from(std::io::Error::other("c")), line!());
+        assert_eq!(checked.kind(), std::io::ErrorKind::Other);
+        drop(checked);
+
+        let (taken, taken_line) = (LocatedError::from(std::io::Error::other("d")), line!());
+        drop(taken.into_inner());
+
+        let (cloned, cloned_line) = (LocatedError::from(core::fmt::Error), line!());
+        drop(cloned.clone());
+        drop(cloned);
+
+        let (shared, shared_line) = (LocatedError::from(core::fmt::Error), line!());
+        let clone = shared.clone();
+        assert!(!clone.to_string().is_empty());
+        drop(shared);
+        drop(clone);
+
+        let dropped = DROPPED.lock().unwrap();
+        assert!(dropped.contains(&swallowed_line));
+        assert!(!dropped.contains(&handled_line));
+        assert!(!dropped.contains(&checked_line));
+        assert!(!dropped.contains(&taken_line));
+        let cloned = dropped.iter().filter(|&&line| line == cloned_line);
+        assert_eq!(cloned.count(), 1);
+        assert!(!dropped.contains(&shared_line));
+    }
+}
@@ -0,0 +1,104 @@
+use crate::{BoxedError, LocatedDynError, LocatedError};
+use core::fmt;
+use core::ops::Deref;
+use core::panic::Location;
+
+/// Type-erased error, like `anyhow::Error`, with the location where it was converted and the
+/// backtrace, for application code which doesn't define an error type for every layer:
+/// ```ignore
+/// fn load() -> Result<Config, backerror::Error> {
+///     let text = std::fs::read_to_string("config.toml")?; // captured here
+///     Ok(toml::from_str(&text)?)
+/// }
+/// ```
+/// Any `E: Error + Send + Sync + 'static` converts into it with `?`. It derefs to a
+/// [`LocatedDynError`] for the location, backtrace and report accessors. Like `anyhow::Error`, it
+/// doesn't implement [`core::error::Error`] itself, which would conflict with that conversion.
+pub struct Error(LocatedDynError);
+
+impl Error {
+    /// the located, boxed error
+    pub fn into_inner(self) -> LocatedDynError {
+        self.0
+    }
+
+    /// The converted error, if it is an `E`. [`LocatedError::downcast_ref`], through `Deref`,
+    /// also looks through its `source()` chain.
+    pub fn downcast<E: core::error::Error + 'static>(self) -> Result<E, Self> {
+        if !self.0.0.is::<E>() {
+            return Err(self);
+        }
+        let boxed = self.0.into_inner().into_inner();
+        Ok(*boxed.downcast::<E>().expect("checked with `is`"))
+    }
+}
+
+impl<E: core::error::Error + Send + Sync + 'static> From<E> for Error {
+    #[track_caller]
+    fn from(err: E) -> Self {
+        Error(LocatedError::located_at(
+            BoxedError(Box::new(err)),
+            Location::caller(),
+        ))
+    }
+}
+
+impl From<Error> for LocatedDynError {
+    fn from(err: Error) -> Self {
+        err.0
+    }
+}
+
+impl From<Error> for Box<dyn core::error::Error + Send + Sync> {
+    fn from(err: Error) -> Self {
+        Box::new(err.0)
+    }
+}
+
+impl Deref for Error {
+    type Target = LocatedDynError;
+
+    fn deref(&self) -> &LocatedDynError {
+        &self.0
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Debug for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Error;
+
+    fn parse(text: &str) -> Result<u32, Error> {
+        Ok(text.parse::<u32>()?)
+    }
+
+    #[test]
+    fn convert_any_error() {
+        let err = parse("x").unwrap_err();
+        assert!(err.downcast_ref::<core::num::ParseIntError>().is_some());
+        assert!(err.downcast_ref::<std::io::Error>().is_none());
+
+        let err = err.downcast::<std::io::Error>().unwrap_err();
+        assert!(err.downcast::<core::num::ParseIntError>().is_ok());
+
+        let (err, line) = (Error::from(std::io::Error::other("boom")), line!());
+        assert_eq!(
+            err.downcast_ref::<std::io::Error>().unwrap().to_string(),
+            "boom"
+        );
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+#[cfg(all(feature = "std", not(passthrough)))]
+use crate::FormatOptions;
+#[cfg(not(passthrough))]
+use core::any::type_name;
+use core::marker::PhantomData;
+#[cfg(all(feature = "std", not(passthrough)))]
+use std::collections::BTreeMap;
+#[cfg(all(feature = "std", not(passthrough)))]
+use std::sync::{PoisonError, RwLock};
+
+/// Stable, human friendly name of an error type, shown instead of [`core::any::type_name`]
+/// in the "by Type" label of [`crate::LocatedError`].
+///
+/// `#[backerror(name = "...")]` implements it. The name is resolved at compile time where the
+/// error is wrapped: by the `From` impls which `#[backerror]` generates for the `#[from]` fields
+/// of the type, e.g. `Config(#[from] MyError)`, and by [`crate::LocatedError::named`] for the
+/// errors wrapped by hand:
+/// ```ignore
+/// impl ErrorName for MyError {
+///     fn error_name() -> &'static str {
+///         "MyError"
+///     }
+/// }
+///
+/// let err = LocatedError::from(MyError::Timeout).named();
+/// ```
+/// Without `std`, and in passthrough builds, the name isn't kept and the label is the type name.
+pub trait ErrorName {
+    fn error_name() -> &'static str;
+}
+
+/// `T` for [`__error_name!`], whose method resolves to [`__Named`] if `T` implements
+/// [`ErrorName`], else to [`__Unnamed`] (autoref specialization)
+#[doc(hidden)]
+pub struct __NameOf<T: ?Sized>(PhantomData<fn() -> *const T>);
+
+impl<T: ?Sized> __NameOf<T> {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self(PhantomData)
+    }
+}
+
+#[doc(hidden)]
+pub trait __Named {
+    fn __error_name(&self) -> Option<&'static str>;
+}
+
+impl<T: ErrorName + ?Sized> __Named for &__NameOf<T> {
+    fn __error_name(&self) -> Option<&'static str> {
+        Some(T::error_name())
+    }
+}
+
+#[doc(hidden)]
+pub trait __Unnamed {
+    fn __error_name(&self) -> Option<&'static str>;
+}
+
+impl<T: ?Sized> __Unnamed for __NameOf<T> {
+    fn __error_name(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+/// [`ErrorName::error_name`] of `$ty` if it implements [`ErrorName`], else `None`, for the `From`
+/// impls generated by `#[backerror]`. `$ty` must be concrete, a generic parameter is `None`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __error_name {
+    ($ty:ty) => {{
+        #[allow(unused_imports)]
+        use $crate::{__Named as _, __Unnamed as _};
+        (&&$crate::__NameOf::<$ty>::new()).__error_name()
+    }};
+}
+
+/// shortened type names which are not a suffix of the full name, each distinct one is leaked once
+#[cfg(all(feature = "std", not(passthrough)))]
+static SHORT_NAMES: RwLock<BTreeMap<&'static str, &'static str>> = RwLock::new(BTreeMap::new());
+
+/// `name` of a [`crate::LocatedError`], see [`ErrorName`], else [`core::any::type_name`] of `E`,
+/// shortened if [`FormatOptions::short_type_names`] is set
+#[cfg(all(feature = "std", not(passthrough)))]
+pub(crate) fn error_name<E: ?Sized>(name: Option<&'static str>) -> &'static str {
+    if let Some(name) = name {
+        return name;
+    }
+    let name = type_name::<E>();
+    if FormatOptions::with_current(|options| options.short_type_names) {
+        short_name(name)
+    } else {
+        name
+    }
+}
+
+/// `'static` [`short_type_name`] of `name`
+#[cfg(all(feature = "std", not(passthrough)))]
+fn short_name(name: &'static str) -> &'static str {
+    if let Some(short) = SHORT_NAMES
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get(name)
+    {
+        return short;
+    }
+    let short = short_type_name(name);
+    // plain paths are cut down to a suffix of the name, no need to leak those
+    let short = match name.strip_suffix(short.as_str()) {
+        Some(prefix) => &name[prefix.len()..],
+        None => Box::leak(short.into_boxed_str()),
+    };
+    SHORT_NAMES
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(name, short);
+    short
+}
+
+/// `name` with every path cut down to its last segment, including generic arguments,
+/// e.g. `LocatedError<Error>` for `backerror::located_error::LocatedError<std::io::error::Error>`
+#[cfg(all(feature = "std", not(passthrough)))]
+fn short_type_name(name: &str) -> String {
+    let mut short = String::with_capacity(name.len());
+    // start of the current path in `short`
+    let mut path_start = 0;
+    let mut chars = name.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let in_path = short[path_start..]
+            .chars()
+            .next_back()
+            .is_some_and(|last| last.is_alphanumeric() || last == '_');
+        if ch == ':' && chars.peek() == Some(&':') && in_path {
+            // drop the leading segment
+            chars.next();
+            short.truncate(path_start);
+            continue;
+        }
+        short.push(ch);
+        if !(ch.is_alphanumeric() || ch == '_') {
+            path_start = short.len();
+        }
+    }
+    short
+}
+
+#[cfg(all(not(feature = "std"), not(passthrough)))]
+pub(crate) fn error_name<E: ?Sized>(_name: Option<&'static str>) -> &'static str {
+    type_name::<E>()
+}
+
+#[cfg(all(test, feature = "std", not(passthrough)))]
+mod tests {
+    use super::short_type_name;
+
+    #[test]
+    fn short_type_names() {
+        assert_eq!(short_type_name("std::io::error::Error"), "Error");
+        assert_eq!(
+            short_type_name("backerror::located_error::LocatedError<std::io::error::Error>"),
+            "LocatedError<Error>"
+        );
+        assert_eq!(
+            short_type_name(
+                "alloc::boxed::Box<dyn core::error::Error + core::marker::Send + core::marker::Sync>"
+            ),
+            "Box<dyn Error + Send + Sync>"
+        );
+        assert_eq!(
+            short_type_name("app::Pair<(u8, &app::Item), [app::Item; 2]>"),
+            "Pair<(u8, &Item), [Item; 2]>"
+        );
+        assert_eq!(
+            short_type_name("<T as app::Trait>::Error"),
+            "<T as Trait>::Error"
+        );
+        assert_eq!(short_type_name("MyError"), "MyError");
+    }
+}
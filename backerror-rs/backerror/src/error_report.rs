@@ -0,0 +1,211 @@
+use crate::LocatedError;
+use crate::SourceLocation;
+#[cfg(not(passthrough))]
+use crate::format_options::redact_path;
+use crate::report::{Located, layers, write_report};
+use core::error::Error;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Owned, serializable counterpart of [`LocatedError::report`], e.g. to ship error reports
+/// across process boundaries and render them centrally. Created by [`LocatedError::to_report`].
+///
+/// `type` and `location` are `None` for errors which are not located. Files are redacted according
+/// to [`crate::FormatOptions`] when the report is created. [`fmt::Display`] has the layout of
+/// [`crate::Report`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub type_name: Option<String>,
+    pub location: Option<SourceLocation>,
+    /// documentation or runbook URL attached with [`LocatedError::with_url`]
+    #[serde(default)]
+    pub url: Option<String>,
+    /// fields attached with [`LocatedError::with_field`]
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    /// the `source()` chain, outermost first
+    pub causes: Vec<ReportCause>,
+    /// backtrace frames, empty if no backtrace was captured
+    pub frames: Vec<ReportFrame>,
+}
+
+/// One error of the `source()` chain of an [`ErrorReport`]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportCause {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub type_name: Option<String>,
+    pub location: Option<SourceLocation>,
+}
+
+/// One backtrace frame of an [`ErrorReport`], `file` is empty and `line` is `0` when unknown
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportFrame {
+    pub func: String,
+    pub file: String,
+    pub line: u32,
+}
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// Owned report of this error, its `source()` chain and backtrace, see [`ErrorReport`]
+    pub fn to_report(&self) -> ErrorReport {
+        let mut causes = layers(self).into_iter().map(cause);
+        let ReportCause {
+            message,
+            type_name,
+            location,
+        } = causes.next().expect("a report has at least one layer");
+
+        #[cfg(capture_backtrace)]
+        let frames = self
+            .stacktrace()
+            .map(|stacktrace| stacktrace.frames)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|frame| ReportFrame {
+                func: frame.func,
+                file: redacted(&frame.file),
+                line: frame.line,
+            })
+            .collect();
+        #[cfg(not(capture_backtrace))]
+        let frames = Vec::new();
+
+        ErrorReport {
+            message,
+            type_name,
+            location,
+            url: self.url().map(str::to_string),
+            fields: self.fields().iter().cloned().collect(),
+            causes: causes.collect(),
+            frames,
+        }
+    }
+}
+
+/// [`ReportCause`] of one layer of the `source()` chain
+fn cause((message, located): (String, Option<Located>)) -> ReportCause {
+    ReportCause {
+        message,
+        type_name: located.as_ref().map(|located| located.name.to_string()),
+        location: located.map(|located| {
+            let location = SourceLocation::from(&located.location);
+            SourceLocation::new(
+                redacted(location.file()),
+                location.line(),
+                location.column(),
+            )
+        }),
+    }
+}
+
+/// `file` redacted according to [`crate::FormatOptions`]
+#[cfg(not(passthrough))]
+fn redacted(file: &str) -> String {
+    redact_path(file).into_owned()
+}
+
+#[cfg(passthrough)]
+fn redacted(file: &str) -> String {
+    file.to_string()
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = (&self.message, self.location.as_ref());
+        let causes = self
+            .causes
+            .iter()
+            .map(|cause| (&cause.message, cause.location.as_ref()));
+        let layers = [head].into_iter().chain(causes).map(|(message, location)| {
+            (message, location.map(|location| format!("at {location}")))
+        });
+        let frames = (!self.frames.is_empty()).then_some(&self.frames);
+        write_report(f, layers, frames)
+    }
+}
+
+/// `{func} ({file}:{line})`, like [`crate::StackTraceFrame`]
+impl fmt::Display for ReportFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.file.is_empty() {
+            write!(f, "{}", self.func)
+        } else {
+            write!(f, "{} ({}:{})", self.func, self.file, self.line)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorReport, ReportFrame};
+    use crate::LocatedError;
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    enum ConfigError {
+        #[error("failed to read config")]
+        Read(#[from] LocatedError<std::io::Error>),
+    }
+
+    #[test]
+    fn render_like_report() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        let err = LocatedError::from(ConfigError::from(err));
+
+        let report = err.to_report();
+        assert_eq!(report.message, "failed to read config");
+        assert_eq!(report.causes.len(), 1);
+        assert_eq!(report.causes[0].message, "boom");
+        assert_eq!(report.to_string(), err.report().to_string());
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn report_fields() {
+        let err = LocatedError::from(std::io::Error::other("boom")).with_field("retry", 3);
+        let report = err.to_report();
+        assert_eq!(report.fields.get("retry").map(String::as_str), Some("3"));
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""fields":{"retry":"3"}"#), "{json}");
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn report_url() {
+        let err = LocatedError::from(std::io::Error::other("boom"))
+            .with_url("https://runbooks.example.com/io");
+        let report = err.to_report();
+        assert_eq!(
+            report.url.as_deref(),
+            Some("https://runbooks.example.com/io")
+        );
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(
+            json.contains(r#""url":"https://runbooks.example.com/io""#),
+            "{json}"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut report = LocatedError::from(std::io::Error::other("boom")).to_report();
+        report.frames = vec![ReportFrame {
+            func: "app::main".to_string(),
+            file: "src/main.rs".to_string(),
+            line: 5,
+        }];
+
+        let json = serde_json::to_string(&report).unwrap();
+        let parsed: ErrorReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, report);
+        assert!(
+            parsed
+                .to_string()
+                .ends_with("\n\nStack backtrace:\n    at app::main (src/main.rs:5)")
+        );
+    }
+}
@@ -0,0 +1,161 @@
+#[cfg(feature = "std")]
+use crate::Context;
+use crate::LocatedError;
+use core::error::Error;
+#[cfg(feature = "std")]
+use core::fmt;
+#[cfg(feature = "std")]
+use core::panic::Location;
+
+/// Extension methods for [`Result`], to attach locations at call sites without `#[backerror]`.
+/// ```ignore
+/// use backerror::{LocatedError, ResultExt};
+///
+/// fn read_config() -> Result<String, LocatedError<std::io::Error>> {
+///     std::fs::read_to_string("config.toml").wrap_located()
+/// }
+/// ```
+pub trait ResultExt<T, E: Error> {
+    /// wrap the error into a [`LocatedError`], capturing the caller's location
+    fn wrap_located(self) -> Result<T, LocatedError<E>>;
+
+    /// [`ResultExt::wrap_located`] without capturing a backtrace, see
+    /// [`LocatedError::without_backtrace`]
+    fn wrap_located_without_backtrace(self) -> Result<T, LocatedError<E>>;
+
+    /// Layer a message built by `f` over the error, located at the caller, see
+    /// [`crate::Context`]. `f` is only called on the error path, so formatting the message costs
+    /// nothing in hot loops which succeed:
+    /// ```ignore
+    /// let data = std::fs::read(path).with_context(|| format!("failed to read {path}"))?;
+    /// ```
+    #[cfg(feature = "std")]
+    fn with_context<M: fmt::Display>(
+        self,
+        f: impl FnOnce() -> M,
+    ) -> Result<T, LocatedError<Context<E>>>
+    where
+        E: 'static;
+}
+
+impl<T, E: Error> ResultExt<T, E> for Result<T, E> {
+    #[track_caller]
+    fn wrap_located(self) -> Result<T, LocatedError<E>> {
+        match self {
+            Ok(v) => Ok(v),
+            // not `map_err`, the closure would be the caller
+            Err(e) => Err(LocatedError::wrap(e)),
+        }
+    }
+
+    #[track_caller]
+    fn wrap_located_without_backtrace(self) -> Result<T, LocatedError<E>> {
+        match self {
+            Ok(v) => Ok(v),
+            Err(e) => Err(LocatedError::without_backtrace(e)),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[track_caller]
+    fn with_context<M: fmt::Display>(
+        self,
+        f: impl FnOnce() -> M,
+    ) -> Result<T, LocatedError<Context<E>>>
+    where
+        E: 'static,
+    {
+        match self {
+            Ok(v) => Ok(v),
+            Err(e) => Err(LocatedError::located_at(
+                Context::new(f(), e),
+                Location::caller(),
+            )),
+        }
+    }
+}
+
+/// Await a future returning a [`Result`], wrapping the error into a [`LocatedError`] located at
+/// the `located_await!` call site.
+///
+/// `#[track_caller]` is not supported on `async fn`, and the location seen by a `#[track_caller]`
+/// function returning a future is lost once the future is polled. The location is therefore taken
+/// at the call site, before awaiting:
+/// ```ignore
+/// async fn load() -> Result<String, LocatedError<std::io::Error>> {
+///     located_await!(tokio::fs::read_to_string("config.toml"))
+/// }
+/// ```
+#[macro_export]
+macro_rules! located_await {
+    ($future:expr) => {{
+        let location = ::core::panic::Location::caller();
+        match $future.await {
+            ::core::result::Result::Ok(v) => ::core::result::Result::Ok(v),
+            ::core::result::Result::Err(e) => {
+                ::core::result::Result::Err($crate::LocatedError::located_at(e, location))
+            }
+        }
+    }};
+}
+
+#[cfg(all(test, not(passthrough)))]
+mod tests {
+    use super::ResultExt;
+    use core::{error::Error, fmt};
+
+    /// error type of another crate, which can't be annotated with `#[backerror]`
+    mod foreign {
+        use super::*;
+
+        #[derive(Debug)]
+        pub struct ParseError {
+            pub line: usize,
+        }
+
+        impl fmt::Display for ParseError {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "parse error at line {}", self.line)
+            }
+        }
+
+        impl Error for ParseError {}
+
+        pub fn parse(line: usize) -> Result<(), ParseError> {
+            Err(ParseError { line })
+        }
+    }
+
+    #[test]
+    fn wrap_located() {
+        let (result, line) = (foreign::parse(7).wrap_located(), line!());
+        let err = result.unwrap_err();
+        assert_eq!(err.site_key(), (file!(), line));
+        assert_eq!(err.line, 7);
+        assert!(err.to_string().starts_with("parse error at line 7"));
+
+        assert!(Ok::<(), foreign::ParseError>(()).wrap_located().is_ok());
+    }
+
+    #[test]
+    fn wrap_located_without_backtrace() {
+        let (result, line) = (foreign::parse(7).wrap_located_without_backtrace(), line!());
+        let err = result.unwrap_err();
+        assert_eq!(err.site_key(), (file!(), line));
+        #[cfg(capture_backtrace)]
+        assert!(err.backtrace().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn with_context() {
+        let (result, line) = (foreign::parse(7).with_context(|| "parsing"), line!());
+        let err = result.unwrap_err();
+        err.assert_located_at(file!(), line);
+        assert_eq!(err.message(), "parsing");
+        assert_eq!(err.error().line, 7);
+
+        let ok = Ok::<(), foreign::ParseError>(()).with_context(|| -> String { unreachable!() });
+        assert!(ok.is_ok());
+    }
+}
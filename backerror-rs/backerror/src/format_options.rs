@@ -0,0 +1,148 @@
+use std::borrow::Cow;
+use std::sync::{PoisonError, RwLock};
+
+/// Placeholder of redacted path components
+pub const REDACTED: &str = "<redacted>";
+
+/// Directories marking the crate-relative root of a source path
+const ROOT_MARKERS: [&str; 4] = ["src", "tests", "examples", "benches"];
+
+/// How file paths of locations and backtrace frames are rendered
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PathRedaction {
+    /// paths are rendered as captured
+    #[default]
+    Off,
+    /// absolute paths are cut down to the crate-relative root,
+    /// e.g. `/home/me/project/src/net.rs` is rendered as `<redacted>/src/net.rs`
+    Redact,
+    /// paths starting with one of the prefixes are rendered as captured, all others are redacted
+    AllowList(Vec<String>),
+}
+
+impl PathRedaction {
+    /// render `path`
+    pub fn apply<'a>(&self, path: &'a str) -> Cow<'a, str> {
+        match self {
+            PathRedaction::Off => Cow::Borrowed(path),
+            PathRedaction::Redact if !is_absolute(path) => Cow::Borrowed(path),
+            PathRedaction::Redact => Cow::Owned(redact(path)),
+            PathRedaction::AllowList(prefixes)
+                if prefixes
+                    .iter()
+                    .any(|prefix| path.starts_with(prefix.as_str())) =>
+            {
+                Cow::Borrowed(path)
+            }
+            PathRedaction::AllowList(_) => Cow::Owned(redact(path)),
+        }
+    }
+}
+
+/// `/...`, `\\...` or `C:...`
+fn is_absolute(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || path.as_bytes().get(1) == Some(&b':')
+}
+
+/// replace everything before the crate-relative root, or before the file name if there is none
+fn redact(path: &str) -> String {
+    let components: Vec<&str> = path.split(['/', '\\']).collect();
+    let root = components
+        .iter()
+        .rposition(|component| ROOT_MARKERS.contains(component))
+        .unwrap_or(components.len() - 1);
+    format!("{REDACTED}/{}", components[root..].join("/"))
+}
+
+/// Options applied when formatting errors.
+/// ```ignore
+/// FormatOptions {
+///     path_redaction: PathRedaction::Redact,
+///     ..Default::default()
+/// }
+/// .install();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub path_redaction: PathRedaction,
+
+    /// append ` [at {top user frame}]` to `Display`, if a backtrace was captured
+    pub display_top_frame: bool,
+
+    /// print only the last path segment of type names, e.g. `LocatedError<Error>` instead of
+    /// `backerror::located_error::LocatedError<std::io::error::Error>`.
+    /// [`crate::LocatedError::type_name`] still returns the full name.
+    pub short_type_names: bool,
+}
+
+static CURRENT: RwLock<FormatOptions> = RwLock::new(FormatOptions {
+    path_redaction: PathRedaction::Off,
+    display_top_frame: false,
+    short_type_names: false,
+});
+
+impl FormatOptions {
+    /// use these options for all errors formatted from now on
+    pub fn install(self) {
+        *CURRENT.write().unwrap_or_else(PoisonError::into_inner) = self;
+    }
+
+    /// options currently in use
+    pub fn current() -> Self {
+        Self::with_current(Clone::clone)
+    }
+
+    pub(crate) fn with_current<R>(f: impl FnOnce(&FormatOptions) -> R) -> R {
+        f(&CURRENT.read().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+/// render `path` according to the options currently in use
+pub(crate) fn redact_path(path: &str) -> Cow<'_, str> {
+    FormatOptions::with_current(|options| match options.path_redaction.apply(path) {
+        Cow::Borrowed(_) => Cow::Borrowed(path),
+        Cow::Owned(redacted) => Cow::Owned(redacted),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PathRedaction;
+
+    #[test]
+    fn redact_absolute_paths() {
+        let redaction = PathRedaction::Redact;
+        assert_eq!(
+            redaction.apply("/home/me/project/src/net.rs"),
+            "<redacted>/src/net.rs"
+        );
+        assert_eq!(
+            redaction.apply(r"C:\Users\me\project\tests\example.rs"),
+            "<redacted>/tests/example.rs"
+        );
+        assert_eq!(
+            redaction.apply("/rustc/254b59607d44/library/core/src/ops/function.rs"),
+            "<redacted>/src/ops/function.rs"
+        );
+        assert_eq!(
+            redaction.apply("/tmp/generated.rs"),
+            "<redacted>/generated.rs"
+        );
+        assert_eq!(redaction.apply("./src/main.rs"), "./src/main.rs");
+        assert_eq!(PathRedaction::Off.apply("/tmp/a.rs"), "/tmp/a.rs");
+    }
+
+    #[test]
+    fn allow_list() {
+        let redaction = PathRedaction::AllowList(vec!["backerror/".to_string()]);
+        assert_eq!(
+            redaction.apply("backerror/src/lib.rs"),
+            "backerror/src/lib.rs"
+        );
+        assert_eq!(
+            redaction.apply("/home/me/.cargo/registry/src/index/foo-1.0/src/lib.rs"),
+            "<redacted>/src/lib.rs"
+        );
+        assert_eq!(redaction.apply("other/src/lib.rs"), "<redacted>/src/lib.rs");
+    }
+}
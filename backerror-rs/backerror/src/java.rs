@@ -0,0 +1,156 @@
+use crate::LocatedError;
+#[cfg(not(passthrough))]
+use crate::format_options::redact_path;
+use crate::loc::Loc;
+use crate::report::{Located, layers};
+#[cfg(capture_backtrace)]
+use crate::stacktrace::StackTraceFrame;
+use core::error::Error;
+use std::fmt::Write;
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// Render this error in Java stack trace syntax, for log tooling which expects it:
+    /// ```text
+    /// app::ConfigError: failed to read config
+    ///     at app::config::load(src/config.rs:30)
+    ///     at app::main(src/main.rs:12)
+    /// Caused by: std::io::Error: No such file or directory (os error 2)
+    ///     at std::io::Error(src/config.rs:30)
+    /// ```
+    /// Frames are indented with a tab. The backtrace frames follow the first line; without a
+    /// backtrace, and for the located causes, the capture location is listed as a frame named
+    /// after the error type. Causes which are not located have no frames.
+    pub fn java_format(&self) -> String {
+        #[cfg(capture_backtrace)]
+        let frames = self
+            .stacktrace()
+            .map(|stacktrace| stacktrace.frames.iter().map(java_frame).collect())
+            .unwrap_or_default();
+        #[cfg(not(capture_backtrace))]
+        let frames = Vec::new();
+        write_java(&layers(self), frames)
+    }
+}
+
+/// `{func}({file}:{line})`, or `{func}(Unknown Source)` if the file is unknown
+#[cfg(capture_backtrace)]
+fn java_frame(frame: &StackTraceFrame) -> String {
+    if frame.file.is_empty() {
+        format!("{}(Unknown Source)", frame.func)
+    } else {
+        format!(
+            "{}({}:{})",
+            frame.func,
+            redact_path(&frame.file),
+            frame.line
+        )
+    }
+}
+
+/// `{name}({file}:{line})`
+#[cfg(not(passthrough))]
+fn location_frame(location: &Loc, name: &str) -> String {
+    format!(
+        "{name}({}:{})",
+        redact_path(location.file()),
+        location.line()
+    )
+}
+
+#[cfg(passthrough)]
+fn location_frame(location: &Loc, name: &str) -> String {
+    format!("{name}({}:{})", location.file(), location.line())
+}
+
+/// Java-style stack trace of `layers`, `frames` are the backtrace of the first layer
+fn write_java(layers: &[(String, Option<Located>)], mut frames: Vec<String>) -> String {
+    let mut output = String::new();
+    for (index, (message, located)) in layers.iter().enumerate() {
+        if index > 0 {
+            output.push_str("\nCaused by: ");
+        }
+        match located {
+            Some(located) => write!(output, "{}: {message}", located.name).unwrap(),
+            None => output.push_str(message),
+        }
+
+        let frames = match located {
+            Some(_) if index == 0 && !frames.is_empty() => core::mem::take(&mut frames),
+            Some(located) => vec![location_frame(&located.location, located.name)],
+            None => Vec::new(),
+        };
+        for frame in frames {
+            write!(output, "\n\tat {frame}").unwrap();
+        }
+    }
+    output
+}
+
+#[cfg(all(test, not(passthrough)))]
+mod tests {
+    use super::write_java;
+    use crate::LocatedError;
+    use crate::SourceLocation;
+    use crate::loc::Loc;
+    use crate::report::Located;
+
+    fn located(file: &str, line: u32, name: &'static str) -> Option<Located> {
+        let location = Loc::owned(SourceLocation::new(file, line, 9));
+        Some(Located { location, name })
+    }
+
+    #[test]
+    fn java_layout() {
+        let layers = [
+            (
+                "failed to start".to_string(),
+                located("src/main.rs", 12, "app::AppError"),
+            ),
+            (
+                "failed to read config".to_string(),
+                located("src/config.rs", 30, "app::ConfigError"),
+            ),
+            ("No such file or directory (os error 2)".to_string(), None),
+        ];
+        let frames = vec![
+            "app::run(src/main.rs:12)".to_string(),
+            "app::main(src/main.rs:5)".to_string(),
+            "std::rt::lang_start(Unknown Source)".to_string(),
+        ];
+
+        assert_eq!(
+            write_java(&layers, frames),
+            "app::AppError: failed to start\n\
+             \tat app::run(src/main.rs:12)\n\
+             \tat app::main(src/main.rs:5)\n\
+             \tat std::rt::lang_start(Unknown Source)\n\
+             Caused by: app::ConfigError: failed to read config\n\
+             \tat app::ConfigError(src/config.rs:30)\n\
+             Caused by: No such file or directory (os error 2)"
+        );
+        assert_eq!(
+            write_java(&layers[1..], Vec::new()),
+            "app::ConfigError: failed to read config\n\
+             \tat app::ConfigError(src/config.rs:30)\n\
+             Caused by: No such file or directory (os error 2)"
+        );
+    }
+
+    #[test]
+    fn java_format_located() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let java = err.java_format();
+        let mut lines = java.lines();
+        let name = core::any::type_name::<std::io::Error>();
+        assert_eq!(lines.next(), Some(format!("{name}: boom").as_str()));
+        let frames: Vec<_> = lines.collect();
+        assert!(!frames.is_empty());
+        assert!(
+            frames.iter().all(|frame| frame.starts_with("\tat ")),
+            "{java}"
+        );
+        if cfg!(not(capture_backtrace)) {
+            assert_eq!(frames, [format!("\tat {name}({}:{line})", file!())]);
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use crate::LocatedError;
+#[cfg(not(passthrough))]
+use crate::format_options::redact_path;
+use crate::loc::Loc;
+use crate::report::{Located, layers};
+use core::error::Error;
+use core::fmt::{self, Write as _};
+use std::io;
+
+/// JSON rendering of a [`LocatedError`] and its `source()` chain, one object on one line:
+/// ```text
+/// {"message":"failed to load config","type":"app::ConfigError",
+///  "location":{"file":"src/main.rs","line":12,"column":5},"url":null,"fields":{"user_id":"42"},
+///  "causes":[{"message":"No such file or directory (os error 2)","type":"std::io::error::Error",
+///  "location":{"file":"src/config.rs","line":30,"column":9}}],
+///  "frames":[{"func":"app::config::load","file":"src/config.rs","line":30}]}
+/// ```
+/// `type` and `location` are `null` for causes which are not located, `url` and `fields` are the
+/// ones attached with [`LocatedError::with_url`] and [`LocatedError::with_field`], `frames` is empty without a captured backtrace. Created by [`LocatedError::json`].
+pub struct Json<'a, E: Error + 'static> {
+    error: &'a LocatedError<E>,
+}
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// Render this error as JSON, e.g. for structured logs, see [`Json`]
+    pub fn json(&self) -> Json<'_, E> {
+        Json { error: self }
+    }
+
+    /// [`LocatedError::json`] as a `String`
+    pub fn to_json_string(&self) -> String {
+        self.json().to_string()
+    }
+
+    /// Stream [`LocatedError::report`] to `w`, without formatting it into a `String` first
+    pub fn write_report<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        stream(w, self.report())
+    }
+
+    /// Stream [`LocatedError::json`] to `w`, without formatting it into a `String` first
+    pub fn write_json<W: io::Write + ?Sized>(&self, w: &mut W) -> io::Result<()> {
+        stream(w, self.json())
+    }
+}
+
+/// write `display` to `w` piece by piece, as formatted
+fn stream<W: io::Write + ?Sized>(w: &mut W, display: impl fmt::Display) -> io::Result<()> {
+    write!(w, "{display}")
+}
+
+impl<E: Error + 'static> fmt::Display for Json<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut layers = layers(self.error).into_iter();
+        f.write_char('{')?;
+        if let Some((message, located)) = layers.next() {
+            write_layer(f, &message, located)?;
+        }
+
+        f.write_str(",\"url\":")?;
+        match self.error.url() {
+            Some(url) => write_string(f, url)?,
+            None => f.write_str("null")?,
+        }
+
+        f.write_str(",\"fields\":{")?;
+        for (index, (key, value)) in self.error.fields().iter().enumerate() {
+            if index > 0 {
+                f.write_char(',')?;
+            }
+            write_string(f, key)?;
+            f.write_char(':')?;
+            write_string(f, value)?;
+        }
+
+        f.write_str("},\"causes\":[")?;
+        for (index, (message, located)) in layers.enumerate() {
+            if index > 0 {
+                f.write_char(',')?;
+            }
+            f.write_char('{')?;
+            write_layer(f, &message, located)?;
+            f.write_char('}')?;
+        }
+
+        f.write_str("],\"frames\":[")?;
+        #[cfg(capture_backtrace)]
+        if let Some(stacktrace) = self.error.stacktrace() {
+            for (index, frame) in stacktrace.frames.iter().enumerate() {
+                if index > 0 {
+                    f.write_char(',')?;
+                }
+                f.write_str("{\"func\":")?;
+                write_string(f, &frame.func)?;
+                f.write_str(",\"file\":")?;
+                write_file(f, &frame.file)?;
+                write!(f, ",\"line\":{}}}", frame.line)?;
+            }
+        }
+        f.write_str("]}")
+    }
+}
+
+impl<E: Error + 'static> fmt::Debug for Json<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// `"message":..,"type":..,"location":..` of one layer
+fn write_layer(f: &mut fmt::Formatter<'_>, message: &str, located: Option<Located>) -> fmt::Result {
+    f.write_str("\"message\":")?;
+    write_string(f, message)?;
+    f.write_str(",\"type\":")?;
+    match &located {
+        Some(located) => write_string(f, located.name)?,
+        None => f.write_str("null")?,
+    }
+    f.write_str(",\"location\":")?;
+    match located {
+        Some(located) => write_location(f, &located.location),
+        None => f.write_str("null"),
+    }
+}
+
+fn write_location(f: &mut fmt::Formatter<'_>, location: &Loc) -> fmt::Result {
+    f.write_str("{\"file\":")?;
+    write_file(f, location.file())?;
+    write!(
+        f,
+        ",\"line\":{},\"column\":{}}}",
+        location.line(),
+        location.column()
+    )
+}
+
+/// `file` as a JSON string, redacted according to [`crate::FormatOptions`]
+#[cfg(not(passthrough))]
+fn write_file(f: &mut fmt::Formatter<'_>, file: &str) -> fmt::Result {
+    write_string(f, &redact_path(file))
+}
+
+#[cfg(passthrough)]
+fn write_file(f: &mut fmt::Formatter<'_>, file: &str) -> fmt::Result {
+    write_string(f, file)
+}
+
+/// `s` as a JSON string, with quotes, backslashes and control characters escaped
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    let mut start = 0;
+    for (index, ch) in s.char_indices() {
+        let escaped = match ch {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            ch if ch.is_control() => None,
+            _ => continue,
+        };
+        f.write_str(&s[start..index])?;
+        match escaped {
+            Some(escaped) => f.write_str(escaped)?,
+            None => write!(f, "\\u{:04x}", ch as u32)?,
+        }
+        start = index + ch.len_utf8();
+    }
+    f.write_str(&s[start..])?;
+    f.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocatedError;
+
+    #[test]
+    fn write_report() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        let mut output = Vec::new();
+        err.write_report(&mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), err.report().to_string());
+    }
+
+    #[test]
+    fn write_json() {
+        let inner = std::io::Error::other("say \"boom\"\n\u{1}");
+        let (err, line) = (LocatedError::from(inner), line!());
+        let mut output = Vec::new();
+        err.write_json(&mut output).unwrap();
+        let json = String::from_utf8(output).unwrap();
+        assert_eq!(json, err.to_json_string());
+
+        let name = core::any::type_name::<std::io::Error>();
+        let head = if cfg!(passthrough) {
+            r#"{"message":"say \"boom\"\n\u0001","type":null,"location":null"#.to_string()
+        } else {
+            format!(
+                r#"{{"message":"say \"boom\"\n\u0001","type":"{name}","location":{{"file":"{}","line":{line},"#,
+                file!()
+            )
+        };
+        assert!(json.starts_with(&head), "{json}");
+        assert!(
+            json.contains(r#","url":null,"fields":{},"causes":[],"frames":["#),
+            "{json}"
+        );
+        assert!(json.ends_with("]}"), "{json}");
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn json_fields() {
+        let err = LocatedError::from(std::io::Error::other("boom")).with_field("user \"a\"", 42);
+        let json = err.to_json_string();
+        assert!(json.contains(r#","fields":{"user \"a\"":"42"},"#), "{json}");
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn json_url() {
+        let err = LocatedError::from(std::io::Error::other("boom")).with_url("https://a/\"b\"");
+        let json = err.to_json_string();
+        assert!(json.contains(r#","url":"https://a/\"b\"","#), "{json}");
+    }
+}
@@ -1,11 +1,133 @@
 //! Patch for `thiserror`
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "provide", feature(error_generic_member_access))]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "anyhow")]
+mod anyhow_error;
+
+#[cfg(feature = "std")]
+mod boxed_error;
+
+#[cfg(feature = "std")]
+mod builder;
+
+mod by_location;
+
+#[cfg(feature = "std")]
+mod compact;
+
+#[cfg(feature = "std")]
+mod context;
+
+#[cfg(all(feature = "drop_logging", not(passthrough)))]
+mod drop_logging;
+
+#[cfg(feature = "std")]
+mod dyn_error;
+
+mod error_name;
+
+#[cfg(feature = "serde")]
+mod error_report;
+
+mod ext;
+mod loc;
+mod locate_each;
 mod located_error;
 
-#[cfg(feature = "backtrace")]
+#[cfg(feature = "std")]
+mod format_options;
+
+#[cfg(feature = "std")]
+mod java;
+
+#[cfg(feature = "std")]
+mod json;
+
+#[cfg(feature = "std")]
+mod main_error;
+
+#[cfg(feature = "std")]
+mod message;
+
+#[cfg(all(feature = "opentelemetry", not(passthrough)))]
+mod otel;
+
+#[cfg(feature = "std")]
+mod report;
+
+#[cfg(feature = "serde")]
+mod serialize;
+
+#[cfg(feature = "std")]
+mod site;
+
+#[cfg(feature = "std")]
+mod source_location;
+
+#[cfg(feature = "std")]
 mod stacktrace;
 
-pub use backerror_macros::backerror;
-pub use located_error::LocatedError;
+/// `context` of `#[backerror]` types is only generated with `std`, see `Context`
+#[cfg(not(feature = "std"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __context_impl {
+    ($($header:tt)*) => {};
+}
+
+/// `future`, with its output type given for `?` in an `async` block, used by `#[locate]`
+#[doc(hidden)]
+pub fn __expect_output<T, F: Future<Output = T>>(future: F) -> F {
+    future
+}
+
+#[cfg(feature = "anyhow")]
+pub use anyhow_error::AnyhowError;
+#[cfg(feature = "std")]
+pub use backerror_macros::main;
+pub use backerror_macros::{BackError, backerror, locate};
+#[cfg(feature = "std")]
+pub use boxed_error::{BoxedError, LocatedDynError};
+#[cfg(feature = "std")]
+pub use builder::LocatedErrorBuilder;
+pub use by_location::ByLocation;
+#[cfg(feature = "std")]
+pub use compact::Compact;
+#[cfg(feature = "std")]
+pub use context::Context;
+#[cfg(all(feature = "drop_logging", not(passthrough)))]
+pub use drop_logging::set_drop_hook;
+#[cfg(feature = "std")]
+pub use dyn_error::Error;
+pub use error_name::ErrorName;
+#[doc(hidden)]
+pub use error_name::{__NameOf, __Named, __Unnamed};
+#[cfg(feature = "serde")]
+pub use error_report::{ErrorReport, ReportCause, ReportFrame};
+pub use ext::ResultExt;
+#[cfg(feature = "std")]
+pub use format_options::{FormatOptions, PathRedaction, REDACTED};
+#[cfg(feature = "std")]
+pub use json::Json;
+#[doc(hidden)]
+pub use locate_each::{LocateKind as __LocateKind, PassKind as __PassKind};
+#[cfg(feature = "std")]
+pub use located_error::SharedLocatedError;
+pub use located_error::{LocatedError, SendLocatedError};
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use main_error::{__MainError, __main_result};
+#[cfg(feature = "std")]
+pub use message::{LocatedMessage, Message};
+#[cfg(feature = "std")]
+pub use report::Report;
+#[cfg(feature = "std")]
+pub use site::SiteCounter;
+#[cfg(feature = "std")]
+pub use source_location::SourceLocation;
+#[cfg(feature = "external_backtrace")]
+pub use stacktrace::CapturedBacktrace;
+#[cfg(feature = "std")]
+pub use stacktrace::{StackTrace, StackTraceFrame};
@@ -0,0 +1,76 @@
+#[cfg(feature = "std")]
+use crate::SourceLocation;
+use core::fmt;
+use core::panic::Location;
+#[cfg(all(feature = "std", not(passthrough)))]
+use std::sync::Arc;
+
+/// Capture location of a [`crate::LocatedError`]
+#[derive(Clone, Debug)]
+pub(crate) enum Loc {
+    /// captured at the call site
+    Static(&'static Location<'static>),
+
+    /// reconstructed from a [`SourceLocation`], see `Loc::owned`
+    #[cfg(all(feature = "std", not(passthrough)))]
+    Owned(Arc<SourceLocation>),
+}
+
+impl Loc {
+    /// location equal to `location`, shared by the clones of the error
+    #[cfg(all(feature = "std", not(passthrough)))]
+    pub fn owned(location: SourceLocation) -> Self {
+        Loc::Owned(Arc::new(location))
+    }
+
+    /// the captured location, `None` if reconstructed
+    pub fn static_location(&self) -> Option<&'static Location<'static>> {
+        match self {
+            Loc::Static(location) => Some(location),
+            #[cfg(all(feature = "std", not(passthrough)))]
+            Loc::Owned(_) => None,
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        match self {
+            Loc::Static(location) => location.file(),
+            #[cfg(all(feature = "std", not(passthrough)))]
+            Loc::Owned(location) => location.file(),
+        }
+    }
+
+    pub fn line(&self) -> u32 {
+        match self {
+            Loc::Static(location) => location.line(),
+            #[cfg(all(feature = "std", not(passthrough)))]
+            Loc::Owned(location) => location.line(),
+        }
+    }
+
+    pub fn column(&self) -> u32 {
+        match self {
+            Loc::Static(location) => location.column(),
+            #[cfg(all(feature = "std", not(passthrough)))]
+            Loc::Owned(location) => location.column(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<&Loc> for SourceLocation {
+    fn from(location: &Loc) -> Self {
+        match location {
+            Loc::Static(location) => SourceLocation::from(*location),
+            #[cfg(not(passthrough))]
+            Loc::Owned(location) => SourceLocation::clone(location),
+        }
+    }
+}
+
+/// `{file}:{line}:{column}`
+impl fmt::Display for Loc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file(), self.line(), self.column())
+    }
+}
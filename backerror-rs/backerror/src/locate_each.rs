@@ -0,0 +1,92 @@
+//! support of `#[locate(each)]`, which rewrites each `expr?` of a function into
+//!
+//! ```ignore
+//! match expr {
+//!     result => {
+//!         use backerror::{__LocateKind as _, __PassKind as _};
+//!         (&result).__locate_kind().locate(result, "app::load")
+//!     }
+//! }?
+//! ```
+//!
+//! The method is picked by autoref, like `anyhow!`: `__LocateKind` applies to `&Result<T, E>` as
+//! it is, so it's preferred over `__PassKind`, which only applies to `&&Result<T, E>`. A `Result`
+//! with an error type, e.g. `std::io::Error`, is located at the `?`, anything else, e.g. an
+//! `Option` or a `Result<T, anyhow::Error>`, is left as it is.
+use crate::LocatedError;
+use core::error::Error;
+
+/// `Result<T, E>` is located by [`Locate`]
+#[doc(hidden)]
+pub trait LocateKind {
+    fn __locate_kind(&self) -> Locate {
+        Locate
+    }
+}
+
+impl<T, E: Error> LocateKind for Result<T, E> {}
+
+/// anything else is left as it is by [`Pass`]
+#[doc(hidden)]
+pub trait PassKind {
+    fn __locate_kind(&self) -> Pass {
+        Pass
+    }
+}
+
+impl<T> PassKind for &T {}
+
+#[doc(hidden)]
+pub struct Locate;
+
+impl Locate {
+    /// `result`, with its error located at the caller, which is the `?`, and recording `function`
+    #[track_caller]
+    pub fn locate<T, E: Error>(
+        self,
+        result: Result<T, E>,
+        function: &'static str,
+    ) -> Result<T, LocatedError<E>> {
+        match result {
+            Ok(value) => Ok(value),
+            Err(err) => Err(LocatedError::from(err).with_function(function)),
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct Pass;
+
+impl Pass {
+    /// `result` as it is
+    pub fn locate<R>(self, result: R, _function: &'static str) -> R {
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LocateKind as _, PassKind as _};
+    use crate::LocatedError;
+
+    #[test]
+    fn locate_kind() {
+        let result: Result<(), core::fmt::Error> = Err(core::fmt::Error);
+        // borrowed like the expansion of `#[locate(each)]`, which picks the method by autoref
+        #[allow(clippy::needless_borrow)]
+        let (result, line) = ((&result).__locate_kind().locate(result, "f"), line!());
+        let err: LocatedError<core::fmt::Error> = result.unwrap_err();
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+
+        // not an error type
+        let result: Result<(), &str> = Err("boom");
+        let result: Result<(), &str> = (&result).__locate_kind().locate(result, "f");
+        assert_eq!(result, Err("boom"));
+
+        let option = Some(1);
+        let option: Option<i32> = (&option).__locate_kind().locate(option, "f");
+        assert_eq!(option, Some(1));
+    }
+}
@@ -1,13 +1,32 @@
-use core::any::type_name;
+use crate::ErrorName;
+#[cfg(feature = "std")]
+use crate::SourceLocation;
+#[cfg(feature = "std")]
+use crate::builder::Metadata;
+#[cfg(all(feature = "drop_logging", not(passthrough)))]
+use crate::drop_logging::DropGuard;
+#[cfg(not(passthrough))]
+use crate::error_name::error_name;
+#[cfg(capture_backtrace)]
+use crate::format_options::FormatOptions;
+#[cfg(all(feature = "std", not(passthrough)))]
+use crate::format_options::redact_path;
+use crate::loc::Loc;
+#[cfg(all(feature = "opentelemetry", not(passthrough)))]
+use crate::otel;
+#[cfg(all(feature = "std", not(passthrough)))]
+use crate::report;
+#[cfg(feature = "std")]
+use crate::stacktrace::{self, CapturedBacktrace, StackTrace};
+use core::any::{Any, TypeId};
 use core::error::Error;
-use core::ops::Deref;
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
 use core::panic::Location;
-use core::{borrow, fmt, panic};
-#[cfg(feature = "backtrace")]
-use std::backtrace::Backtrace;
-#[cfg(feature = "backtrace")]
+use core::{borrow, fmt};
+#[cfg(feature = "std")]
 use std::borrow::Cow;
-#[cfg(feature = "backtrace")]
+#[cfg(feature = "std")]
 use std::sync::Arc;
 
 /// New error type encapsulating the original error and location data.
@@ -18,98 +37,316 @@ use std::sync::Arc;
 /// }
 /// let _r = open_fail();
 /// ```
+///
+/// With the `release_passthrough` feature, release builds compile `LocatedError<E>` down to a
+/// transparent wrapper around `E`: no location or backtrace is captured, [`fmt::Display`] and
+/// [`fmt::Debug`] are the ones of `E`, and location accessors return a sentinel, see
+/// [`LocatedError::try_location`].
+#[cfg_attr(passthrough, repr(transparent))]
 pub struct LocatedError<E: Error> {
     inner: E,
-    location: &'static Location<'static>,
 
-    #[cfg(feature = "backtrace")]
-    backtrace: Arc<Backtrace>,
+    #[cfg(not(passthrough))]
+    payload: PayloadPtr,
 }
 
-/// Error
-impl<E: Error> Error for LocatedError<E> {
-    fn source(&self) -> Option<&(dyn Error + 'static)> {
-        self.inner.source()
+/// Capture location, backtrace and metadata of a [`LocatedError`], kept behind one pointer so that
+/// `LocatedError<E>`, and every `Result` carrying it, is only `E` plus one pointer
+#[cfg(not(passthrough))]
+#[derive(Clone)]
+struct Payload {
+    location: Loc,
+
+    #[cfg(capture_backtrace)]
+    backtrace: Arc<CapturedBacktrace>,
+
+    #[cfg(feature = "drop_logging")]
+    guard: DropGuard,
+
+    #[cfg(feature = "std")]
+    metadata: Option<Box<Metadata>>,
+
+    /// original location of a relocated error, see [`LocatedError::relocate`]
+    #[cfg(feature = "std")]
+    first_seen: Option<Loc>,
+}
+
+#[cfg(all(feature = "std", not(passthrough)))]
+type PayloadPtr = Box<Payload>;
+
+/// without `std` the payload is only the `&'static Location`, a pointer already
+#[cfg(all(not(feature = "std"), not(passthrough)))]
+type PayloadPtr = Payload;
+
+#[cfg(not(passthrough))]
+impl Payload {
+    /// the payload behind [`PayloadPtr`]: boxed with `std`, as it is without
+    #[cfg(feature = "std")]
+    fn into_ptr(self) -> PayloadPtr {
+        Box::new(self)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn into_ptr(self) -> PayloadPtr {
+        self
+    }
+}
+
+/// [`LocatedError`] which can be sent across threads, e.g. returned from a
+/// `std::thread::spawn` closure and received through `JoinHandle::join`.
+///
+/// The location and the shared backtrace are always `Send + Sync`, so through the auto traits
+/// `LocatedError<E>` is `Send` if `E: Send` and `Sync` if `E: Sync`. Spawning a thread also
+/// requires `E: 'static`. Type aliases can't enforce bounds, the alias documents them:
+/// ```ignore
+/// let handle = std::thread::spawn(|| -> Result<(), SendLocatedError<std::io::Error>> {
+///     std::fs::File::open("blurb.txt")?;
+///     Ok(())
+/// });
+/// let err = handle.join().unwrap().unwrap_err();
+/// ```
+pub type SendLocatedError<E> = LocatedError<E>;
+
+/// [`LocatedError`] sharing its error through an [`Arc`], so that it can be cloned cheaply even if
+/// `E` isn't `Clone`, e.g. an `io::Error` reported both to a log and to a monitoring service.
+/// Clones share the error, the location and the backtrace. Created by
+/// [`LocatedError::into_shared`]:
+/// ```ignore
+/// let err: SharedLocatedError<std::io::Error> = err.into_shared();
+/// tx.send(err.clone())?;
+/// log::error!("{err:?}");
+/// ```
+#[cfg(feature = "std")]
+pub type SharedLocatedError<E> = LocatedError<Arc<E>>;
+
+/// Location reported by a [`LocatedError`] which has no `Location`: in passthrough builds, which
+/// don't capture any location, and for errors rebuilt from a `SourceLocation`. It points here, into
+/// the source of this crate, see [`LocatedError::try_location`].
+pub(crate) const UNKNOWN_LOCATION: &Location<'static> = Location::caller();
+
+/// `Error` for [`LocatedError`], `source()` returns the wrapped error with `layered_source`, which
+/// needs `E: 'static`
+macro_rules! impl_error {
+    ($($bound:tt)*) => {
+        impl<E: Error $($bound)*> Error for LocatedError<E> {
+            /// the source of the wrapped error, which is one layer with its location
+            #[cfg(not(layered))]
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                self.inspect();
+                self.inner.source()
+            }
+
+            /// the wrapped error, so that generic chain walkers see every layer
+            #[cfg(layered)]
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                self.inspect();
+                Some(&self.inner)
+            }
+
+            /// the capture `Location` and the captured backtrace, then whatever the wrapped error
+            /// provides
+            #[cfg(feature = "provide")]
+            fn provide<'a>(&'a self, request: &mut core::error::Request<'a>) {
+                #[cfg(not(passthrough))]
+                if let Some(location) = self.loc().static_location() {
+                    request.provide_ref::<Location<'static>>(location);
+                }
+                #[cfg(capture_backtrace)]
+                if let Some(backtrace) = self.backtrace() {
+                    request.provide_ref::<CapturedBacktrace>(backtrace);
+                }
+                #[cfg(all(feature = "std", not(passthrough)))]
+                request.provide_value_with::<report::Probed>(|| self.probed());
+                self.inner.provide(request);
+            }
+
+            /// the description of the wrapped error, answering the probe of a report for located
+            /// layers on stable compilers, see `report::probe`
+            #[cfg(all(feature = "std", not(passthrough), not(feature = "provide")))]
+            #[allow(deprecated)]
+            fn description(&self) -> &str {
+                report::answer_probe(|| self.probed());
+                self.inner.description()
+            }
+        }
+    };
+}
+
+// Error
+#[cfg(not(layered))]
+impl_error!();
+#[cfg(layered)]
+impl_error!(+ 'static);
+
+#[cfg(all(feature = "std", not(passthrough)))]
+impl<E: Error> LocatedError<E> {
+    /// this layer as seen by a report
+    fn probed(&self) -> report::Probed {
+        report::Probed {
+            location: self.payload.location.clone(),
+            name: self.error_name(),
+            help: self.help().map(str::to_string),
+            #[cfg(capture_backtrace)]
+            backtrace: stacktrace::is_captured(&self.payload.backtrace)
+                .then(|| self.payload.backtrace.clone()),
+        }
     }
 }
 
-#[cfg(feature = "backtrace")]
+#[cfg(capture_backtrace)]
 const DEBUG_CAUSED_BY_PAT: &str = "Caused by: ";
+#[cfg(capture_backtrace)]
+const FRAME_PAT: &str = "\tat ";
+#[cfg(capture_backtrace)]
+const HOP_PAT: &str = "-> ";
+/// sources visited by [`LocatedError::root_cause`] at most, in case the chain is cyclic
+const MAX_CHAIN_DEPTH: usize = 1024;
+#[cfg(not(passthrough))]
 const DISPLAY_CAUSED_BY_PAT: &str = "; Caused by ";
 
 /// Display
 impl<E: Error> fmt::Display for LocatedError<E> {
-    #[cfg(feature = "std")]
+    #[cfg(passthrough)]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+
+    #[cfg(all(feature = "std", not(passthrough)))]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inspect();
+        if report::is_plain() {
+            return fmt::Display::fmt(&self.inner, f);
+        }
         let inner_msg = format!("{}", self.inner);
+        let top_frame = self.top_frame_suffix();
         if let Some(pos) = inner_msg.find(DISPLAY_CAUSED_BY_PAT) {
             write!(
                 f,
-                "{}{DISPLAY_CAUSED_BY_PAT}{} ({}){}",
+                "{}{DISPLAY_CAUSED_BY_PAT}{} ({}){}{}",
                 &inner_msg[..pos],
-                type_name::<E>(),
-                self.location,
+                self.error_name(),
+                self.display_location(),
+                top_frame,
                 &inner_msg[pos..]
             )
         } else {
             write!(
                 f,
-                "{}{DISPLAY_CAUSED_BY_PAT}{}({});",
+                "{}{DISPLAY_CAUSED_BY_PAT}{}({}){};",
                 self.inner,
-                type_name::<E>(),
-                self.location,
+                self.error_name(),
+                self.display_location(),
+                top_frame,
             )
         }
     }
 
-    #[cfg(not(feature = "std"))]
+    #[cfg(all(not(feature = "std"), not(passthrough)))]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inspect();
         write!(
             f,
             "{}{DISPLAY_CAUSED_BY_PAT}{}({});",
             self.inner,
-            type_name::<E>(),
-            self.location,
+            self.error_name(),
+            self.display_location(),
         )
     }
 }
 
+/// trailing `help: ...` and `see: <url>` lines of [`fmt::Debug`], each starting with a newline
+#[cfg(not(passthrough))]
+struct Trailer<'a> {
+    help: Option<&'a str>,
+    url: Option<&'a str>,
+}
+
+#[cfg(not(passthrough))]
+impl fmt::Display for Trailer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(help) = self.help {
+            write!(f, "\nhelp: {help}")?;
+        }
+        if let Some(url) = self.url {
+            write!(f, "\nsee: {url}")?;
+        }
+        Ok(())
+    }
+}
+
 /// Debug
 impl<E: Error> fmt::Debug for LocatedError<E> {
-    #[cfg(not(feature = "backtrace"))]
+    #[cfg(passthrough)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+
+    #[cfg(all(not(capture_backtrace), not(passthrough)))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inspect();
         write!(
             f,
-            "{:?} at ({}) by {}",
+            "{:?} at ({}) by {}{}{}",
             self.inner,
-            self.location,
-            type_name::<E>(), // name
+            self.display_location(),
+            self.error_name(), // name
+            self.fields_suffix(),
+            self.trailer(),
         )
     }
 
-    #[cfg(feature = "backtrace")]
+    #[cfg(capture_backtrace)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(stacktrace) = super::stacktrace::StackTrace::parse(&self.backtrace) {
+        self.inspect();
+        if let Some(stacktrace) = self.stacktrace() {
             self.fmt_stacktrace(stacktrace, f)
         } else {
             write!(
                 f,
-                "{:?} at ({}) by {}",
+                "{:?} at ({}) by {}{}{}",
                 self.inner,
-                self.location,
-                type_name::<E>() // name
+                self.display_location(),
+                self.error_name(), // name
+                self.fields_suffix(),
+                self.trailer(),
             )
         }
     }
 }
 
-#[cfg(feature = "backtrace")]
+#[cfg(feature = "std")]
+impl<E: Error> LocatedError<E> {
+    /// Normalized frames of the captured backtrace, `None` if no backtrace was captured.
+    /// Always `None` without the `backtrace` feature, in release builds with `release_off` and in
+    /// passthrough builds, which don't capture backtraces.
+    pub fn stacktrace(&self) -> Option<StackTrace> {
+        self.backtrace().and_then(StackTrace::from_captured)
+    }
+
+    /// The captured backtrace, e.g. for error reporters which take a raw backtrace,
+    /// `None` if no backtrace was captured, see [`LocatedError::stacktrace`].
+    /// With the `external_backtrace` feature, this is a [`crate::CapturedBacktrace`] instead, which
+    /// derefs to a `backtrace::Backtrace`.
+    pub fn backtrace(&self) -> Option<&CapturedBacktrace> {
+        self.inspect();
+        #[cfg(capture_backtrace)]
+        return stacktrace::is_captured(&self.payload.backtrace)
+            .then_some(&*self.payload.backtrace);
+        #[cfg(not(capture_backtrace))]
+        None
+    }
+}
+
+#[cfg(capture_backtrace)]
 impl<E: Error> LocatedError<E> {
-    fn fmt_stacktrace(
-        &self,
-        stacktrace: super::stacktrace::StackTrace,
-        f: &mut fmt::Formatter<'_>,
-    ) -> fmt::Result {
+    /// capture a backtrace, unless one was already captured
+    pub(crate) fn force_backtrace(&mut self) {
+        if !stacktrace::is_captured(&self.payload.backtrace) {
+            self.payload.backtrace = Arc::new(stacktrace::force_capture());
+        }
+    }
+
+    fn fmt_stacktrace(&self, stacktrace: StackTrace, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut output = Vec::new();
         let inner_debug = format!("{:?}", self.inner);
         let mut lines = inner_debug.lines();
@@ -117,15 +354,18 @@ impl<E: Error> LocatedError<E> {
         let mut first_caused_by = true;
         while let Some(line) = lines.next() {
             if first_caused_by {
-                if line.starts_with(DEBUG_CAUSED_BY_PAT) {
+                if is_caused_by_header(line) {
                     first_caused_by = false;
                     // inject the stacktrace
                     self.inject_stacktrace(&stacktrace, &mut output);
                 }
                 output.push(Cow::Borrowed(line));
             } else {
+                // only frames and hops are shared between nested stacktraces,
+                // lines of the inner error's own `Debug` are kept as is
                 let line = Cow::Borrowed(line);
-                if !output.contains(&line) {
+                let shared = line.starts_with(FRAME_PAT) || line.starts_with(HOP_PAT);
+                if !shared || !output.contains(&line) {
                     output.push(line);
                 }
             }
@@ -139,32 +379,46 @@ impl<E: Error> LocatedError<E> {
             writeln!(f, "{}", line)?;
         }
 
-        write!(f, "")
+        let trailer = self.trailer().to_string();
+        write!(f, "{}", trailer.trim_start_matches('\n'))
     }
 
-    #[cfg(feature = "backtrace")]
-    fn inject_stacktrace(
-        &self,
-        stacktrace: &crate::stacktrace::StackTrace,
-        output: &mut Vec<Cow<'_, str>>,
-    ) {
+    fn inject_stacktrace(&self, stacktrace: &StackTrace, output: &mut Vec<Cow<'_, str>>) {
         let cause = format!(
-            "{DEBUG_CAUSED_BY_PAT}{}: {} ({})",
-            type_name::<E>(),
+            "{DEBUG_CAUSED_BY_PAT}{}: {} ({}){}",
+            self.error_name(),
             self.pure_desc(),
-            self.location
+            self.display_location(),
+            self.fields_suffix(),
         );
         output.push(Cow::Owned(cause));
+        // the rest of the propagation trail, this error's own location is in the header
+        let hops = self.inner.source().map(report::trail).unwrap_or_default();
+        for hop in hops {
+            output.push(Cow::Owned(format!(
+                "{HOP_PAT}{}:{}",
+                redact_path(hop.file()),
+                hop.line()
+            )));
+        }
         for frame in &stacktrace.frames {
-            let trace = if frame.file.is_empty() {
-                format!("\tat {}", frame.func)
-            } else {
-                format!("\tat {} ({}:{})", frame.func, frame.file, frame.line)
-            };
-            output.push(Cow::Owned(trace));
+            output.push(Cow::Owned(format!("{FRAME_PAT}{frame}")));
         }
     }
 
+    /// ` [at {frame}]` if enabled by [`FormatOptions::display_top_frame`]
+    fn top_frame_suffix(&self) -> String {
+        if !FormatOptions::with_current(|options| options.display_top_frame) {
+            return String::new();
+        }
+        self.stacktrace()
+            .and_then(|stacktrace| {
+                let frame = stacktrace.top_user_frame()?;
+                Some(format!(" [at {frame}]"))
+            })
+            .unwrap_or_default()
+    }
+
     fn pure_desc(&self) -> String {
         let desc = self.inner.to_string();
         let desc = if let Some(pos) = desc.find(DISPLAY_CAUSED_BY_PAT) {
@@ -176,93 +430,1633 @@ impl<E: Error> LocatedError<E> {
     }
 }
 
-/// From
-impl<E: Error> From<E> for LocatedError<E> {
+impl<E: Error> LocatedError<E> {
+    /// Wrap `err`, capturing the caller's location.
+    ///
+    /// Same as [`LocatedError::from`], for error types which are not annotated with `#[backerror]`,
+    /// e.g. because they are defined in another crate.
     #[track_caller]
-    fn from(err: E) -> Self {
-        LocatedError {
+    pub fn wrap(err: E) -> Self {
+        Self::from(err)
+    }
+
+    /// Wrap `err` with an explicit `location`.
+    ///
+    /// `#[track_caller]` does not propagate through `async fn` and `.await`, so in async code the
+    /// location can be captured before awaiting, see [`crate::located_await`]:
+    /// ```ignore
+    /// let location = Location::caller();
+    /// let err = fetch().await.unwrap_err();
+    /// let err = LocatedError::located_at(err, location);
+    /// ```
+    #[cfg_attr(passthrough, allow(unused_variables))]
+    pub fn located_at(err: E, location: &'static Location<'static>) -> Self {
+        let located = LocatedError {
             inner: err,
-            location: panic::Location::caller(),
 
-            #[cfg(all(feature = "backtrace", not(feature = "force_backtrace")))]
-            backtrace: Arc::new(Backtrace::capture()),
+            #[cfg(not(passthrough))]
+            payload: Payload {
+                location: Loc::Static(location),
 
-            #[cfg(feature = "force_backtrace")]
-            backtrace: Arc::new(Backtrace::force_capture()), // or Backtrace::disabled()
+                #[cfg(capture_backtrace)]
+                backtrace: Arc::new(stacktrace::capture()),
+
+                #[cfg(feature = "drop_logging")]
+                guard: DropGuard::new(Loc::Static(location)),
+
+                #[cfg(feature = "std")]
+                metadata: None,
+
+                #[cfg(feature = "std")]
+                first_seen: None,
+            }
+            .into_ptr(),
+        };
+
+        #[cfg(all(feature = "opentelemetry", not(passthrough)))]
+        located.record_exception();
+
+        located
+    }
+
+    /// [`LocatedError::located_at`] with a backtrace captured elsewhere, e.g. forwarded along
+    /// with the location by a code generator or a custom propagation layer.
+    /// `None` captures a backtrace here, like [`LocatedError::located_at`].
+    /// ```ignore
+    /// let err = LocatedError::new_at(err, location, Some(backtrace));
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(not(capture_backtrace), allow(unused_variables))]
+    pub fn new_at(
+        err: E,
+        location: &'static Location<'static>,
+        backtrace: Option<Arc<CapturedBacktrace>>,
+    ) -> Self {
+        let Some(backtrace) = backtrace else {
+            return Self::located_at(err, location);
+        };
+        let located = LocatedError {
+            inner: err,
+
+            #[cfg(not(passthrough))]
+            payload: Payload {
+                location: Loc::Static(location),
+
+                #[cfg(capture_backtrace)]
+                backtrace,
+
+                #[cfg(feature = "drop_logging")]
+                guard: DropGuard::new(Loc::Static(location)),
+
+                #[cfg(feature = "std")]
+                metadata: None,
+
+                #[cfg(feature = "std")]
+                first_seen: None,
+            }
+            .into_ptr(),
+        };
+
+        #[cfg(all(feature = "opentelemetry", not(passthrough)))]
+        located.record_exception();
+
+        located
+    }
+
+    /// Wrap `err`, capturing only the caller's location, never a backtrace, for hot call sites
+    /// which can't afford to capture one even with the `backtrace` feature.
+    /// ```ignore
+    /// return Err(LocatedError::without_backtrace(ParseError::Eof));
+    /// ```
+    /// [`crate::ResultExt::wrap_located_without_backtrace`] does the same for a `Result`, where
+    /// `?` would capture a backtrace through [`From`].
+    #[track_caller]
+    pub fn without_backtrace(err: E) -> Self {
+        #[cfg(capture_backtrace)]
+        return Self::new_at(
+            err,
+            Location::caller(),
+            Some(Arc::new(stacktrace::disabled())),
+        );
+
+        #[cfg(not(capture_backtrace))]
+        Self::located_at(err, Location::caller())
+    }
+
+    /// Take the wrapped error by value, dropping the location and backtrace.
+    /// ```ignore
+    /// let err: std::io::Error = located.into_inner();
+    /// ```
+    pub fn into_inner(self) -> E {
+        self.inspect();
+        self.inner
+    }
+
+    /// Rebuild an error from its parts, e.g. received from another process or read from a cache,
+    /// the inverse of [`LocatedError::into_source_parts`]. No location or backtrace is captured.
+    /// ```ignore
+    /// let (inner, location) = err.into_source_parts();
+    /// let err = LocatedError::from_parts(inner, location);
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(passthrough, allow(unused_variables))]
+    pub fn from_parts(inner: E, location: SourceLocation) -> Self {
+        #[cfg(not(passthrough))]
+        let location = Loc::owned(location);
+        LocatedError {
+            inner,
+
+            #[cfg(not(passthrough))]
+            payload: Payload {
+                #[cfg(feature = "drop_logging")]
+                guard: DropGuard::new(location.clone()),
+
+                location,
+
+                #[cfg(capture_backtrace)]
+                backtrace: Arc::new(stacktrace::disabled()),
+
+                metadata: None,
+
+                #[cfg(feature = "std")]
+                first_seen: None,
+            }
+            .into_ptr(),
         }
     }
-}
 
-/// AsRef
-impl<T: Error> AsRef<T> for LocatedError<T> {
-    fn as_ref(&self) -> &T {
-        &self.inner
+    /// [`LocatedError::from_parts`] with the backtrace of the original error,
+    /// the inverse of [`LocatedError::into_source_parts_with_backtrace`].
+    /// The backtrace is dropped in builds which don't capture backtraces.
+    #[cfg(feature = "std")]
+    #[cfg_attr(not(capture_backtrace), allow(unused_variables, unused_mut))]
+    pub fn from_parts_with_backtrace(
+        inner: E,
+        location: SourceLocation,
+        backtrace: Arc<CapturedBacktrace>,
+    ) -> Self {
+        let mut located = Self::from_parts(inner, location);
+        #[cfg(capture_backtrace)]
+        {
+            located.payload.backtrace = backtrace;
+        }
+        located
     }
-}
 
-/// Deref
-impl<T: Error> Deref for LocatedError<T> {
-    type Target = T;
+    /// Split into the wrapped error, its capture location and the captured backtrace, e.g. to
+    /// feed them into a telemetry system without formatting this error.
+    /// ```ignore
+    /// let (inner, location, backtrace) = err.into_parts();
+    /// ```
+    /// The location is the one of [`LocatedError::location`], a sentinel for errors rebuilt with
+    /// [`LocatedError::from_parts`]. The backtrace is `None` if none was captured.
+    #[cfg(feature = "std")]
+    pub fn into_parts(
+        self,
+    ) -> (
+        E,
+        &'static Location<'static>,
+        Option<Arc<CapturedBacktrace>>,
+    ) {
+        let location = self.location();
+        #[cfg(capture_backtrace)]
+        let backtrace =
+            stacktrace::is_captured(&self.payload.backtrace).then_some(self.payload.backtrace);
+        #[cfg(not(capture_backtrace))]
+        let backtrace = None;
+        (self.inner, location, backtrace)
+    }
 
-    fn deref(&self) -> &T {
-        &self.inner
+    /// Split into the wrapped error and its owned capture location, e.g. to send or store them,
+    /// see [`LocatedError::from_parts`].
+    /// Passthrough builds return a sentinel location.
+    #[cfg(feature = "std")]
+    pub fn into_source_parts(self) -> (E, SourceLocation) {
+        let location = SourceLocation::from(self.loc());
+        (self.inner, location)
     }
-}
 
-/// Borrow
-impl<T: Error> borrow::Borrow<T> for LocatedError<T> {
-    fn borrow(&self) -> &T {
-        &self.inner
+    /// [`LocatedError::into_source_parts`], with the captured backtrace, a backtrace without
+    /// frames in builds which don't capture backtraces
+    #[cfg(feature = "std")]
+    pub fn into_source_parts_with_backtrace(self) -> (E, SourceLocation, Arc<CapturedBacktrace>) {
+        let location = SourceLocation::from(self.loc());
+        #[cfg(capture_backtrace)]
+        return (self.inner, location, self.payload.backtrace);
+        #[cfg(not(capture_backtrace))]
+        (self.inner, location, Arc::new(stacktrace::disabled()))
     }
-}
 
-// Send
-unsafe impl<T: Error + Send> Send for LocatedError<T> {}
+    /// record the `exception` event on the current OpenTelemetry span,
+    /// without marking this error as inspected
+    #[cfg(all(feature = "opentelemetry", not(passthrough)))]
+    fn record_exception(&self) {
+        otel::record_exception(
+            self.error_name(),
+            &self.inner,
+            &self.payload.location,
+            || self.exception_stacktrace(),
+        );
+    }
 
-/// Sync
-unsafe impl<T: Error + Sync> Sync for LocatedError<T> {}
+    /// frames of the captured backtrace, one per line
+    #[cfg(all(feature = "opentelemetry", capture_backtrace))]
+    fn exception_stacktrace(&self) -> Option<String> {
+        let stacktrace = StackTrace::from_captured(&self.payload.backtrace)?;
+        let frames: Vec<String> = stacktrace
+            .frames
+            .iter()
+            .map(|frame| format!("{FRAME_PAT}{frame}"))
+            .collect();
+        Some(frames.join("\n"))
+    }
 
-// Clone
-impl<T: Error + Clone> Clone for LocatedError<T> {
-    fn clone(&self) -> Self {
+    #[cfg(all(feature = "opentelemetry", not(capture_backtrace), not(passthrough)))]
+    fn exception_stacktrace(&self) -> Option<String> {
+        None
+    }
+
+    /// Full type name of the wrapped error, [`core::any::type_name`] of `E`, regardless of
+    /// [`crate::ErrorName`] and `FormatOptions::short_type_names`
+    pub fn type_name(&self) -> &'static str {
+        core::any::type_name::<E>()
+    }
+
+    /// Location where this error was captured, e.g. to log its file and line as structured fields.
+    ///
+    /// Errors rebuilt with `LocatedError::from_parts` only have a `SourceLocation`, see
+    /// `LocatedError::into_source_parts` and [`LocatedError::site_key`]. Passthrough builds don't
+    /// capture any location. Both return a sentinel: a fixed location in the source of this crate,
+    /// not one of the application. [`LocatedError::try_location`] returns `None` for them instead.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.loc().static_location().unwrap_or(UNKNOWN_LOCATION)
+    }
+
+    /// [`LocatedError::location`], or `None` instead of its sentinel: for errors rebuilt with
+    /// `LocatedError::from_parts`, whose location is [`LocatedError::source_location`], and in
+    /// passthrough builds.
+    /// ```ignore
+    /// if let Some(location) = err.try_location() {
+    ///     span.record("code.lineno", location.line());
+    /// }
+    /// ```
+    #[cfg(not(passthrough))]
+    pub fn try_location(&self) -> Option<&'static Location<'static>> {
+        self.loc().static_location()
+    }
+
+    #[cfg(passthrough)]
+    pub fn try_location(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    /// Owned location where this error was captured, see [`SourceLocation`]. Unlike
+    /// [`LocatedError::location`], errors rebuilt with [`LocatedError::from_parts`] keep their
+    /// original location. Passthrough builds return a sentinel location.
+    #[cfg(feature = "std")]
+    pub fn source_location(&self) -> SourceLocation {
+        SourceLocation::from(self.loc())
+    }
+
+    /// Move this error to the caller's location, e.g. when a stored error is raised again after
+    /// being pulled from a cache or a job queue, where its capture location would be misleading.
+    /// ```ignore
+    /// return Err(cached_err.relocate());
+    /// ```
+    /// With `std`, the original location is kept as [`LocatedError::first_seen`]. The backtrace is
+    /// kept as captured.
+    #[track_caller]
+    pub fn relocate(self) -> Self {
+        self.relocated_at(Location::caller())
+    }
+
+    /// [`LocatedError::relocate`] to an explicit `location`
+    #[cfg_attr(passthrough, allow(unused_mut, unused_variables))]
+    pub fn relocated_at(mut self, location: &'static Location<'static>) -> Self {
+        #[cfg(not(passthrough))]
+        {
+            let previous = core::mem::replace(&mut self.payload.location, Loc::Static(location));
+            #[cfg(feature = "std")]
+            self.payload.first_seen.get_or_insert(previous);
+            #[cfg(not(feature = "std"))]
+            let _ = previous;
+            #[cfg(feature = "drop_logging")]
+            self.payload.guard.relocate(Loc::Static(location));
+        }
+        self
+    }
+
+    /// Location where this error was first captured, if it was moved with
+    /// [`LocatedError::relocate`], the earliest one if it was moved several times.
+    /// Always `None` in passthrough builds.
+    #[cfg(all(feature = "std", not(passthrough)))]
+    pub fn first_seen(&self) -> Option<&'static Location<'static>> {
+        self.inspect();
+        let first_seen = self.payload.first_seen.as_ref()?;
+        Some(first_seen.static_location().unwrap_or(UNKNOWN_LOCATION))
+    }
+
+    #[cfg(all(feature = "std", passthrough))]
+    pub fn first_seen(&self) -> Option<&'static Location<'static>> {
+        None
+    }
+
+    /// Record `name`, e.g. `concat!(module_path!(), "::load")`, as the function which created this
+    /// error, unless a name was recorded already: the innermost function wins while the error is
+    /// propagated. `#[backerror::locate]` records the name of the annotated function.
+    /// Without `std`, and in passthrough builds, the name is dropped.
+    #[cfg_attr(
+        any(not(feature = "std"), passthrough),
+        allow(unused_mut, unused_variables)
+    )]
+    pub fn with_function(mut self, name: &'static str) -> Self {
+        #[cfg(all(feature = "std", not(passthrough)))]
+        {
+            let metadata = self.payload.metadata.get_or_insert_default();
+            metadata.function.get_or_insert(name);
+        }
+        self
+    }
+
+    /// Use [`ErrorName::error_name`] of `E` instead of its type name in the "by Type" label, for an
+    /// error wrapped by hand; the `From` impls generated by `#[backerror]` name their errors.
+    /// Without `std`, and in passthrough builds, the name is dropped.
+    /// ```ignore
+    /// let err = LocatedError::from(MyError::Timeout).named();
+    /// ```
+    pub fn named(self) -> Self
+    where
+        E: ErrorName,
+    {
+        self.__with_error_name(Some(E::error_name()))
+    }
+
+    /// [`LocatedError::named`] with the result of `__error_name!`, `None` leaves the type name
+    #[doc(hidden)]
+    #[cfg_attr(
+        any(not(feature = "std"), passthrough),
+        allow(unused_mut, unused_variables)
+    )]
+    pub fn __with_error_name(mut self, name: Option<&'static str>) -> Self {
+        #[cfg(all(feature = "std", not(passthrough)))]
+        if let Some(name) = name {
+            self.payload.metadata.get_or_insert_default().name = Some(name);
+        }
+        self
+    }
+
+    /// name of `E` in the "by Type" label, see [`ErrorName`]
+    #[cfg(not(passthrough))]
+    pub(crate) fn error_name(&self) -> &'static str {
+        #[cfg(feature = "std")]
+        let name = self.metadata().and_then(|metadata| metadata.name);
+        #[cfg(not(feature = "std"))]
+        let name = None;
+        error_name::<E>(name)
+    }
+
+    /// Function which created this error, e.g. for log correlation: the name recorded by
+    /// [`LocatedError::with_function`], else the top application frame of the captured backtrace.
+    /// `None` without either, and in passthrough builds.
+    #[cfg(feature = "std")]
+    pub fn function(&self) -> Option<String> {
+        if let Some(function) = self.metadata().and_then(|metadata| metadata.function) {
+            return Some(function.to_string());
+        }
+        #[cfg(capture_backtrace)]
+        if let Some(stacktrace) = self.stacktrace() {
+            return stacktrace.top_user_frame().map(|frame| frame.func.clone());
+        }
+        None
+    }
+
+    /// Key identifying where this error was captured, `(file, line)`.
+    ///
+    /// Suitable for bucketing errors by origin, e.g. in a `HashMap`. This is the key of
+    /// [`LocatedError::location`], errors rebuilt with [`LocatedError::from_parts`] and passthrough
+    /// builds share the key of its sentinel location, see [`LocatedError::source_site_key`].
+    pub fn site_key(&self) -> (&'static str, u32) {
+        let location = self.location();
+        (location.file(), location.line())
+    }
+
+    /// [`LocatedError::site_key`] of the owned location, the original one of errors rebuilt with
+    /// [`LocatedError::from_parts`], see [`LocatedError::source_location`]. The file is only
+    /// copied for rebuilt errors.
+    #[cfg(feature = "std")]
+    pub fn source_site_key(&self) -> (Cow<'static, str>, u32) {
+        let location = self.loc();
+        let file = match location.static_location() {
+            Some(location) => Cow::Borrowed(location.file()),
+            None => Cow::Owned(location.file().to_string()),
+        };
+        (file, location.line())
+    }
+
+    /// Assert that this error was captured at `line` of a file whose path ends with `file`.
+    /// ```ignore
+    /// let err = load_config().unwrap_err();
+    /// err.assert_located_at("src/config.rs", 42);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the capture location doesn't match, with both locations in the message.
+    /// Always panics in passthrough builds, which don't capture any location.
+    #[track_caller]
+    pub fn assert_located_at(&self, file: &str, line: u32) {
+        let location = self.loc();
+        if !location.file().ends_with(file) || location.line() != line {
+            panic!(
+                "error `{}` was captured at {}:{}, expected {file}:{line}",
+                self.inner,
+                location.file(),
+                location.line()
+            );
+        }
+    }
+
+    /// Convert the inner error into `F`, keeping the captured location and backtrace.
+    /// ```ignore
+    /// let err: LocatedError<std::io::Error> = ...;
+    /// let err: LocatedError<MyError> = err.convert();
+    /// ```
+    ///
+    /// A blanket `impl From<LocatedError<E>> for LocatedError<F>` would overlap with
+    /// `impl From<E> for LocatedError<E>`, so the conversion is explicit.
+    pub fn convert<F: Error + From<E>>(self) -> LocatedError<F> {
+        self.map(F::from)
+    }
+
+    /// Move the wrapped error into an [`Arc`], keeping the location and backtrace, so that the
+    /// error can be cloned, see [`SharedLocatedError`]
+    #[cfg(feature = "std")]
+    pub fn into_shared(self) -> SharedLocatedError<E> {
+        let name = self.metadata().and_then(|metadata| metadata.name);
+        self.map(Arc::new).__with_error_name(name)
+    }
+
+    /// Transform the inner error with `f`, keeping the captured location and backtrace, but not
+    /// the [`ErrorName`] of `E`.
+    /// ```ignore
+    /// let err: LocatedError<MyError> = err.map(|err| MyError::Io(err.kind()));
+    /// ```
+    pub fn map<F, E2>(self, f: F) -> LocatedError<E2>
+    where
+        F: FnOnce(E) -> E2,
+        E2: Error,
+    {
+        #[cfg(not(passthrough))]
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut payload = self.payload;
+        #[cfg(all(feature = "std", not(passthrough)))]
+        if let Some(metadata) = &mut payload.metadata {
+            metadata.name = None;
+        }
         LocatedError {
-            inner: self.inner.clone(),
-            location: self.location,
+            inner: f(self.inner),
 
-            #[cfg(feature = "backtrace")]
-            backtrace: self.backtrace.clone(),
+            #[cfg(not(passthrough))]
+            payload,
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use thiserror::Error;
+    /// Identifier set with [`crate::LocatedErrorBuilder::id`].
+    /// Always `None` in passthrough builds.
+    #[cfg(feature = "std")]
+    pub fn id(&self) -> Option<&str> {
+        self.metadata()?.id.as_deref()
+    }
 
-    #[derive(Debug, Error)]
-    pub enum MyError {
-        #[error("MyError {0}")]
-        LocatedIoError(#[from] LocatedError<std::io::Error>),
+    /// Context set with [`crate::LocatedErrorBuilder::context`].
+    /// Always `None` in passthrough builds.
+    #[cfg(feature = "std")]
+    pub fn context_message(&self) -> Option<&str> {
+        self.metadata()?.context.as_deref()
     }
 
-    impl From<std::io::Error> for MyError {
-        #[track_caller]
-        fn from(err: std::io::Error) -> Self {
-            MyError::from(LocatedError::from(err))
+    /// Attach `key` with `value` as structured context, e.g. a request id, user id or retry
+    /// count, instead of baking it into the message. Fields are rendered in [`fmt::Debug`],
+    /// [`LocatedError::json`] and serialized reports. Setting a key again replaces its value.
+    /// Fields are dropped in passthrough builds.
+    /// ```ignore
+    /// let err = LocatedError::from(err).with_field("user_id", user.id).with_field("retry", 3);
+    /// ```
+    #[cfg(feature = "std")]
+    #[cfg_attr(passthrough, allow(unused_mut, unused_variables))]
+    pub fn with_field(mut self, key: impl Into<String>, value: impl fmt::Display) -> Self {
+        #[cfg(not(passthrough))]
+        self.payload
+            .metadata
+            .get_or_insert_default()
+            .set_field(key.into(), value.to_string());
+        self
+    }
+
+    /// Attach an actionable hint for the user, e.g. `"create it with `app init`"`, rendered as a
+    /// trailing `help: ...` line by [`LocatedError::report`] and [`fmt::Debug`].
+    /// `#[backerror(help = "...")]` attaches one to the errors converted into a variant.
+    /// Without `std`, and in passthrough builds, the help text is dropped.
+    #[cfg_attr(
+        any(not(feature = "std"), passthrough),
+        allow(unused_mut, unused_variables)
+    )]
+    pub fn with_help(mut self, help: impl fmt::Display) -> Self {
+        #[cfg(all(feature = "std", not(passthrough)))]
+        {
+            self.payload.metadata.get_or_insert_default().help = Some(help.to_string());
         }
+        self
     }
 
-    fn located_error1() -> Result<(), MyError> {
-        std::fs::File::open("blurb.txt").map_err(|e| LocatedError::<std::io::Error>::from(e))?;
-        Ok(())
+    /// Help text attached with [`LocatedError::with_help`]
+    #[cfg(feature = "std")]
+    pub fn help(&self) -> Option<&str> {
+        self.metadata()?.help.as_deref()
     }
 
-    fn located_error2() -> Result<(), MyError> {
-        std::fs::File::open("blurb.txt")?;
-        Ok(())
+    /// Help text attached with [`LocatedError::with_help`], always `None` without `std`
+    #[cfg(not(feature = "std"))]
+    pub fn help(&self) -> Option<&str> {
+        None
+    }
+
+    /// Attach a documentation or runbook URL, e.g. for routing alerts, rendered as a trailing
+    /// `see: <url>` line by [`fmt::Debug`] and included in [`LocatedError::json`] and serialized
+    /// reports. `#[backerror(url = "...")]` attaches one to the errors converted into a type or
+    /// variant. Without `std`, and in passthrough builds, the URL is dropped.
+    #[cfg_attr(
+        any(not(feature = "std"), passthrough),
+        allow(unused_mut, unused_variables)
+    )]
+    pub fn with_url(mut self, url: impl fmt::Display) -> Self {
+        #[cfg(all(feature = "std", not(passthrough)))]
+        {
+            self.payload.metadata.get_or_insert_default().url = Some(url.to_string());
+        }
+        self
+    }
+
+    /// URL attached with [`LocatedError::with_url`]
+    #[cfg(feature = "std")]
+    pub fn url(&self) -> Option<&str> {
+        self.metadata()?.url.as_deref()
+    }
+
+    /// URL attached with [`LocatedError::with_url`], always `None` without `std`
+    #[cfg(not(feature = "std"))]
+    pub fn url(&self) -> Option<&str> {
+        None
+    }
+
+    /// `(key, value)` fields attached with [`LocatedError::with_field`], in the order they were
+    /// first set. Always empty in passthrough builds.
+    #[cfg(feature = "std")]
+    pub fn fields(&self) -> &[(String, String)] {
+        self.metadata()
+            .map(|metadata| metadata.fields.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// value of the field `key`, see [`LocatedError::with_field`]
+    #[cfg(feature = "std")]
+    pub fn field(&self, key: &str) -> Option<&str> {
+        self.fields()
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[cfg(all(feature = "std", not(passthrough)))]
+    fn metadata(&self) -> Option<&Metadata> {
+        self.inspect();
+        self.payload.metadata.as_deref()
+    }
+
+    #[cfg(all(feature = "std", passthrough))]
+    fn metadata(&self) -> Option<&Metadata> {
+        None
+    }
+
+    #[cfg(all(feature = "std", not(passthrough)))]
+    pub(crate) fn set_metadata(&mut self, metadata: Metadata) {
+        self.payload.metadata = Some(Box::new(metadata));
+    }
+
+    /// ` {key=value, ..}` of the fields attached with [`LocatedError::with_field`], if any
+    #[cfg(all(feature = "std", not(passthrough)))]
+    fn fields_suffix(&self) -> String {
+        let fields = self.fields();
+        if fields.is_empty() {
+            return String::new();
+        }
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        format!(" {{{}}}", fields.join(", "))
+    }
+
+    #[cfg(all(not(feature = "std"), not(passthrough)))]
+    fn fields_suffix(&self) -> &'static str {
+        ""
+    }
+
+    /// help text and URL, rendered after the error by [`fmt::Debug`]
+    #[cfg(not(passthrough))]
+    fn trailer(&self) -> Trailer<'_> {
+        Trailer {
+            help: self.help(),
+            url: self.url(),
+        }
+    }
+
+    /// mark this error as inspected, it will be dropped silently
+    #[inline]
+    fn inspect(&self) {
+        #[cfg(all(feature = "drop_logging", not(passthrough)))]
+        self.payload.guard.inspect();
+    }
+
+    #[cfg(not(passthrough))]
+    pub(crate) fn loc(&self) -> &Loc {
+        self.inspect();
+        &self.payload.location
+    }
+
+    #[cfg(not(passthrough))]
+    fn display_location(&self) -> LocationDisplay<'_> {
+        LocationDisplay(&self.payload.location)
+    }
+
+    #[cfg(all(feature = "std", not(capture_backtrace), not(passthrough)))]
+    fn top_frame_suffix(&self) -> String {
+        String::new()
+    }
+
+    #[cfg(passthrough)]
+    pub(crate) fn loc(&self) -> &Loc {
+        const { &Loc::Static(UNKNOWN_LOCATION) }
+    }
+}
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// The innermost error of the `source()` chain, the wrapped error if it has no source.
+    /// Similar to `anyhow::Error::root_cause`:
+    /// ```ignore
+    /// if let Some(err) = err.root_cause().downcast_ref::<std::io::Error>() {
+    ///     println!("{:?}", err.kind());
+    /// }
+    /// ```
+    ///
+    /// A cyclic chain has no end, the walk stops after 1024 sources then.
+    pub fn root_cause(&self) -> &(dyn Error + 'static) {
+        self.chain().last().unwrap_or(&self.inner)
+    }
+
+    /// The wrapped error and its `source()` chain, like `anyhow::Error::chain`:
+    /// ```ignore
+    /// for cause in err.chain() {
+    ///     println!("{cause}");
+    /// }
+    /// ```
+    /// A nested [`LocatedError`] is visited, not the error it wraps, its `source()` is the source
+    /// of that error. With `layered_source`, both are visited.
+    /// A wrapped [`crate::BoxedError`] (or `AnyhowError`) is looked through, to the error it
+    /// boxes.
+    /// A cyclic chain has no end, the iteration stops after 1024 sources then.
+    pub fn chain(&self) -> impl Iterator<Item = &(dyn Error + 'static)> {
+        self.inspect();
+        // errors can't be told apart by address, an error and its first field share one
+        core::iter::successors(Some(self.chain_start()), |&error| error.source())
+            .take(MAX_CHAIN_DEPTH + 1)
+    }
+
+    /// The first error of type `T` in the `source()` chain, starting with the wrapped error,
+    /// e.g. the located `io::Error` at the bottom of a typed error chain:
+    /// ```ignore
+    /// if let Some(err) = err.downcast_ref::<LocatedError<std::io::Error>>() {
+    ///     println!("{:?}", err.kind());
+    /// }
+    /// ```
+    /// A wrapped [`crate::BoxedError`] (or `AnyhowError`) is looked through, to the error it
+    /// boxes.
+    pub fn downcast_ref<T: Error + 'static>(&self) -> Option<&T> {
+        self.chain().find_map(|error| error.downcast_ref::<T>())
+    }
+
+    /// The wrapped error, if it is a `T`. Unlike [`LocatedError::downcast_ref`], the rest of the
+    /// `source()` chain can't be taken by value.
+    pub fn downcast<T: Error + 'static>(self) -> Result<T, Self> {
+        if TypeId::of::<T>() != TypeId::of::<E>() {
+            return Err(self);
+        }
+        let mut inner = Some(self.into_inner());
+        let inner = (&mut inner as &mut dyn Any).downcast_mut::<Option<T>>();
+        Ok(inner.and_then(Option::take).expect("`T` is `E`"))
+    }
+
+    /// the wrapped error, or the error boxed in a [`crate::BoxedError`] or an `AnyhowError`
+    fn chain_start(&self) -> &(dyn Error + 'static) {
+        #[cfg(feature = "std")]
+        if let Some(boxed) = (&self.inner as &dyn Any).downcast_ref::<crate::BoxedError>() {
+            return &*boxed.0;
+        }
+        #[cfg(feature = "anyhow")]
+        if let Some(anyhow) = (&self.inner as &dyn Any).downcast_ref::<crate::AnyhowError>() {
+            return &*anyhow.0;
+        }
+        &self.inner
+    }
+}
+
+/// `{file}:{line}:{column}`, the file is redacted according to [`crate::FormatOptions`]
+#[cfg(not(passthrough))]
+pub(crate) struct LocationDisplay<'a>(pub(crate) &'a Loc);
+
+#[cfg(not(passthrough))]
+impl fmt::Display for LocationDisplay<'_> {
+    #[cfg(feature = "std")]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}",
+            redact_path(self.0.file()),
+            self.0.line(),
+            self.0.column()
+        )
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// check whether `line` is a `Caused by: ` header written by [`LocatedError`],
+/// i.e. `Caused by: {type}: {desc} ({file}:{line}:{column})`.
+///
+/// The inner error's own `Debug` may contain arbitrary lines, including a literal `Caused by: `,
+/// which must not be taken for a nested [`LocatedError`].
+#[cfg(capture_backtrace)]
+fn is_caused_by_header(line: &str) -> bool {
+    let Some(rest) = line.strip_prefix(DEBUG_CAUSED_BY_PAT) else {
+        return false;
+    };
+    let Some(rest) = rest.strip_suffix(')') else {
+        return false;
+    };
+    let Some(pos) = rest.rfind(" (") else {
+        return false;
+    };
+
+    // `{file}:{line}:{column}`, file names may contain ':' themselves
+    let mut parts = rest[pos + " (".len()..].rsplitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(column), Some(line), Some(file)) => {
+            !file.is_empty() && column.parse::<u32>().is_ok() && line.parse::<u32>().is_ok()
+        }
+        _ => false,
+    }
+}
+
+/// From
+impl<E: Error> From<E> for LocatedError<E> {
+    #[track_caller]
+    fn from(err: E) -> Self {
+        Self::located_at(err, Location::caller())
+    }
+}
+
+/// AsRef
+impl<T: Error> AsRef<T> for LocatedError<T> {
+    fn as_ref(&self) -> &T {
+        self.inspect();
+        &self.inner
+    }
+}
+
+/// Deref
+impl<T: Error> Deref for LocatedError<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inspect();
+        &self.inner
+    }
+}
+
+/// AsMut, e.g. to update a retry counter of the inner error after capture
+impl<T: Error> AsMut<T> for LocatedError<T> {
+    fn as_mut(&mut self) -> &mut T {
+        self.inspect();
+        &mut self.inner
+    }
+}
+
+/// DerefMut
+impl<T: Error> DerefMut for LocatedError<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.inspect();
+        &mut self.inner
+    }
+}
+
+/// Borrow
+impl<T: Error> borrow::Borrow<T> for LocatedError<T> {
+    fn borrow(&self) -> &T {
+        self.inspect();
+        &self.inner
+    }
+}
+
+// Clone
+impl<T: Error + Clone> Clone for LocatedError<T> {
+    fn clone(&self) -> Self {
+        LocatedError {
+            inner: self.inner.clone(),
+
+            #[cfg(not(passthrough))]
+            payload: self.payload.clone(),
+        }
+    }
+}
+
+/// PartialEq, compares the inner errors only, not where they were captured
+impl<T: Error + PartialEq> PartialEq for LocatedError<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+/// Eq
+impl<T: Error + Eq> Eq for LocatedError<T> {}
+
+/// Hash, hashes the inner error only, consistent with `PartialEq`,
+/// see [`crate::ByLocation`] to also hash the capture site
+impl<T: Error + Hash> Hash for LocatedError<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state);
+    }
+}
+
+/// PartialEq with the inner error, e.g. `assert_eq!(err, MyError::NotFound)`
+impl<T: Error + PartialEq> PartialEq<T> for LocatedError<T> {
+    fn eq(&self, other: &T) -> bool {
+        **self == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    pub enum MyError {
+        #[error("MyError {0}")]
+        LocatedIoError(#[from] LocatedError<std::io::Error>),
+    }
+
+    impl From<std::io::Error> for MyError {
+        #[track_caller]
+        fn from(err: std::io::Error) -> Self {
+            MyError::from(LocatedError::from(err))
+        }
+    }
+
+    #[derive(Debug, Error)]
+    pub enum DomainError {
+        #[error("DomainError {0}")]
+        Io(#[from] std::io::Error),
+    }
+
+    fn located_error1() -> Result<(), MyError> {
+        std::fs::File::open("blurb.txt").map_err(LocatedError::<std::io::Error>::from)?;
+        Ok(())
+    }
+
+    fn located_error2() -> Result<(), MyError> {
+        std::fs::File::open("blurb.txt")?;
+        Ok(())
+    }
+
+    /// error with a multi-line `Debug`, which looks like a `Caused by: ` chain
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    struct MultilineError;
+
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    impl fmt::Debug for MultilineError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "MultilineError {{\n    détail: ünïcödé\nCaused by: not a header (at all)\n}}"
+            )
+        }
+    }
+
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    impl fmt::Display for MultilineError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "multiline")
+        }
+    }
+
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    impl Error for MultilineError {}
+
+    use super::*;
+
+    #[test]
+    #[cfg(passthrough)]
+    fn test_passthrough() {
+        use core::mem::size_of;
+
+        assert_eq!(
+            size_of::<LocatedError<std::io::Error>>(),
+            size_of::<std::io::Error>()
+        );
+
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        assert_eq!(err.to_string(), "boom");
+        let sentinel = (UNKNOWN_LOCATION.file(), UNKNOWN_LOCATION.line());
+        assert_eq!(err.site_key(), sentinel);
+        assert_eq!(err.location(), UNKNOWN_LOCATION);
+        assert_eq!(err.try_location(), None);
+    }
+
+    #[test]
+    #[cfg(capture_backtrace)]
+    fn test_caused_by_header() {
+        assert!(is_caused_by_header(
+            "Caused by: example::MyError1: boom (tests/example.rs:29:8)"
+        ));
+        assert!(is_caused_by_header(
+            r"Caused by: std::io::error::Error: boom (C:\src\main.rs:1:2)"
+        ));
+        assert!(!is_caused_by_header("Caused by: not a header (at all)"));
+        assert!(!is_caused_by_header("Caused by: "));
+        assert!(!is_caused_by_header("at (src/main.rs:1:2)"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    fn test_multiline_inner_debug() {
+        let err = LocatedError::from(MultilineError);
+        let debug = format!("{:?}", err);
+        let lines: Vec<&str> = debug.lines().collect();
+
+        assert_eq!(lines[0], "MultilineError {");
+        assert_eq!(lines[1], "    détail: ünïcödé");
+        assert_eq!(lines[2], "Caused by: not a header (at all)");
+        assert_eq!(lines[3], "}");
+        assert!(lines[4].starts_with("Caused by: "));
+        assert!(lines[4].contains("MultilineError: multiline"));
+        assert!(lines[5..].iter().all(|line| line.starts_with(FRAME_PAT)));
+    }
+
+    /// `force_backtrace` is enabled by default, release builds with `release_off` (also enabled
+    /// by default) must not capture, nor store, backtraces nevertheless
+    #[test]
+    #[cfg(all(not(capture_backtrace), not(passthrough)))]
+    fn test_no_backtrace_field() {
+        use core::mem::size_of;
+
+        /// `Payload` without the backtrace field
+        #[allow(dead_code)]
+        struct NoBacktrace {
+            location: Loc,
+            #[cfg(feature = "drop_logging")]
+            guard: DropGuard,
+            #[cfg(feature = "std")]
+            metadata: Option<Box<Metadata>>,
+            #[cfg(feature = "std")]
+            first_seen: Option<Loc>,
+        }
+
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        assert!(!format!("{:?}", err).contains("\tat "));
+
+        assert_eq!(size_of::<Payload>(), size_of::<NoBacktrace>());
+        assert_eq!(
+            size_of::<LocatedError<std::io::Error>>(),
+            size_of::<(std::io::Error, PayloadPtr)>()
+        );
+    }
+
+    /// the accessors exist in every profile, without capturing anything in release, passthrough
+    /// and `release_off` builds
+    #[test]
+    #[cfg(not(capture_backtrace))]
+    fn test_backtrace_not_captured() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        assert!(err.backtrace().is_none());
+        assert!(err.stacktrace().is_none());
+
+        let (_, _, backtrace) = err.into_parts();
+        assert!(backtrace.is_none());
+    }
+
+    /// location, backtrace and metadata are boxed, `LocatedError<E>` is `E` plus one pointer
+    #[test]
+    fn test_size() {
+        use core::mem::size_of;
+
+        fn assert_size<E: Error>() {
+            let expected = if cfg!(passthrough) {
+                size_of::<E>()
+            } else {
+                size_of::<(E, usize)>()
+            };
+            assert_eq!(size_of::<LocatedError<E>>(), expected);
+        }
+
+        assert_size::<std::io::Error>();
+        assert_size::<core::fmt::Error>();
+        assert_size::<DomainError>();
+        // the payload pointer is a niche
+        assert_eq!(
+            size_of::<Result<(), LocatedError<std::io::Error>>>(),
+            size_of::<LocatedError<std::io::Error>>()
+        );
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_convert() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let err: LocatedError<DomainError> = err.convert();
+        assert_eq!(err.site_key(), (file!(), line));
+        assert!(matches!(*err, DomainError::Io(_)));
+        assert!(err.to_string().starts_with("DomainError boom"));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_new_at() {
+        let location = Location::caller();
+        let err = LocatedError::new_at(std::io::Error::other("a"), location, None);
+        assert_eq!(err.location(), location);
+
+        let (_, _, backtrace) = err.into_parts();
+        let err = LocatedError::new_at(std::io::Error::other("b"), location, backtrace.clone());
+        assert_eq!(err.location(), location);
+        #[cfg(capture_backtrace)]
+        match backtrace {
+            Some(backtrace) => assert!(std::sync::Arc::ptr_eq(&err.payload.backtrace, &backtrace)),
+            #[cfg(feature = "force_backtrace")]
+            None => panic!("no forced backtrace"),
+            #[cfg(not(feature = "force_backtrace"))]
+            None => {}
+        }
+    }
+
+    #[test]
+    fn test_without_backtrace() {
+        let io = std::io::Error::other("a");
+        let (err, line) = (LocatedError::without_backtrace(io), line!());
+        #[cfg(capture_backtrace)]
+        assert!(err.backtrace().is_none());
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_with_field() {
+        let err = LocatedError::from(std::io::Error::other("boom"))
+            .with_field("request_id", "req-42")
+            .with_field("retry", 3);
+        assert_eq!(err.field("retry"), Some("3"));
+        assert_eq!(err.field("user"), None);
+        assert!(
+            format!("{err:?}").contains(" {request_id=req-42, retry=3}"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_with_help() {
+        let err = LocatedError::from(std::io::Error::other("boom")).with_help("create it first");
+        if cfg!(passthrough) {
+            assert_eq!(err.help(), None);
+        } else {
+            assert_eq!(err.help(), Some("create it first"));
+            assert!(
+                format!("{err:?}").ends_with("\nhelp: create it first"),
+                "{err:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_url() {
+        let err = LocatedError::from(std::io::Error::other("boom"))
+            .with_help("retry later")
+            .with_url("https://runbooks.example.com/io");
+        if cfg!(passthrough) {
+            assert_eq!(err.url(), None);
+        } else {
+            assert_eq!(err.url(), Some("https://runbooks.example.com/io"));
+            let debug = format!("{err:?}");
+            assert!(
+                debug.ends_with("\nhelp: retry later\nsee: https://runbooks.example.com/io"),
+                "{debug}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_with_function() {
+        let err = LocatedError::from(std::io::Error::other("boom"))
+            .with_function("app::config::load")
+            .with_function("app::main");
+        if cfg!(passthrough) {
+            assert_eq!(err.function(), None);
+        } else {
+            assert_eq!(err.function().as_deref(), Some("app::config::load"));
+        }
+    }
+
+    #[test]
+    fn test_source_of_inner() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        if cfg!(not(layered)) {
+            assert!(err.source().is_none());
+        }
+
+        let err = LocatedError::from(DomainError::Io(std::io::Error::other("boom")));
+        let source = err.source();
+        #[cfg(layered)]
+        let source = source.and_then(Error::source);
+        let source = source.and_then(|source| source.downcast_ref::<std::io::Error>());
+        assert_eq!(source.map(ToString::to_string).as_deref(), Some("boom"));
+    }
+
+    #[test]
+    #[cfg(layered)]
+    fn test_layered_source() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        let inner = err
+            .source()
+            .and_then(|source| source.downcast_ref::<std::io::Error>());
+        assert_eq!(inner.map(ToString::to_string).as_deref(), Some("boom"));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_source_location() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let location = err.source_location();
+        assert_eq!((location.file(), location.line()), (file!(), line));
+
+        let err = LocatedError::from_parts(std::io::Error::other("boom"), location.clone());
+        assert_eq!(err.source_location(), location);
+    }
+
+    #[test]
+    fn test_into_shared() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let err = err.into_shared();
+        let clone = err.clone();
+        assert!(Arc::ptr_eq(&err, &clone));
+        assert_eq!(clone.to_string(), err.to_string());
+        if cfg!(not(passthrough)) {
+            clone.assert_located_at(file!(), line);
+        }
+    }
+
+    #[test]
+    fn test_partial_eq() {
+        #[derive(Debug, Error, PartialEq, Eq)]
+        enum Lookup {
+            #[error("not found")]
+            NotFound,
+            #[error("denied")]
+            Denied,
+        }
+
+        let err = LocatedError::from(Lookup::NotFound);
+        assert_eq!(err, Lookup::NotFound);
+        assert_ne!(err, Lookup::Denied);
+        // the location is ignored
+        assert_eq!(err, LocatedError::from(Lookup::NotFound));
+        assert_ne!(err, LocatedError::from(Lookup::Denied));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_relocate() {
+        let (err, first) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        assert_eq!(err.first_seen(), None);
+
+        let (err, line) = (err.relocate(), line!());
+        err.assert_located_at(file!(), line);
+        assert_eq!(err.first_seen().unwrap().line(), first);
+
+        // the earliest location is kept
+        let location = Location::caller();
+        let err = err.relocated_at(location);
+        assert_eq!(err.location(), location);
+        assert_eq!(err.first_seen().unwrap().line(), first);
+    }
+
+    #[test]
+    fn test_deref_mut() {
+        #[derive(Debug, Error)]
+        #[error("failed after {attempts} attempts")]
+        struct RetryError {
+            attempts: u32,
+        }
+
+        let (mut err, line) = (LocatedError::from(RetryError { attempts: 1 }), line!());
+        err.attempts += 1;
+        err.as_mut().attempts += 1;
+        assert_eq!(err.attempts, 3);
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "provide", not(passthrough)))]
+    fn test_provide() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let err: &(dyn Error + 'static) = &err;
+        let location = core::error::request_ref::<Location<'static>>(err).unwrap();
+        assert_eq!((location.file(), location.line()), (file!(), line));
+
+        #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+        assert!(core::error::request_ref::<CapturedBacktrace>(err).is_some());
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_map() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        #[cfg(capture_backtrace)]
+        let backtrace = err.payload.backtrace.clone();
+        let err = err.map(|err| std::io::Error::new(std::io::ErrorKind::NotFound, err));
+        err.assert_located_at(file!(), line);
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        #[cfg(capture_backtrace)]
+        assert!(std::sync::Arc::ptr_eq(&err.payload.backtrace, &backtrace));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_root_cause() {
+        #[derive(Debug, Error)]
+        enum AppError {
+            #[error("AppError {0}")]
+            My(#[from] LocatedError<MyError>),
+        }
+
+        let err = LocatedError::from(AppError::from(LocatedError::from(MyError::from(
+            std::io::Error::other("boom"),
+        ))));
+        let root = err.root_cause();
+        if cfg!(layered) {
+            assert_eq!(root.to_string(), "boom");
+            assert!(root.downcast_ref::<std::io::Error>().is_some());
+        } else {
+            assert!(root.to_string().starts_with("boom"), "{root}");
+            assert!(
+                root.downcast_ref::<LocatedError<std::io::Error>>()
+                    .is_some()
+            );
+        }
+
+        let err = LocatedError::from(std::io::Error::other("leaf"));
+        assert!(err.root_cause().downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn test_downcast() {
+        let inner = LocatedError::from(MyError::from(std::io::Error::other("boom")));
+        let err = LocatedError::from(DomainError::Io(std::io::Error::other("outer")));
+        assert_eq!(
+            err.downcast_ref::<std::io::Error>().unwrap().to_string(),
+            "outer"
+        );
+        assert!(err.downcast_ref::<MyError>().is_none());
+
+        assert!(inner.downcast_ref::<MyError>().is_some());
+        assert!(
+            inner
+                .downcast_ref::<LocatedError<std::io::Error>>()
+                .is_some()
+        );
+        if cfg!(layered) {
+            let io = inner.downcast_ref::<std::io::Error>();
+            assert_eq!(io.unwrap().to_string(), "boom");
+        }
+
+        let err = inner.downcast::<std::io::Error>().unwrap_err();
+        assert!(matches!(
+            err.downcast::<MyError>(),
+            Ok(MyError::LocatedIoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_chain() {
+        let err = LocatedError::from(MyError::from(std::io::Error::other("boom")));
+        let chain: Vec<String> = err.chain().map(|error| error.to_string()).collect();
+        if cfg!(passthrough) {
+            assert_eq!(chain, ["MyError boom", "boom"]);
+        } else {
+            assert_eq!(chain.len(), if cfg!(layered) { 3 } else { 2 });
+            assert!(chain[0].starts_with("MyError boom"), "{chain:?}");
+            assert!(chain[1].starts_with("boom; Caused by "), "{chain:?}");
+            if cfg!(layered) {
+                assert_eq!(chain[2], "boom");
+            }
+        }
+    }
+
+    #[test]
+    fn test_root_cause_cycle() {
+        #[derive(Debug)]
+        struct Cyclic;
+
+        impl fmt::Display for Cyclic {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "cyclic")
+            }
+        }
+
+        impl Error for Cyclic {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&CYCLIC)
+            }
+        }
+
+        static CYCLIC: Cyclic = Cyclic;
+
+        let err = LocatedError::from(Cyclic);
+        assert_eq!(err.root_cause().to_string(), "cyclic");
+        assert_eq!(err.chain().count(), 1025);
+    }
+
+    #[test]
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    fn test_redacted_paths() {
+        use crate::format_options::{FormatOptions, PathRedaction, REDACTED};
+
+        let err = LocatedError::from(MultilineError);
+
+        FormatOptions {
+            path_redaction: PathRedaction::AllowList(Vec::new()),
+            ..Default::default()
+        }
+        .install();
+        let debug = format!("{:?}", err);
+        FormatOptions::default().install();
+
+        let mut files = 0;
+        let located = |line: &&str| line.starts_with(FRAME_PAT) || is_caused_by_header(line);
+        for line in debug.lines().filter(located) {
+            if let Some(pos) = line.rfind(" (") {
+                assert!(line[pos + " (".len()..].starts_with(REDACTED), "{line}");
+                files += 1;
+            }
+        }
+        assert!(files > 1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    fn test_display_top_frame() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        assert!(!err.to_string().contains(" [at "));
+
+        FormatOptions {
+            display_top_frame: true,
+            ..Default::default()
+        }
+        .install();
+        let display = err.to_string();
+        FormatOptions::default().install();
+
+        assert!(display.starts_with("boom; Caused by std::io::error::Error("));
+        assert!(display.contains(" [at "));
+        assert!(display.ends_with("];"));
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(passthrough)))]
+    fn test_short_type_names() {
+        use crate::format_options::FormatOptions;
+
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        FormatOptions {
+            short_type_names: true,
+            ..Default::default()
+        }
+        .install();
+        let display = err.to_string();
+        FormatOptions::default().install();
+
+        assert!(display.starts_with("boom; Caused by Error("), "{display}");
+        assert_eq!(err.type_name(), "std::io::error::Error");
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", not(passthrough)))]
+    fn test_error_name() {
+        #[derive(Debug, Error)]
+        #[error("named {0:?}")]
+        struct NamedError<T: fmt::Debug>(T);
+
+        impl<T: fmt::Debug> ErrorName for NamedError<T> {
+            fn error_name() -> &'static str {
+                "NamedError"
+            }
+        }
+
+        let err = LocatedError::from(NamedError(1u8));
+        assert!(err.to_string().contains("NamedError<u8>("));
+
+        let err = err.named();
+        let display = err.to_string();
+        assert!(
+            display.starts_with("named 1; Caused by NamedError("),
+            "{display}"
+        );
+        assert!(!format!("{:?}", err).contains("NamedError<u8>"));
+        // resolved where the type is known, like the `From` impls of `#[backerror]`
+        assert_eq!(crate::__error_name!(NamedError<u8>), Some("NamedError"));
+        assert_eq!(crate::__error_name!(std::io::Error), None);
+
+        // the name belongs to `E`
+        let err = err.map(|err| std::io::Error::other(err.to_string()));
+        assert!(err.to_string().contains("std::io::error::Error("));
+    }
+
+    /// fails to compile if `$ty` implements `$trait`, the method is ambiguous then
+    macro_rules! assert_not_impl {
+        ($ty:ty: $trait:path) => {
+            const _: fn() = || {
+                trait AmbiguousIfImpl<A> {
+                    fn some_item() {}
+                }
+                impl<T: ?Sized> AmbiguousIfImpl<()> for T {}
+                struct Invalid;
+                impl<T: ?Sized + $trait> AmbiguousIfImpl<Invalid> for T {}
+                let _ = <$ty as AmbiguousIfImpl<_>>::some_item;
+            };
+        };
+    }
+
+    /// `LocatedError<E>` is `Send` and `Sync` through the auto traits, exactly when `E` is
+    #[test]
+    fn test_auto_traits() {
+        #[derive(Debug, Error)]
+        #[error("not send")]
+        struct NotSend(std::rc::Rc<()>);
+
+        #[derive(Debug, Error)]
+        #[error("not sync")]
+        struct NotSync(std::cell::Cell<u32>);
+
+        fn assert_send<T: Send>() {}
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<LocatedError<std::io::Error>>();
+        assert_send::<LocatedError<NotSync>>();
+        assert_not_impl!(LocatedError<NotSend>: Send);
+        assert_not_impl!(LocatedError<NotSend>: Sync);
+        assert_not_impl!(LocatedError<NotSync>: Sync);
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_send_across_threads() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<SendLocatedError<std::io::Error>>();
+
+        let handle = std::thread::spawn(|| {
+            let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+            (Err::<(), SendLocatedError<_>>(err), line)
+        });
+        let (result, line) = handle.join().unwrap();
+        let err = result.unwrap_err();
+        assert_eq!(err.site_key(), (file!(), line));
+        assert!(err.to_string().starts_with("boom; Caused by "));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_assert_located_at() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        err.assert_located_at("located_error.rs", line);
+        err.assert_located_at(file!(), line);
+    }
+
+    #[test]
+    #[should_panic(expected = "error `boom` was captured at ")]
+    fn test_assert_located_at_mismatch() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        err.assert_located_at("located_error.rs", line + 1);
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_parts_round_trip() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let display = err.to_string();
+        let (inner, location) = err.into_source_parts();
+        assert_eq!(location.line(), line);
+
+        let err = LocatedError::from_parts(inner, location.clone());
+        err.assert_located_at(file!(), line);
+        assert_eq!(err.to_string(), display);
+
+        let (inner, round_trip) = err.into_source_parts();
+        assert_eq!(inner.to_string(), "boom");
+        assert_eq!(round_trip, location);
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_location() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        assert_eq!(err.location().file(), file!());
+        assert_eq!(err.location().line(), line);
+        assert_eq!(err.try_location(), Some(err.location()));
+
+        assert_eq!(err.source_site_key(), (Cow::Borrowed(file!()), line));
+
+        // rebuilt errors have no `Location`, `source_site_key` still has the real one
+        let (inner, location) = err.into_source_parts();
+        let err = LocatedError::from_parts(inner, location);
+        assert_eq!(err.location(), UNKNOWN_LOCATION);
+        assert_eq!(UNKNOWN_LOCATION.file(), file!());
+        assert_eq!(err.try_location(), None);
+        let sentinel = (UNKNOWN_LOCATION.file(), UNKNOWN_LOCATION.line());
+        assert_eq!(err.site_key(), sentinel);
+        assert!(
+            matches!(err.source_site_key(), (Cow::Owned(file), l) if file == file!() && l == line)
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    fn test_parts_with_backtrace() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        let (inner, location, backtrace) = err.into_source_parts_with_backtrace();
+        let err = LocatedError::from_parts_with_backtrace(inner, location, backtrace);
+        assert!(err.stacktrace().is_some());
+
+        let (inner, location) = err.into_source_parts();
+        assert!(
+            LocatedError::from_parts(inner, location)
+                .stacktrace()
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn test_into_parts() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        #[cfg(capture_backtrace)]
+        let captured = err.backtrace().is_some();
+        #[cfg(not(capture_backtrace))]
+        let captured = false;
+        let (inner, location, backtrace) = err.into_parts();
+        assert_eq!(inner.to_string(), "boom");
+        assert_eq!((location.file(), location.line()), (file!(), line));
+        assert_eq!(backtrace.is_some(), captured);
+
+        let err = LocatedError::from_parts(inner, SourceLocation::from(location));
+        let (_, location, backtrace) = err.into_parts();
+        assert_eq!(location, UNKNOWN_LOCATION);
+        assert!(backtrace.is_none());
+    }
+
+    #[test]
+    #[cfg(all(feature = "force_backtrace", capture_backtrace))]
+    fn test_backtrace() {
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        assert!(err.backtrace().is_some());
+
+        let (inner, location) = err.into_source_parts();
+        assert!(
+            LocatedError::from_parts(inner, location)
+                .backtrace()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_into_inner() {
+        let err = LocatedError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "boom"));
+        let inner = err.into_inner();
+        assert_eq!(inner.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(inner.to_string(), "boom");
     }
 
-    use super::*;
     #[test]
     fn test_located_error() {
         if let Err(e) = located_error1() {
@@ -0,0 +1,99 @@
+use crate::report::write_error_report;
+use core::error::Error;
+use core::fmt;
+use std::io::IsTerminal;
+use std::process::{ExitCode, Termination};
+
+/// Error returned by a `#[backerror::main]` function: any `Error + 'static`, e.g. a `#[backerror]`
+/// type or a [`crate::LocatedError`], or a [`crate::Error`]
+#[doc(hidden)]
+pub trait __MainError {
+    fn __as_error(&self) -> &(dyn Error + 'static);
+}
+
+impl<E: Error + 'static> __MainError for E {
+    fn __as_error(&self) -> &(dyn Error + 'static) {
+        self
+    }
+}
+
+impl __MainError for crate::Error {
+    fn __as_error(&self) -> &(dyn Error + 'static) {
+        &**self
+    }
+}
+
+/// Exit code of the result of a `#[backerror::main]` function, printing the report of its error
+#[doc(hidden)]
+pub fn __main_result<T: Termination, E: __MainError>(result: Result<T, E>) -> ExitCode {
+    match result {
+        Ok(value) => value.report(),
+        Err(err) => {
+            let report = MainReport(err.__as_error());
+            if color() {
+                eprintln!("\x1b[1;31mError:\x1b[0m {report}");
+            } else {
+                eprintln!("Error: {report}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// whether the standard error is a terminal, and `NO_COLOR` isn't set, see <https://no-color.org>
+fn color() -> bool {
+    std::io::stderr().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// [`crate::Report`] of any error, the located layers are found in its `source()` chain, and the
+/// backtrace is the one of the innermost located layer which captured one
+struct MainReport<'a>(&'a (dyn Error + 'static));
+
+impl fmt::Display for MainReport<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(capture_backtrace)]
+        let frames =
+            crate::report::innermost_stacktrace(self.0).map(|stacktrace| stacktrace.frames);
+        #[cfg(not(capture_backtrace))]
+        let frames = None::<[&str; 0]>;
+        write_error_report(f, self.0, frames, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{__main_result, MainReport};
+    use crate::LocatedError;
+    use std::process::ExitCode;
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    enum CliError {
+        #[error("failed to sync")]
+        Sync(#[from] LocatedError<std::io::Error>),
+    }
+
+    #[test]
+    fn main_report() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let err = CliError::from(err);
+
+        let report = MainReport(&err).to_string();
+        let mut lines = report.lines();
+        assert_eq!(lines.next(), Some("failed to sync"));
+        assert!(report.contains("\n\nCaused by:\n    0: boom"), "{report}");
+        if cfg!(not(passthrough)) {
+            let located = format!("at {}:{line}:", file!());
+            assert!(report.contains(&located), "{report}");
+        }
+        #[cfg(all(capture_backtrace, feature = "force_backtrace"))]
+        assert!(report.contains("\n\nStack backtrace:\n"), "{report}");
+    }
+
+    #[test]
+    fn main_result() {
+        assert_eq!(__main_result(Ok::<(), CliError>(())), ExitCode::SUCCESS);
+        let err = crate::Error::from(std::io::Error::other("boom"));
+        assert_eq!(__main_result(Err::<(), _>(err)), ExitCode::FAILURE);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::LocatedError;
+use core::error::Error;
+use core::fmt;
+use core::panic::Location;
+
+/// Message as an error type, so that it can be wrapped into a [`LocatedError`], e.g. a formatted
+/// `String` for an ad-hoc error. [`fmt::Display`] and [`fmt::Debug`] are the ones of `T`.
+pub struct Message<T: fmt::Display + fmt::Debug = String>(pub T);
+
+/// [`LocatedError`] of a plain message, created with [`LocatedError::msg`]:
+/// ```ignore
+/// fn check(len: usize) -> Result<(), LocatedMessage> {
+///     if len > MAX_LEN {
+///         return Err(LocatedError::msg(format!("{len} bytes exceed the limit")));
+///     }
+///     Ok(())
+/// }
+/// ```
+pub type LocatedMessage<T = String> = LocatedError<Message<T>>;
+
+impl<T: fmt::Display + fmt::Debug> LocatedError<Message<T>> {
+    /// Wrap `message`, capturing the caller's location
+    #[track_caller]
+    pub fn msg(message: T) -> Self {
+        LocatedError::located_at(Message(message), Location::caller())
+    }
+}
+
+impl<T: fmt::Display + fmt::Debug> fmt::Display for Message<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl<T: fmt::Display + fmt::Debug> fmt::Debug for Message<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl<T: fmt::Display + fmt::Debug> Error for Message<T> {}
+
+/// Create a [`LocatedMessage`] from a format string, located at the `located!` call site, for a
+/// one-off error without declaring a variant:
+/// ```ignore
+/// let header = lines.next().ok_or_else(|| located!("missing header in {path}"))?;
+/// return Err(located!("bad header {header}"))?;
+/// ```
+/// It is [`LocatedError::msg`] with `format!` arguments. `?` converts it into any error type
+/// with a `From<LocatedMessage>` impl, e.g. [`crate::Error`] or a `#[backerror]` type with a
+/// `#[from] backerror::LocatedMessage` variant.
+#[macro_export]
+macro_rules! located {
+    ($($arg:tt)+) => {
+        $crate::LocatedError::msg(::std::format!($($arg)+))
+    };
+}
+
+/// Alias of [`located!`], named like `anyhow::format_err!`.
+#[macro_export]
+macro_rules! format_err {
+    ($($arg:tt)+) => {
+        $crate::located!($($arg)+)
+    };
+}
+
+/// Return early with an error located at the `bail!` call site, like `anyhow::bail!`.
+///
+/// A message, with `format!` arguments or not, is converted from a [`Message`], and an error
+/// expression is converted as it is, with [`From`] into the error type of the function:
+/// ```ignore
+/// fn open(path: &str) -> Result<File, backerror::Error> {
+///     if path.is_empty() {
+///         bail!("empty path"); // located here
+///     }
+///     if !path.ends_with(".toml") {
+///         bail!("{path} is not a TOML file");
+///     }
+///     if !Path::new(path).exists() {
+///         bail!(io::Error::from(io::ErrorKind::NotFound));
+///     }
+///     Ok(File::open(path)?)
+/// }
+/// ```
+/// The error type is [`crate::Error`], [`LocatedMessage`], or a `#[backerror]` type with a
+/// `#[from] backerror::Message` variant for the messages, and the `#[from]` types of its
+/// variants for the errors.
+#[macro_export]
+macro_rules! bail {
+    ($msg:literal $(,)?) => {
+        return ::core::result::Result::Err(::core::convert::From::from($crate::Message(
+            ::std::format!($msg),
+        )))
+    };
+    ($err:expr $(,)?) => {
+        return ::core::result::Result::Err(::core::convert::From::from($err))
+    };
+    ($fmt:expr, $($arg:tt)*) => {
+        return ::core::result::Result::Err(::core::convert::From::from($crate::Message(
+            ::std::format!($fmt, $($arg)*),
+        )))
+    };
+}
+
+/// Return early with an error located at the `ensure!` call site unless `cond` holds, like
+/// `anyhow::ensure!`. The error is the one of [`bail!`] with the other arguments, or a
+/// `condition failed` [`Message`] without:
+/// ```ignore
+/// ensure!(len <= MAX_LEN, "{len} bytes exceed the limit");
+/// ensure!(!name.is_empty()); // "condition failed: `!name.is_empty()`"
+/// ```
+#[macro_export]
+macro_rules! ensure {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            return ::core::result::Result::Err(::core::convert::From::from($crate::Message(
+                ::std::string::String::from(::core::concat!(
+                    "condition failed: `",
+                    ::core::stringify!($cond),
+                    "`"
+                )),
+            )));
+        }
+    };
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            $crate::bail!($($arg)+);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LocatedMessage;
+    use crate::LocatedError;
+
+    #[test]
+    fn located_message() {
+        let (err, line): (LocatedMessage, _) = (LocatedError::msg(format!("{} bytes", 3)), line!());
+        assert_eq!(err.0, "3 bytes");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+
+        let err = LocatedError::msg(42);
+        assert_eq!(err.0, 42);
+        assert!(format!("{err:?}").contains("42"));
+    }
+
+    #[test]
+    fn located_macro() {
+        let len = 3;
+        let (err, line): (LocatedMessage, _) = (crate::located!("{len} bytes"), line!());
+        assert_eq!(err.0, "3 bytes");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+
+        let (err, line) = (crate::format_err!("{} bytes", len + 1), line!());
+        assert_eq!(err.0, "4 bytes");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), line);
+        }
+    }
+
+    const BAIL_LINE: u32 = line!() + 2;
+    fn check(len: usize) -> Result<(), LocatedMessage> {
+        crate::ensure!(len > 0);
+        crate::ensure!(len <= 4, "{len} bytes exceed the limit");
+        if len == 3 {
+            crate::bail!("odd length");
+        }
+        Ok(())
+    }
+
+    fn erased(len: usize) -> Result<usize, crate::Error> {
+        if len == 0 {
+            crate::bail!(std::io::Error::other("empty"));
+        }
+        crate::bail!("{} bytes", len)
+    }
+
+    #[test]
+    fn bail_ensure() {
+        assert!(check(2).is_ok());
+        let err = check(0).unwrap_err();
+        assert_eq!(err.0, "condition failed: `len > 0`");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), BAIL_LINE);
+        }
+        let err = check(5).unwrap_err();
+        assert_eq!(err.0, "5 bytes exceed the limit");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), BAIL_LINE + 1);
+        }
+        let err = check(3).unwrap_err();
+        assert_eq!(err.0, "odd length");
+        if cfg!(not(passthrough)) {
+            err.assert_located_at(file!(), BAIL_LINE + 3);
+        }
+
+        let err = erased(0).unwrap_err();
+        assert!(err.downcast_ref::<std::io::Error>().is_some());
+        let err = erased(2).unwrap_err();
+        assert_eq!(err.downcast::<super::Message>().unwrap().0, "2 bytes");
+    }
+}
@@ -0,0 +1,43 @@
+use crate::loc::Loc;
+use crate::report::is_located;
+use core::error::Error;
+use opentelemetry::KeyValue;
+use opentelemetry::trace::get_active_span;
+
+/// Record an `exception` event on the current span, following the OpenTelemetry semantic
+/// conventions. Nothing is recorded without a recording span, or if `error` wraps a
+/// [`crate::LocatedError`], which was recorded when it was created.
+pub(crate) fn record_exception(
+    type_name: &'static str,
+    error: &dyn Error,
+    location: &Loc,
+    stacktrace: impl FnOnce() -> Option<String>,
+) {
+    get_active_span(|span| {
+        if !span.is_recording() || has_located_source(error) {
+            return;
+        }
+
+        let mut attributes = vec![
+            KeyValue::new("exception.type", type_name),
+            KeyValue::new("exception.message", error.to_string()),
+            KeyValue::new("code.filepath", location.file().to_string()),
+            KeyValue::new("code.lineno", i64::from(location.line())),
+        ];
+        if let Some(stacktrace) = stacktrace() {
+            attributes.push(KeyValue::new("exception.stacktrace", stacktrace));
+        }
+        span.add_event("exception", attributes);
+    });
+}
+
+fn has_located_source(error: &dyn Error) -> bool {
+    let mut source = error.source();
+    while let Some(error) = source {
+        if is_located(error) {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
@@ -0,0 +1,576 @@
+use crate::LocatedError;
+use crate::SourceLocation;
+use crate::loc::Loc;
+#[cfg(not(passthrough))]
+use crate::located_error::LocationDisplay;
+use crate::located_error::UNKNOWN_LOCATION;
+#[cfg(capture_backtrace)]
+use crate::stacktrace::{CapturedBacktrace, StackTrace};
+#[cfg(not(passthrough))]
+use core::cell::Cell;
+use core::error::Error;
+use core::fmt;
+use core::panic::Location;
+#[cfg(capture_backtrace)]
+use std::sync::Arc;
+#[cfg(not(passthrough))]
+use std::thread::LocalKey;
+
+/// Human facing rendering of a [`LocatedError`] and its `source()` chain, similar to `anyhow`:
+/// ```text
+/// failed to load config
+///     at src/main.rs:12:5
+///
+/// Caused by:
+///     0: No such file or directory (os error 2)
+///        at src/config.rs:30:9
+///
+/// Stack backtrace:
+///     at app::config::load (src/config.rs:30)
+///     ...
+///
+/// help: create it with `app init`
+/// ```
+/// `help:` lines list the help texts of the located layers, see [`LocatedError::with_help`].
+/// Created by [`LocatedError::report`].
+///
+/// Like `std::error::Report`, [`Report::pretty`] toggles between this layout and a single line,
+/// and [`Report::show_backtrace`] toggles the backtrace:
+/// ```text
+/// failed to load config (at src/main.rs:12:5): No such file or directory (os error 2) (at src/config.rs:30:9)
+/// ```
+pub struct Report<'a, E: Error + 'static> {
+    error: &'a LocatedError<E>,
+    pretty: bool,
+    show_backtrace: bool,
+}
+
+impl<E: Error + 'static> Report<'_, E> {
+    /// the multi-line layout if `true` (the default), else the causes on the same line, separated
+    /// by `: `, without help lines
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// list the frames of the captured backtrace, if any, `true` by default
+    pub fn show_backtrace(mut self, show_backtrace: bool) -> Self {
+        self.show_backtrace = show_backtrace;
+        self
+    }
+}
+
+impl<E: Error + 'static> LocatedError<E> {
+    /// Render this error for humans, e.g. `eprintln!("{}", err.report())` in a CLI tool.
+    ///
+    /// Unlike [`fmt::Display`] and [`fmt::Debug`], the causes are listed one per line, each with its
+    /// location if it was captured by a [`LocatedError`].
+    pub fn report(&self) -> Report<'_, E> {
+        Report {
+            error: self,
+            pretty: true,
+            show_backtrace: true,
+        }
+    }
+
+    /// Message and location of each layer of the `source()` chain, starting with this error.
+    ///
+    /// A [`LocatedError`] and the error it wraps are one layer, with the location of the
+    /// [`LocatedError`]. Layers which are not located have no location.
+    /// ```ignore
+    /// for (message, location) in err.flatten() {
+    ///     println!("{message} ({})", location.map(|l| l.to_string()).unwrap_or_default());
+    /// }
+    /// ```
+    pub fn flatten(&self) -> Vec<(String, Option<SourceLocation>)> {
+        layers(self)
+            .into_iter()
+            .map(|(message, located)| {
+                let location = located.map(|located| SourceLocation::from(&located.location));
+                (message, location)
+            })
+            .collect()
+    }
+
+    /// Propagation trail: capture locations of the located layers of the `source()` chain,
+    /// starting with this error, i.e. each `?` which wrapped the error into a [`LocatedError`].
+    ///
+    /// Rendered by [`fmt::Debug`] as `-> {file}:{line}` lines after the location of this error.
+    pub fn trail(&self) -> Vec<SourceLocation> {
+        trail(self).iter().map(SourceLocation::from).collect()
+    }
+
+    /// [`LocatedError::trail`] as `&'static Location`s, e.g. to print a compact propagation path:
+    /// ```ignore
+    /// let path: Vec<String> = err.locations().iter().map(|l| l.line().to_string()).collect();
+    /// eprintln!("propagated through lines {}", path.join(" <- "));
+    /// ```
+    /// Layers rebuilt with [`LocatedError::from_parts`] have the sentinel of
+    /// [`LocatedError::location`].
+    pub fn locations(&self) -> Vec<&'static Location<'static>> {
+        trail(self)
+            .into_iter()
+            .map(|location| location.static_location().unwrap_or(UNKNOWN_LOCATION))
+            .collect()
+    }
+}
+
+impl<E: Error + 'static> fmt::Display for Report<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(capture_backtrace)]
+        let frames = self.error.stacktrace().map(|stacktrace| stacktrace.frames);
+        #[cfg(not(capture_backtrace))]
+        let frames = None::<[&str; 0]>;
+        let frames = frames.filter(|_| self.show_backtrace);
+        write_error_report(f, self.error, frames, self.pretty)
+    }
+}
+
+/// [`Report`] of `error` with the frames of its backtrace, also used for the errors which aren't
+/// a [`LocatedError`] themselves, e.g. by `#[backerror::main]`
+pub(crate) fn write_error_report<F: fmt::Display>(
+    f: &mut fmt::Formatter<'_>,
+    error: &(dyn Error + 'static),
+    frames: Option<impl IntoIterator<Item = F>>,
+    pretty: bool,
+) -> fmt::Result {
+    let (layers, helps) = layers_with_help(error);
+    let layers = layers.into_iter().map(|(message, located)| {
+        let location = located.map(|located| location_line(&located.location));
+        (message, location)
+    });
+
+    if !pretty {
+        for (index, (message, location)) in layers.enumerate() {
+            if index > 0 {
+                f.write_str(": ")?;
+            }
+            write!(f, "{message}")?;
+            if let Some(location) = location {
+                write!(f, " ({location})")?;
+            }
+        }
+        return write_report(f, core::iter::empty::<(String, Option<String>)>(), frames);
+    }
+    write_report(f, layers, frames)?;
+
+    // actionable hints last, where a CLI user sees them
+    for (index, help) in helps.iter().enumerate() {
+        let separator = if index == 0 { "\n\n" } else { "\n" };
+        write!(f, "{separator}help: {help}")?;
+    }
+    Ok(())
+}
+
+impl<E: Error + 'static> fmt::Debug for Report<'_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// messages with their `at ..` location lines, and the backtrace frames, laid out as [`Report`]
+pub(crate) fn write_report<M, F>(
+    f: &mut fmt::Formatter<'_>,
+    layers: impl IntoIterator<Item = (M, Option<String>)>,
+    frames: Option<impl IntoIterator<Item = F>>,
+) -> fmt::Result
+where
+    M: fmt::Display,
+    F: fmt::Display,
+{
+    for (index, (message, location)) in layers.into_iter().enumerate() {
+        if index == 0 {
+            write!(f, "{message}")?;
+            if let Some(location) = location {
+                write!(f, "\n    {location}")?;
+            }
+            continue;
+        }
+
+        let index = index - 1;
+        if index == 0 {
+            write!(f, "\n\nCaused by:")?;
+        }
+        write!(f, "\n{index:>5}: {message}")?;
+        if let Some(location) = location {
+            write!(f, "\n       {location}")?;
+        }
+    }
+
+    if let Some(frames) = frames {
+        write!(f, "\n\nStack backtrace:")?;
+        for frame in frames {
+            write!(f, "\n    at {frame}")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// `at {file}:{line}:{column}`
+#[cfg(not(passthrough))]
+fn location_line(location: &Loc) -> String {
+    format!("at {}", LocationDisplay(location))
+}
+
+#[cfg(passthrough)]
+fn location_line(location: &Loc) -> String {
+    format!("at {location}")
+}
+
+/// Location and type name of a [`LocatedError`] layer
+#[derive(Clone)]
+pub(crate) struct Located {
+    pub location: Loc,
+    pub name: &'static str,
+}
+
+/// Message of `error` and each of its sources, with location and type name of the located ones,
+/// a located error and the error it wraps are one layer.
+pub(crate) fn layers(error: &(dyn Error + 'static)) -> Vec<(String, Option<Located>)> {
+    layers_with_help(error).0
+}
+
+/// [`layers`], and the help texts of the located layers, outermost first
+pub(crate) fn layers_with_help(
+    error: &(dyn Error + 'static),
+) -> (Vec<(String, Option<Located>)>, Vec<String>) {
+    let mut layers = Vec::new();
+    let mut helps = Vec::new();
+    let mut next = Some(error);
+    while let Some(error) = next {
+        let (message, located, help) = describe(error);
+        next = next_source(error, located.is_some());
+        layers.push((message, located));
+        helps.extend(help);
+    }
+    (layers, helps)
+}
+
+/// capture locations of the located layers of `error` and its sources
+pub(crate) fn trail(error: &(dyn Error + 'static)) -> Vec<Loc> {
+    layers(error)
+        .into_iter()
+        .filter_map(|(_, located)| Some(located?.location))
+        .collect()
+}
+
+/// the source of `error`, with `layered_source` the source of a located error is the error it
+/// wraps, which was already rendered with it
+fn next_source<'a>(
+    error: &'a (dyn Error + 'static),
+    located: bool,
+) -> Option<&'a (dyn Error + 'static)> {
+    if cfg!(layered) && located {
+        error.source().and_then(Error::source)
+    } else {
+        error.source()
+    }
+}
+
+/// a [`LocatedError`] layer answering a [`probe`]
+#[cfg(not(passthrough))]
+pub(crate) struct Probed {
+    pub location: Loc,
+    pub name: &'static str,
+    pub help: Option<String>,
+    #[cfg(capture_backtrace)]
+    pub backtrace: Option<Arc<CapturedBacktrace>>,
+}
+
+#[cfg(not(passthrough))]
+thread_local! {
+    /// set while the messages of the layers of a report are rendered, see [`is_plain`]
+    static PLAIN: Cell<bool> = const { Cell::new(false) };
+    /// `Some` while a [`probe`] runs, with the layer which answered it
+    #[cfg(not(feature = "provide"))]
+    static PROBE: Cell<Option<Option<Probed>>> = const { Cell::new(None) };
+}
+
+/// Sets a thread-local until dropped, then restores the outer value, also when the `Display` or
+/// `description()` of a layer panics.
+#[cfg(not(passthrough))]
+struct Scoped<T: 'static> {
+    key: &'static LocalKey<Cell<T>>,
+    outer: Option<T>,
+}
+
+#[cfg(not(passthrough))]
+impl<T: 'static> Scoped<T> {
+    fn set(key: &'static LocalKey<Cell<T>>, value: T) -> Self {
+        let outer = Some(key.replace(value));
+        Self { key, outer }
+    }
+}
+
+#[cfg(not(passthrough))]
+impl<T: 'static> Drop for Scoped<T> {
+    fn drop(&mut self) {
+        if let Some(outer) = self.outer.take() {
+            self.key.set(outer);
+        }
+    }
+}
+
+/// Whether the [`fmt::Display`] of [`LocatedError`] writes only the message of the wrapped error,
+/// the location and type name of each layer are rendered by the report.
+///
+/// Only set while [`describe`] renders the message of one layer.
+#[cfg(not(passthrough))]
+pub(crate) fn is_plain() -> bool {
+    PLAIN.get()
+}
+
+/// Called by `Error::description` of [`LocatedError`]; while a [`probe`] runs, records the first
+/// (outermost) layer answering it.
+#[cfg(all(not(passthrough), not(feature = "provide")))]
+pub(crate) fn answer_probe(probed: impl FnOnce() -> Probed) {
+    let mut probe = PROBE.take();
+    if let Some(answer @ None) = &mut probe {
+        *answer = Some(probed());
+    }
+    PROBE.set(probe);
+}
+
+/// `error` if it's a [`LocatedError`], requested through `Error::provide`.
+///
+/// Smart pointers such as `Box` and `Arc` forward `provide()`, `Display` and `source()`, so they
+/// are one layer with the [`LocatedError`] they point to.
+#[cfg(all(not(passthrough), feature = "provide"))]
+fn probe(error: &(dyn Error + 'static)) -> Option<Probed> {
+    core::error::request_value::<Probed>(error)
+}
+
+/// `error` if it's a [`LocatedError`].
+///
+/// `dyn Error` can't be downcast to a `LocatedError<_>` of unknown type and `Error::provide` is
+/// unstable, so `error.description()` is called, which only `LocatedError` answers; wrappers such
+/// as `#[error(transparent)]` forward `Display` and `source()` but not `description()`, nor do
+/// `Box` and `Arc`, so a boxed [`LocatedError`] is only located with the `provide` feature.
+#[cfg(all(not(passthrough), not(feature = "provide")))]
+fn probe(error: &(dyn Error + 'static)) -> Option<Probed> {
+    let _probe = Scoped::set(&PROBE, Some(None));
+    #[allow(deprecated)]
+    let _ = error.description();
+    PROBE.take().flatten()
+}
+
+/// Message of `error` and, if it's a [`LocatedError`], its location, type name and help text.
+#[cfg(not(passthrough))]
+fn describe(error: &(dyn Error + 'static)) -> (String, Option<Located>, Option<String>) {
+    let message = {
+        let _plain = Scoped::set(&PLAIN, true);
+        error.to_string()
+    };
+    let Some(probed) = probe(error) else {
+        return (message, None, None);
+    };
+    let located = Located {
+        location: probed.location,
+        name: probed.name,
+    };
+    (message, Some(located), probed.help)
+}
+
+/// frames of the backtrace of the innermost located layer of `error` which captured one, the
+/// closest to where the error occurred
+#[cfg(capture_backtrace)]
+pub(crate) fn innermost_stacktrace(error: &(dyn Error + 'static)) -> Option<StackTrace> {
+    let mut backtrace = None;
+    let mut next = Some(error);
+    while let Some(error) = next {
+        let probed = probe(error);
+        next = next_source(error, probed.is_some());
+        backtrace = probed.and_then(|probed| probed.backtrace).or(backtrace);
+    }
+    StackTrace::from_captured(&*backtrace?)
+}
+
+/// whether `error` is a [`LocatedError`]
+#[cfg(all(feature = "opentelemetry", not(passthrough)))]
+pub(crate) fn is_located(error: &(dyn Error + 'static)) -> bool {
+    probe(error).is_some()
+}
+
+#[cfg(passthrough)]
+fn describe(error: &(dyn Error + 'static)) -> (String, Option<Located>, Option<String>) {
+    (error.to_string(), None, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocatedError;
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    enum ConfigError {
+        #[error("failed to read config")]
+        Read(#[from] LocatedError<std::io::Error>),
+    }
+
+    #[test]
+    fn report_chain() {
+        let (err, inner_line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let (err, outer_line) = (LocatedError::from(ConfigError::from(err)), line!());
+
+        let report = err.report().to_string();
+        let mut lines = report.lines();
+        assert_eq!(lines.next(), Some("failed to read config"));
+        assert!(!report.contains("; Caused by "), "{report}");
+        assert!(report.contains("\n\nCaused by:\n    0: boom"), "{report}");
+        if cfg!(not(passthrough)) {
+            let located = |line: u32| format!("at {}:{line}:", file!());
+            assert!(report.contains(&located(outer_line)), "{report}");
+            assert!(report.contains(&located(inner_line)), "{report}");
+        }
+        // `boom` is listed once, not again as the source of its `LocatedError`
+        assert!(!report.contains("1: "), "{report}");
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn report_help() {
+        let err = LocatedError::from(std::io::Error::other("boom")).with_help("check the path");
+        let err = LocatedError::from(ConfigError::from(err)).with_help("run `app init`");
+
+        let report = err.report().to_string();
+        assert!(
+            report.ends_with("\n\nhelp: run `app init`\nhelp: check the path"),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn report_one_line() {
+        let (err, inner_line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let (err, outer_line) = (LocatedError::from(ConfigError::from(err)), line!());
+
+        let report = err.report().pretty(false).show_backtrace(false).to_string();
+        if cfg!(passthrough) {
+            assert_eq!(report, "failed to read config: boom");
+        } else {
+            let at = |line: u32| format!("(at {}:{line}:", file!());
+            assert!(report.starts_with("failed to read config "), "{report}");
+            assert!(report.contains(&at(outer_line)), "{report}");
+            assert!(
+                report.contains(&format!("): boom {}", at(inner_line))),
+                "{report}"
+            );
+        }
+        assert!(!report.contains('\n'), "{report}");
+    }
+
+    #[cfg(not(passthrough))]
+    #[derive(Debug, Error)]
+    enum AppError {
+        #[error("failed to start")]
+        Config(#[from] LocatedError<ConfigError>),
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn flatten_chain() {
+        let (err, io_line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let (err, config_line) = (LocatedError::from(ConfigError::from(err)), line!());
+        let (err, app_line) = (LocatedError::from(AppError::from(err)), line!());
+
+        let layers = err.flatten();
+        let lines: Vec<_> = layers
+            .iter()
+            .map(|(message, location)| {
+                let location = location.as_ref().unwrap();
+                assert_eq!(location.file(), file!());
+                (message.as_str(), location.line())
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                ("failed to start", app_line),
+                ("failed to read config", config_line),
+                ("boom", io_line)
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn locations_of_nested_errors() {
+        let (err, io_line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let (err, config_line) = (LocatedError::from(ConfigError::from(err)), line!());
+        let (err, app_line) = (LocatedError::from(AppError::from(err)), line!());
+
+        let locations = err.locations();
+        assert!(locations.iter().all(|location| location.file() == file!()));
+        let lines: Vec<u32> = locations.iter().map(|location| location.line()).collect();
+        assert_eq!(lines, [app_line, config_line, io_line]);
+    }
+
+    #[test]
+    fn flatten_unlocated_source() {
+        #[derive(Debug, Error)]
+        #[error("outer")]
+        struct Outer(#[source] std::io::Error);
+
+        let err = LocatedError::from(Outer(std::io::Error::other("inner")));
+        let layers = err.flatten();
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[1], ("inner".to_string(), None));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn transparent_wrapper_is_not_located() {
+        #[derive(Debug, Error)]
+        #[error(transparent)]
+        struct Wrapper(LocatedError<std::io::Error>);
+
+        let err = Wrapper(LocatedError::from(std::io::Error::other("boom")));
+        let layers = super::layers(&err);
+        assert_eq!(layers[0].0, "boom");
+        assert!(layers.iter().all(|(_, located)| located.is_none()));
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn boxed_located_error_is_located_with_provide() {
+        #[derive(Debug, Error)]
+        #[error("failed to sync")]
+        struct SyncError(#[source] Box<LocatedError<std::io::Error>>);
+
+        let (io, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let layers = super::layers(&SyncError(Box::new(io)));
+        assert_eq!(layers[1].0, "boom");
+        // `Box` forwards `provide()`, but not the `description()` probed on stable compilers
+        if cfg!(feature = "provide") {
+            assert_eq!(layers.len(), 2);
+            let location = layers[1].1.as_ref().map(|located| located.location.line());
+            assert_eq!(location, Some(line));
+        } else {
+            assert!(layers.iter().all(|(_, located)| located.is_none()));
+        }
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn panicking_layer_resets_display() {
+        #[derive(Debug)]
+        struct Panics;
+
+        impl std::fmt::Display for Panics {
+            fn fmt(&self, _: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                panic!("Display of a layer panicked")
+            }
+        }
+
+        impl std::error::Error for Panics {}
+
+        let err = LocatedError::from(Panics);
+        let report =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| err.report().to_string()));
+        assert!(report.is_err());
+
+        let err = LocatedError::from(std::io::Error::other("boom"));
+        assert!(err.to_string().contains(file!()), "{err}");
+    }
+}
@@ -0,0 +1,126 @@
+use crate::LocatedError;
+#[cfg(not(passthrough))]
+use crate::format_options::redact_path;
+#[cfg(capture_backtrace)]
+use crate::stacktrace::StackTraceFrame;
+use core::error::Error;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+/// Serialized as a structured object, e.g. for JSON logs:
+/// ```text
+/// {"message":"No such file or directory (os error 2)","type":"std::io::error::Error",
+///  "file":"src/config.rs","line":30,"column":9,"url":null,"fields":{"user_id":"42"},
+///  "frames":[{"func":"app::config::load","file":"src/config.rs","line":30}]}
+/// ```
+/// `url` is the one attached with [`LocatedError::with_url`], if any.
+/// `frames` is empty without a captured backtrace. Passthrough builds have no `type` and
+/// location, they are `null` then. Files are redacted according to [`crate::FormatOptions`].
+impl<E: Error> Serialize for LocatedError<E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("LocatedError", 8)?;
+        state.serialize_field("message", &format_args!("{}", **self))?;
+
+        #[cfg(not(passthrough))]
+        {
+            let location = self.loc();
+            state.serialize_field("type", self.error_name())?;
+            state.serialize_field("file", &redact_path(location.file()))?;
+            state.serialize_field("line", &location.line())?;
+            state.serialize_field("column", &location.column())?;
+        }
+        #[cfg(passthrough)]
+        for key in ["type", "file", "line", "column"] {
+            state.serialize_field(key, &None::<()>)?;
+        }
+
+        state.serialize_field("url", &self.url())?;
+        state.serialize_field("fields", &Fields(self.fields()))?;
+
+        #[cfg(capture_backtrace)]
+        let frames = self
+            .stacktrace()
+            .map(|stacktrace| stacktrace.frames)
+            .unwrap_or_default();
+        #[cfg(not(capture_backtrace))]
+        let frames: [(); 0] = [];
+        state.serialize_field("frames", &frames[..])?;
+        state.end()
+    }
+}
+
+/// fields attached with [`LocatedError::with_field`], as a `{key: value}` object
+struct Fields<'a>(&'a [(String, String)]);
+
+impl Serialize for Fields<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(key, value)| (key, value)))
+    }
+}
+
+/// `{"func":..,"file":..,"line":..}`, the file is redacted according to [`crate::FormatOptions`]
+#[cfg(capture_backtrace)]
+impl Serialize for StackTraceFrame {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("StackTraceFrame", 3)?;
+        state.serialize_field("func", &self.func)?;
+        state.serialize_field("file", &redact_path(&self.file))?;
+        state.serialize_field("line", &self.line)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::LocatedError;
+
+    #[test]
+    fn serialize_located() {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        let json = serde_json::to_string(&err).unwrap();
+
+        let head = if cfg!(passthrough) {
+            r#"{"message":"boom","type":null,"file":null,"line":null,"column":null,"#.to_string()
+        } else {
+            let name = core::any::type_name::<std::io::Error>();
+            format!(
+                r#"{{"message":"boom","type":"{name}","file":"{}","line":{line},"#,
+                file!()
+            )
+        };
+        assert!(json.starts_with(&head), "{json}");
+        assert!(
+            json.contains(r#","url":null,"fields":{},"frames":["#),
+            "{json}"
+        );
+        if cfg!(all(feature = "force_backtrace", capture_backtrace)) {
+            assert!(json.contains(r#","frames":[{"func":"#), "{json}");
+        } else {
+            assert!(json.ends_with(r#","frames":[]}"#), "{json}");
+        }
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn serialize_fields() {
+        let err = LocatedError::from(std::io::Error::other("boom"))
+            .with_field("user_id", 42)
+            .with_field("retry", 3);
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(
+            json.contains(r#","fields":{"user_id":"42","retry":"3"},"#),
+            "{json}"
+        );
+    }
+
+    #[test]
+    #[cfg(not(passthrough))]
+    fn serialize_url() {
+        let err = LocatedError::from(std::io::Error::other("boom"))
+            .with_url("https://runbooks.example.com/io");
+        let json = serde_json::to_string(&err).unwrap();
+        assert!(
+            json.contains(r#","url":"https://runbooks.example.com/io","#),
+            "{json}"
+        );
+    }
+}
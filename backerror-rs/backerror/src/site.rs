@@ -0,0 +1,93 @@
+use crate::LocatedError;
+use core::error::Error;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+/// Aggregates counts of [`LocatedError`]s by their capture site.
+/// ```ignore
+/// let mut counter = SiteCounter::new();
+/// counter.record(&err);
+/// for ((file, line), count) in counter.iter() {
+///     println!("{count} errors from {file}:{line}");
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SiteCounter {
+    /// counts by file, then by line, the files of captured locations are borrowed
+    counts: HashMap<Cow<'static, str>, HashMap<u32, usize>>,
+}
+
+impl SiteCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record one error at its capture site
+    pub fn record<E: Error>(&mut self, err: &LocatedError<E>) {
+        let (file, line) = err.source_site_key();
+        let lines = self.counts.entry(file).or_default();
+        *lines.entry(line).or_insert(0) += 1;
+    }
+
+    /// number of errors recorded for `site`
+    pub fn count(&self, (file, line): (&str, u32)) -> usize {
+        let lines = self.counts.get(file);
+        lines
+            .and_then(|lines| lines.get(&line))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// number of distinct sites
+    pub fn len(&self) -> usize {
+        self.counts.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// iterate over `((file, line), count)`
+    pub fn iter(&self) -> impl Iterator<Item = ((&str, u32), usize)> + '_ {
+        self.counts.iter().flat_map(|(file, lines)| {
+            lines
+                .iter()
+                .map(|(line, count)| ((file.as_ref(), *line), *count))
+        })
+    }
+}
+
+#[cfg(all(test, not(passthrough)))]
+mod tests {
+    use super::SiteCounter;
+    use crate::LocatedError;
+
+    fn io_error() -> std::io::Error {
+        std::io::Error::other("boom")
+    }
+
+    #[test]
+    fn count_by_site() {
+        let mut errors = Vec::new();
+        for _ in 0..3 {
+            errors.push(LocatedError::from(io_error()));
+        }
+        for _ in 0..2 {
+            errors.push(LocatedError::from(io_error()));
+        }
+        let (other, other_line) = (LocatedError::from(io_error()), line!());
+        errors.push(other);
+
+        let mut counter = SiteCounter::new();
+        for e in &errors {
+            counter.record(e);
+        }
+
+        assert_eq!(counter.len(), 3);
+        assert_eq!(counter.count(errors[0].site_key()), 3);
+        assert_eq!(counter.count(errors[3].site_key()), 2);
+        assert_eq!(counter.count((file!(), other_line)), 1);
+        assert_eq!(counter.count((file!(), 0)), 0);
+        assert_eq!(counter.iter().map(|(_, n)| n).sum::<usize>(), 6);
+    }
+}
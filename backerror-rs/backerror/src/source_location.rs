@@ -0,0 +1,78 @@
+use core::fmt;
+use core::panic::Location;
+
+/// Owned capture location of a [`crate::LocatedError`], e.g. to keep or send it somewhere
+/// independently of the error, see [`crate::LocatedError::source_location`].
+///
+/// The fields are private, so that more details like the function name can be added later; it's
+/// built with [`SourceLocation::new`] or from a [`Location`] and read through its accessors.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceLocation {
+    file: String,
+    line: u32,
+    column: u32,
+}
+
+impl SourceLocation {
+    /// location at `line` and `column` of `file`
+    pub fn new(file: impl Into<String>, line: u32, column: u32) -> Self {
+        SourceLocation {
+            file: file.into(),
+            line,
+            column,
+        }
+    }
+
+    /// path of the source file, as given by `file!()`
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// line number, starting at 1
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// column number, starting at 1
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+impl From<&Location<'_>> for SourceLocation {
+    fn from(location: &Location<'_>) -> Self {
+        SourceLocation {
+            file: location.file().to_string(),
+            line: location.line(),
+            column: location.column(),
+        }
+    }
+}
+
+/// `{file}:{line}:{column}`
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SourceLocation;
+    use core::panic::Location;
+
+    #[test]
+    fn accessors() {
+        let location = Location::caller();
+        let source = SourceLocation::from(location);
+        assert_eq!(source.file(), location.file());
+        assert_eq!(source.line(), location.line());
+        assert_eq!(source.column(), location.column());
+        assert_eq!(
+            source,
+            SourceLocation::new(file!(), source.line(), source.column())
+        );
+        assert_eq!(source.to_string(), location.to_string());
+    }
+}
@@ -1,18 +1,208 @@
+use crate::format_options::redact_path;
 use std::backtrace::{Backtrace, BacktraceStatus};
+use std::fmt;
+#[cfg(feature = "external_backtrace")]
+use std::ops::Deref;
+#[cfg(feature = "external_backtrace")]
+use std::sync::OnceLock;
+
+/// Backtrace captured by [`crate::LocatedError`]
+#[cfg(not(feature = "external_backtrace"))]
+pub(crate) type CapturedBacktrace = Backtrace;
+
+/// Backtrace captured by [`crate::LocatedError`] with the `backtrace` crate, derefs to the
+/// `backtrace::Backtrace`. Its frames are resolved once, the first time they're needed, and shared
+/// by the clones of the error.
+#[cfg(feature = "external_backtrace")]
+pub struct CapturedBacktrace {
+    backtrace: backtrace_rs::Backtrace,
+    stacktrace: OnceLock<Option<StackTrace>>,
+}
+
+#[cfg(feature = "external_backtrace")]
+impl From<backtrace_rs::Backtrace> for CapturedBacktrace {
+    fn from(backtrace: backtrace_rs::Backtrace) -> Self {
+        CapturedBacktrace {
+            backtrace,
+            stacktrace: OnceLock::new(),
+        }
+    }
+}
+
+#[cfg(feature = "external_backtrace")]
+impl Deref for CapturedBacktrace {
+    type Target = backtrace_rs::Backtrace;
+
+    fn deref(&self) -> &Self::Target {
+        &self.backtrace
+    }
+}
+
+#[cfg(feature = "external_backtrace")]
+impl fmt::Debug for CapturedBacktrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.backtrace, f)
+    }
+}
+
+/// capture a backtrace only if environment variable `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` is set
+#[cfg(all(
+    capture_backtrace,
+    not(feature = "external_backtrace"),
+    not(feature = "force_backtrace")
+))]
+pub(crate) fn capture() -> CapturedBacktrace {
+    Backtrace::capture()
+}
+
+/// capture a backtrace even if environment variable is not set
+#[cfg(all(
+    capture_backtrace,
+    not(feature = "external_backtrace"),
+    feature = "force_backtrace"
+))]
+pub(crate) fn capture() -> CapturedBacktrace {
+    Backtrace::force_capture() // or Backtrace::disabled()
+}
+
+/// capture the frames of a backtrace only if environment variable `RUST_BACKTRACE` or
+/// `RUST_LIB_BACKTRACE` is set, like [`Backtrace::capture`]
+#[cfg(all(
+    capture_backtrace,
+    feature = "external_backtrace",
+    not(feature = "force_backtrace")
+))]
+pub(crate) fn capture() -> CapturedBacktrace {
+    if env_enabled() {
+        force_capture()
+    } else {
+        disabled()
+    }
+}
+
+/// capture the frames of a backtrace even if environment variable is not set
+#[cfg(all(
+    capture_backtrace,
+    feature = "external_backtrace",
+    feature = "force_backtrace"
+))]
+pub(crate) fn capture() -> CapturedBacktrace {
+    force_capture()
+}
 
-#[derive(Debug)]
+/// whether backtraces are enabled by the environment, read once like [`Backtrace::capture`]:
+/// `RUST_LIB_BACKTRACE`, or else `RUST_BACKTRACE`, is set to anything but `0`
+#[cfg(all(
+    capture_backtrace,
+    feature = "external_backtrace",
+    not(feature = "force_backtrace")
+))]
+fn env_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| {
+        std::env::var_os("RUST_LIB_BACKTRACE")
+            .or_else(|| std::env::var_os("RUST_BACKTRACE"))
+            .is_some_and(|value| value != "0")
+    })
+}
+
+/// capture a backtrace regardless of the environment and features
+#[cfg(all(capture_backtrace, not(feature = "external_backtrace")))]
+pub(crate) fn force_capture() -> CapturedBacktrace {
+    Backtrace::force_capture()
+}
+
+/// capture the frames of a backtrace regardless of the environment and features, symbols are
+/// resolved lazily when formatted
+#[cfg(all(capture_backtrace, feature = "external_backtrace"))]
+pub(crate) fn force_capture() -> CapturedBacktrace {
+    backtrace_rs::Backtrace::new_unresolved().into()
+}
+
+/// backtrace without frames, for errors which were not captured at the call site
+#[cfg(not(feature = "external_backtrace"))]
+pub(crate) fn disabled() -> CapturedBacktrace {
+    Backtrace::disabled()
+}
+
+/// backtrace without frames, for errors which were not captured at the call site
+#[cfg(feature = "external_backtrace")]
+pub(crate) fn disabled() -> CapturedBacktrace {
+    backtrace_rs::Backtrace::from(Vec::new()).into()
+}
+
+/// whether frames were captured
+#[cfg(all(capture_backtrace, not(feature = "external_backtrace")))]
+pub(crate) fn is_captured(backtrace: &CapturedBacktrace) -> bool {
+    backtrace.status() == BacktraceStatus::Captured
+}
+
+/// whether frames were captured
+#[cfg(all(capture_backtrace, feature = "external_backtrace"))]
+pub(crate) fn is_captured(backtrace: &CapturedBacktrace) -> bool {
+    !backtrace.frames().is_empty()
+}
+
+/// Normalized frames of a captured backtrace
+#[derive(Clone, Debug)]
 pub struct StackTrace {
     pub frames: Vec<StackTraceFrame>,
 }
 
-#[derive(Debug)]
+/// One frame of a [`StackTrace`], `file` is empty and `line` is `0` when unknown
+#[derive(Clone, Debug)]
 pub struct StackTraceFrame {
     pub func: String,
     pub file: String,
     pub line: u32,
 }
 
+/// `{func} ({file}:{line})`, the file is redacted according to [`crate::FormatOptions`]
+impl fmt::Display for StackTraceFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.file.is_empty() {
+            write!(f, "{}", self.func)
+        } else {
+            write!(
+                f,
+                "{} ({}:{})",
+                self.func,
+                redact_path(&self.file),
+                self.line
+            )
+        }
+    }
+}
+
 impl StackTrace {
+    /// number of frames
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// the first (innermost) `n` frames, or all frames if there are fewer
+    pub fn head(&self, n: usize) -> &[StackTraceFrame] {
+        &self.frames[..n.min(self.frames.len())]
+    }
+
+    /// the last (outermost) `n` frames, or all frames if there are fewer
+    pub fn tail(&self, n: usize) -> &[StackTraceFrame] {
+        &self.frames[self.frames.len() - n.min(self.frames.len())..]
+    }
+
+    /// the innermost frame which doesn't belong to `std`, `core`, `alloc` or `backerror`
+    pub fn top_user_frame(&self) -> Option<&StackTraceFrame> {
+        const NON_USER: [&str; 4] = ["std::", "core::", "alloc::", "backerror::"];
+        self.frames.iter().find(|frame| {
+            let func = frame.func.trim_start_matches('<');
+            !NON_USER.iter().any(|prefix| func.starts_with(prefix))
+        })
+    }
+
     /// parse [`Backtrace`]'s debug output
     pub fn parse(backtrace: &Backtrace) -> Option<Self> {
         if backtrace.status() != BacktraceStatus::Captured {
@@ -30,6 +220,54 @@ impl StackTrace {
         }
     }
 
+    /// stacktrace of a backtrace captured by [`crate::LocatedError`]
+    #[cfg(not(feature = "external_backtrace"))]
+    pub(crate) fn from_captured(backtrace: &CapturedBacktrace) -> Option<Self> {
+        Self::parse(backtrace)
+    }
+
+    /// stacktrace of a backtrace captured by [`crate::LocatedError`], resolved the first time
+    #[cfg(feature = "external_backtrace")]
+    pub(crate) fn from_captured(backtrace: &CapturedBacktrace) -> Option<Self> {
+        backtrace
+            .stacktrace
+            .get_or_init(|| Self::resolve(&backtrace.backtrace))
+            .clone()
+    }
+
+    /// resolve the frames of a `backtrace::Backtrace` directly, without parsing any debug output
+    #[cfg(feature = "external_backtrace")]
+    pub fn resolve(backtrace: &backtrace_rs::Backtrace) -> Option<Self> {
+        let mut backtrace = backtrace.clone();
+        backtrace.resolve();
+
+        let mut frames = Vec::new();
+        for frame in backtrace.frames() {
+            for symbol in frame.symbols() {
+                let Some(name) = symbol.name() else {
+                    continue;
+                };
+                frames.push(StackTraceFrame {
+                    // alternate format strips the trailing hash
+                    func: format!("{:#}", name),
+                    file: symbol
+                        .filename()
+                        .map(|file| file.display().to_string())
+                        .unwrap_or_default(),
+                    line: symbol.lineno().unwrap_or(0),
+                });
+            }
+        }
+
+        if frames.is_empty() {
+            None
+        } else {
+            let mut stacktrace = StackTrace { frames };
+            stacktrace.nomalize();
+            Some(stacktrace)
+        }
+    }
+
     /// parse [`Backtrace`]'s debug output
     pub fn parse_debug_str(debug: &str) -> Option<Self> {
         const LEADING: &str = "Backtrace ";
@@ -93,15 +331,15 @@ impl StackTrace {
     /// * Remove leading frames owned by [`Backtrace`]
     fn nomalize(&mut self) {
         //  * Remove leading frames owned by [`Backtrace`]
-        loop {
-            if let Some(first) = self.frames.first() {
-                if first.func.starts_with("std::backtrace")
-                    || first.func.starts_with("backerror::located_error")
-                {
-                    self.frames.remove(0);
-                } else {
-                    break;
-                }
+        while let Some(first) = self.frames.first() {
+            // `<backerror::located_error::LocatedError<E> as core::convert::From<E>>::from`
+            let func = first.func.trim_start_matches('<');
+            if func.starts_with("std::backtrace")
+                || func.starts_with("backtrace::")
+                || func.starts_with("backerror::stacktrace::capture")
+                || func.starts_with("backerror::located_error")
+            {
+                self.frames.remove(0);
             } else {
                 break;
             }
@@ -206,6 +444,44 @@ fn find_key_value(input: &str, key: &str) -> Option<String> {
 mod tests {
     use super::StackTrace;
 
+    const FIXTURE: &str = r#"Backtrace [{ fn: "example::throw_error1", file: "./tests/example.rs", line: 24 }, { fn: "example::throw_error2", file: "./tests/example.rs", line: 29 }, { fn: "example::main", file: "./tests/example.rs", line: 40 }, { fn: "__libc_start_main" }]"#;
+
+    #[test]
+    fn slice_frames() {
+        let stack = StackTrace::parse_debug_str(FIXTURE).unwrap();
+        assert_eq!(stack.len(), 4);
+        assert!(!stack.is_empty());
+
+        let head = stack.head(2);
+        assert_eq!(head.len(), 2);
+        assert_eq!(head[0].func, "example::throw_error1");
+        assert_eq!(head[1].line, 29);
+
+        let tail = stack.tail(1);
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].func, "__libc_start_main");
+        assert!(tail[0].file.is_empty());
+
+        assert_eq!(stack.head(10).len(), 4);
+        assert_eq!(stack.tail(10).len(), 4);
+        assert!(stack.head(0).is_empty());
+        assert!(stack.tail(0).is_empty());
+    }
+
+    #[test]
+    fn top_user_frame() {
+        let stack = StackTrace::parse_debug_str(
+            r#"Backtrace [{ fn: "<core::result::Result<T, F> as core::ops::try_trait::FromResidual>::from_residual" }, { fn: "example::throw_error1", file: "./tests/example.rs", line: 24 }]"#,
+        )
+        .unwrap();
+        let frame = stack.top_user_frame().unwrap();
+        assert_eq!(frame.func, "example::throw_error1");
+        assert_eq!(
+            frame.to_string(),
+            "example::throw_error1 (./tests/example.rs:24)"
+        );
+    }
+
     #[test]
     fn parse_backtrace() {
         let backtrace = std::backtrace::Backtrace::force_capture();
@@ -221,4 +497,38 @@ mod tests {
         println!("{}", backtrace);
         println!("{:?}", stack);
     }
+
+    #[test]
+    #[cfg(feature = "external_backtrace")]
+    fn resolve_backtrace() {
+        let backtrace = backtrace_rs::Backtrace::new_unresolved();
+
+        let stack = StackTrace::resolve(&backtrace);
+
+        assert!(stack.is_some());
+        let stack = stack.unwrap();
+        assert!(!stack.frames.is_empty());
+        assert!(stack.frames.iter().all(|frame| !frame.func.is_empty()));
+        assert!(
+            stack
+                .frames
+                .iter()
+                .any(|frame| frame.func.contains("resolve_backtrace"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "external_backtrace")]
+    fn resolve_once() {
+        let backtrace = super::force_capture();
+        assert!(backtrace.stacktrace.get().is_none());
+
+        let stack = StackTrace::from_captured(&backtrace);
+        assert!(backtrace.stacktrace.get().is_some());
+        let again = StackTrace::from_captured(&backtrace);
+        assert_eq!(
+            stack.map(|stack| stack.len()),
+            again.map(|stack| stack.len())
+        );
+    }
 }
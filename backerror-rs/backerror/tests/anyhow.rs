@@ -0,0 +1,48 @@
+#![cfg(all(feature = "anyhow", debug_assertions))]
+
+use backerror::backerror;
+use thiserror::Error;
+
+#[backerror(accessors)]
+#[derive(Debug, Error)]
+pub enum MigratingError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// code not migrated yet, wrapped into `LocatedError<AnyhowError>`
+    #[error("legacy: {0}")]
+    Legacy(#[from] anyhow::Error),
+}
+
+fn legacy(path: &str) -> anyhow::Result<String> {
+    let content = std::fs::read_to_string(path)?;
+    anyhow::ensure!(!content.is_empty(), "empty file {path}");
+    Ok(content)
+}
+
+fn load(path: &str) -> Result<String, MigratingError> {
+    Ok(legacy(path)?)
+}
+
+#[test]
+fn test_anyhow_variant_located() {
+    let (err, line) = (MigratingError::from(anyhow::anyhow!("boom")), line!());
+    let MigratingError::Legacy(located) = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(located.site_key(), (file!(), line));
+    assert!(err.to_string().starts_with("legacy: boom"));
+}
+
+#[test]
+fn test_anyhow_variant_chain() {
+    let err = load("/nonexistent/backerror").unwrap_err();
+    assert!(err.is_legacy());
+    let legacy = err.as_legacy().unwrap();
+    assert!(legacy.downcast_ref::<std::io::Error>().is_some());
+
+    let MigratingError::Legacy(located) = &err else {
+        panic!("{err:?}");
+    };
+    assert!(located.downcast_ref::<std::io::Error>().is_some());
+}
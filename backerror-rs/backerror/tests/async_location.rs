@@ -0,0 +1,27 @@
+#![cfg(debug_assertions)]
+
+use backerror::{LocatedError, located_await};
+
+async fn fail() -> Result<(), std::io::Error> {
+    tokio::task::yield_now().await;
+    Err(std::io::Error::other("boom"))
+}
+
+async fn load() -> (Result<(), LocatedError<std::io::Error>>, u32) {
+    (located_await!(fail()), line!())
+}
+
+#[tokio::test]
+async fn test_located_await() {
+    let (result, line) = load().await;
+    let err = result.unwrap_err();
+    assert_eq!(err.site_key(), (file!(), line));
+}
+
+#[tokio::test]
+async fn test_located_at() {
+    let location = std::panic::Location::caller();
+    let err = fail().await.unwrap_err();
+    let err = LocatedError::located_at(err, location);
+    assert_eq!(err.site_key(), (file!(), location.line()));
+}
@@ -0,0 +1,64 @@
+use backerror::{Message, backerror, ensure};
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Invalid(#[from] Message),
+}
+
+#[cfg(debug_assertions)]
+const UPLOAD_LINE: u32 = line!() + 3;
+#[cfg(debug_assertions)]
+fn upload(name: &str, len: usize) -> Result<(), UploadError> {
+    ensure!(!name.is_empty());
+    ensure!(len <= 16, "{name}: {len} bytes exceed the limit");
+    if name.starts_with('.') {
+        backerror::bail!(std::io::Error::other("hidden file"));
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_bail_into_backerror_type() {
+    assert!(upload("a.txt", 3).is_ok());
+
+    let UploadError::Invalid(err) = upload("", 3).unwrap_err() else {
+        unreachable!()
+    };
+    assert_eq!(err.0, "condition failed: `!name.is_empty()`");
+    err.assert_located_at(file!(), UPLOAD_LINE);
+
+    let UploadError::Invalid(err) = upload("a.txt", 20).unwrap_err() else {
+        unreachable!()
+    };
+    assert_eq!(err.0, "a.txt: 20 bytes exceed the limit");
+    err.assert_located_at(file!(), UPLOAD_LINE + 1);
+
+    let UploadError::Io(err) = upload(".env", 3).unwrap_err() else {
+        unreachable!()
+    };
+    err.assert_located_at(file!(), UPLOAD_LINE + 3);
+}
+
+#[test]
+fn test_bail_into_erased_error() {
+    fn parse(text: &str) -> Result<u32, backerror::Error> {
+        ensure!(text.len() < 8, "too long: {}", text.len());
+        Ok(text.parse::<u32>()?)
+    }
+
+    assert_eq!(parse("42").unwrap(), 42);
+    let err = parse("123456789").unwrap_err();
+    assert_eq!(err.downcast::<Message>().unwrap().0, "too long: 9");
+    assert!(
+        parse("x")
+            .unwrap_err()
+            .downcast_ref::<std::num::ParseIntError>()
+            .is_some()
+    );
+}
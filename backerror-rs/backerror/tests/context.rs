@@ -0,0 +1,48 @@
+use backerror::{LocatedError, backerror};
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// generics are carried over to the generated `context`
+#[backerror]
+#[derive(Debug, Error)]
+#[error("invalid {0}")]
+pub struct Invalid<T: std::fmt::Debug + std::fmt::Display>(T);
+
+fn read_config() -> Result<(), ConfigError> {
+    Err(std::io::Error::other("boom"))?
+}
+
+#[test]
+fn test_context_on_backerror_type() {
+    let (err, line) = (read_config().unwrap_err().context("loading"), line!());
+    assert_eq!(err.message(), "loading");
+    assert!(matches!(err.error(), ConfigError::Io(_)));
+    if cfg!(debug_assertions) {
+        err.assert_located_at(file!(), line);
+    }
+
+    let err: LocatedError<_> = Invalid(3).context(format!("step {}", 2));
+    assert_eq!(err.message(), "step 2");
+    assert_eq!(err.error().to_string(), "invalid 3");
+
+    let (err, line) = (Invalid(4).with_context(|| "lazy"), line!());
+    assert_eq!(err.message(), "lazy");
+    if cfg!(debug_assertions) {
+        err.assert_located_at(file!(), line);
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_context_on_located_error() {
+    let ConfigError::Io(located) = read_config().unwrap_err();
+    let (err, line) = (located.context("loading"), line!());
+    err.assert_located_at(file!(), line);
+    assert!(err.error().to_string().starts_with("boom"));
+}
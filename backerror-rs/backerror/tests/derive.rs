@@ -0,0 +1,78 @@
+use backerror::BackError;
+use thiserror::Error;
+
+#[derive(Debug, Error, BackError)]
+pub enum AppError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("denied: {reason}")]
+    Denied { reason: String },
+
+    #[error("not found")]
+    NotFound,
+}
+
+#[derive(Debug, Error, BackError)]
+#[error(transparent)]
+pub struct ParseError(#[from] std::num::ParseIntError);
+
+fn read(path: &str) -> Result<String, LocatedAppError> {
+    Ok(std::fs::read_to_string(path)?)
+}
+
+fn legacy() -> Result<(), AppError> {
+    Err(AppError::Denied {
+        reason: "nope".to_string(),
+    })
+}
+
+#[test]
+fn test_derive() {
+    // `AppError` itself is left as it is
+    let err = AppError::from(std::io::Error::other("boom"));
+    let AppError::Io(io) = &err else {
+        panic!("{err:?}");
+    };
+    let _: &std::io::Error = io;
+
+    let err = read("/nonexistent/backerror").unwrap_err();
+    assert!(matches!(err, LocatedAppError::Io(_)), "{err:?}");
+
+    let err = LocatedAppError::from(legacy().unwrap_err());
+    assert_eq!(err.to_string(), "denied: nope");
+    assert_eq!(
+        LocatedAppError::from(AppError::NotFound).to_string(),
+        "not found"
+    );
+
+    let err = ParseError::from("x".parse::<u32>().unwrap_err());
+    assert!(
+        LocatedParseError::from(err)
+            .to_string()
+            .starts_with("invalid digit")
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_derive_located() {
+    let io = std::io::Error::other("boom");
+    let (err, line) = (LocatedAppError::from(io), line!());
+    let LocatedAppError::Io(located) = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(located.site_key(), (file!(), line));
+
+    // the `#[from]` fields of an `AppError` are located at the conversion
+    let err = AppError::from(std::io::Error::other("boom"));
+    let (err, line) = (LocatedAppError::from(err), line!());
+    let LocatedAppError::Io(located) = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(located.site_key(), (file!(), line));
+
+    let source = "x".parse::<u32>().unwrap_err();
+    let (err, line) = (LocatedParseError::from(source), line!());
+    assert_eq!(err.0.site_key(), (file!(), line));
+}
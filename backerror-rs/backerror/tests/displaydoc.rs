@@ -0,0 +1,61 @@
+use backerror::{BackError, backerror};
+use displaydoc::Display;
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Display, Error)]
+pub enum StoreError {
+    /// io: {0}
+    Io(#[from] std::io::Error),
+
+    /// key {0} not found
+    NotFound(String),
+}
+
+/// invalid port: {0}
+#[backerror]
+#[derive(Debug, Display, Error)]
+pub struct PortError(#[from] std::num::ParseIntError);
+
+/// config error: {0}
+#[derive(Debug, Display, Error, BackError)]
+pub struct ConfigError(#[from] std::io::Error);
+
+#[cfg(debug_assertions)]
+const PORT_LINE: u32 = line!() + 4;
+
+#[cfg(debug_assertions)]
+fn port(s: &str) -> Result<u16, PortError> {
+    Ok(s.parse()?)
+}
+
+#[test]
+fn test_displaydoc_enum() {
+    let err = StoreError::from(std::io::Error::other("boom"));
+    assert!(err.to_string().starts_with("io: boom"), "{err}");
+    let err = StoreError::NotFound("a".to_string());
+    assert_eq!(err.to_string(), "key a not found");
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_displaydoc_struct() {
+    let err = port("x").unwrap_err();
+    assert!(err.to_string().starts_with("invalid port: "), "{err}");
+    // enhanced like any struct
+    let PortError(located) = &err;
+    assert_eq!(located.site_key(), (file!(), PORT_LINE));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+fn test_displaydoc_derive() {
+    let err = ConfigError::from(std::io::Error::other("boom"));
+    let (located, line) = (LocatedConfigError::from(err), line!());
+    assert!(
+        located.to_string().starts_with("config error: boom"),
+        "{located}"
+    );
+    let LocatedConfigError(io) = &located;
+    assert_eq!(io.site_key().1, line);
+}
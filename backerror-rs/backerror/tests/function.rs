@@ -0,0 +1,64 @@
+#![cfg(debug_assertions)]
+
+use backerror::{LocatedError, locate};
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[locate]
+fn read_config(path: &str) -> Result<String, LocatedError<std::io::Error>> {
+    if path.is_empty() {
+        Err(std::io::Error::other("empty path"))?;
+    }
+    Ok(std::fs::read_to_string(path)?)
+}
+
+#[locate]
+fn load() -> Result<String, LocatedError<std::io::Error>> {
+    read_config("")
+}
+
+#[test]
+fn test_locate() {
+    let err = read_config("").unwrap_err();
+    assert_eq!(err.function().as_deref(), Some("function::read_config"));
+
+    // the innermost function is kept
+    let err = load().unwrap_err();
+    assert_eq!(err.function().as_deref(), Some("function::read_config"));
+}
+
+#[test]
+#[cfg(feature = "force_backtrace")]
+fn test_function_of_top_frame() {
+    let err = LocatedError::from(std::io::Error::other("boom"));
+    let function = err.function().unwrap();
+    assert!(
+        function.contains("test_function_of_top_frame"),
+        "{function}"
+    );
+}
+
+fn min_len(path: &str) -> Result<usize, String> {
+    path.strip_prefix("min:")
+        .map(str::len)
+        .ok_or_else(|| format!("no minimum length in {path}"))
+}
+
+const READ_LEN_LINE: u32 = line!() + 3;
+#[locate(each)]
+fn read_len(path: &str) -> Result<usize, BoxError> {
+    let len = std::fs::read_to_string(path)?.len();
+    // `String` isn't an `Error` type, it's converted as it is
+    Ok(len.max(min_len(path)?))
+}
+
+#[test]
+fn test_locate_each() {
+    let err = read_len("/nonexistent/backerror").unwrap_err();
+    let located = err.downcast_ref::<LocatedError<std::io::Error>>().unwrap();
+    assert_eq!(located.site_key(), (file!(), READ_LEN_LINE));
+    assert_eq!(located.function().as_deref(), Some("function::read_len"));
+
+    let err = read_len(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")).unwrap_err();
+    assert!(err.to_string().starts_with("no minimum length"), "{err}");
+}
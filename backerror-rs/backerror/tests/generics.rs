@@ -0,0 +1,72 @@
+use backerror::backerror;
+use std::error::Error as StdError;
+use thiserror::Error;
+
+/// the `From` impls carry the generics and where clause over
+#[backerror]
+#[derive(Debug, Error)]
+pub enum Wrapped<T: StdError>
+where
+    T: Send,
+{
+    #[error("inner: {0}")]
+    Inner(#[from] T),
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct Transparent<T: StdError>(#[from] T);
+
+/// bounds only given in the where clause are repeated on the `From` impls
+#[backerror]
+#[derive(Debug, Error)]
+pub enum Bounded<E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    #[error("bounded: {0}")]
+    Inner(#[from] E),
+}
+
+/// lifetimes too, e.g. for a borrowed context
+#[backerror]
+#[derive(Debug, Error)]
+pub enum Borrowed<'a> {
+    #[error("invalid key {0}")]
+    Key(&'a str),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_generic_from() {
+    let (err, line) = (Wrapped::from(std::io::Error::other("boom")), line!());
+    let Wrapped::Inner(located) = err;
+    located.assert_located_at(file!(), line);
+
+    let (err, line) = (Transparent::from(std::fmt::Error), line!());
+    err.0.assert_located_at(file!(), line);
+
+    let (err, line) = (Bounded::from(std::fmt::Error), line!());
+    let Bounded::Inner(located) = err;
+    located.assert_located_at(file!(), line);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_lifetime_from() {
+    fn lookup(key: &str) -> Result<(), Borrowed<'_>> {
+        if key.is_empty() {
+            Err(std::io::Error::other("empty"))?;
+        }
+        Err(Borrowed::Key(key))
+    }
+
+    assert_eq!(lookup("a").unwrap_err().to_string(), "invalid key a");
+    let Borrowed::Io(located) = lookup("").unwrap_err() else {
+        unreachable!()
+    };
+    assert_eq!(located.site_key().0, file!());
+}
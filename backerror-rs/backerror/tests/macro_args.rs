@@ -0,0 +1,610 @@
+use backerror::backerror;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+#[error("upstream failure")]
+pub struct Upstream;
+
+#[backerror(also_from(Arc, Box))]
+#[derive(Debug, Error)]
+pub enum UpstreamError {
+    #[error("By UpstreamError: {0}")]
+    Upstream(#[from] Upstream),
+}
+
+#[cfg(debug_assertions)]
+fn line_of(err: UpstreamError) -> u32 {
+    let UpstreamError::Upstream(located) = err;
+    located.site_key().1
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_also_from() {
+    use std::sync::Arc;
+
+    let (err, line) = (UpstreamError::from(Upstream), line!());
+    assert_eq!(line_of(err), line);
+
+    let (err, line) = (UpstreamError::from(Box::new(Upstream)), line!());
+    assert_eq!(line_of(err), line);
+
+    let (err, line) = (UpstreamError::from(Arc::new(Upstream)), line!());
+    assert_eq!(line_of(err), line);
+
+    let shared = Arc::new(Upstream);
+    let (err, line) = (UpstreamError::from(shared.clone()), line!());
+    assert_eq!(line_of(err), line);
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[backerror(help = "check that the file exists")]
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_variant_help() {
+    let LoadError::Io(located) = LoadError::from(std::io::Error::other("boom"));
+    assert_eq!(located.help(), Some("check that the file exists"));
+    assert!(
+        located
+            .report()
+            .to_string()
+            .ends_with("help: check that the file exists")
+    );
+}
+
+#[backerror(url = "https://runbooks.example.com/store")]
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[backerror(url = "https://runbooks.example.com/upstream")]
+    #[error("upstream: {0}")]
+    Upstream(#[from] Upstream),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_url() {
+    let StoreError::Io(located) = StoreError::from(std::io::Error::other("boom")) else {
+        unreachable!()
+    };
+    assert_eq!(located.url(), Some("https://runbooks.example.com/store"));
+
+    let StoreError::Upstream(located) = StoreError::from(Upstream) else {
+        unreachable!()
+    };
+    assert_eq!(located.url(), Some("https://runbooks.example.com/upstream"));
+    assert!(format!("{located:?}").ends_with("see: https://runbooks.example.com/upstream"));
+}
+
+#[backerror(wrap_source)]
+#[derive(Debug, Error)]
+pub enum ParseError {
+    #[error("invalid number on line {line}")]
+    Number {
+        #[source]
+        source: std::num::ParseIntError,
+        line: usize,
+    },
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_wrap_source() {
+    let source = "x".parse::<u32>().unwrap_err();
+    let (err, line) = (ParseError::new_number(source, 3), line!());
+    let ParseError::Number { source, line: 3 } = err else {
+        panic!("{err:?}")
+    };
+    source.assert_located_at(file!(), line);
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum ReadError {
+    /// `thiserror` doesn't allow context fields next to `#[from]`, `new_read` locates it instead
+    #[error("could not read {path}")]
+    Read {
+        #[from]
+        source: std::io::Error,
+        path: String,
+    },
+}
+
+#[backerror(wrap_source)]
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    /// a field named `source` is the source without `#[source]`
+    #[error("invalid byte at {offset}")]
+    Byte {
+        source: std::str::Utf8Error,
+        offset: usize,
+    },
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_source_with_context() {
+    let source = std::io::Error::other("boom");
+    let (err, line) = (ReadError::new_read(source, "a.toml".to_string()), line!());
+    assert_eq!(err.to_string(), "could not read a.toml");
+    let ReadError::Read { source, path } = &err;
+    assert_eq!(path, "a.toml");
+    source.assert_located_at(file!(), line);
+
+    let source = String::from_utf8(vec![0xff]).unwrap_err().utf8_error();
+    let (err, line) = (DecodeError::new_byte(source, 0), line!());
+    let DecodeError::Byte { source, offset: 0 } = &err else {
+        panic!("{err:?}")
+    };
+    source.assert_located_at(file!(), line);
+}
+
+/// `backerror` re-exported from a facade crate
+mod facade {
+    pub use ::backerror as errors;
+}
+
+#[backerror(crate = "facade::errors")]
+#[derive(Debug, Error)]
+pub enum FacadeError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(debug_assertions)]
+#[backerror::locate(each, crate = "facade::errors")]
+fn read_through_facade(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(std::fs::read_to_string(path)?)
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_crate_path() {
+    let (err, line) = (FacadeError::from(std::io::Error::other("boom")), line!());
+    let FacadeError::Io(located) = err;
+    let located: facade::errors::LocatedError<std::io::Error> = located;
+    located.assert_located_at(file!(), line);
+
+    let err = read_through_facade("/nonexistent/backerror").unwrap_err();
+    assert!(
+        err.downcast_ref::<facade::errors::LocatedError<std::io::Error>>()
+            .is_some()
+    );
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum SkipError {
+    /// already located, not wrapped again
+    #[backerror(skip)]
+    #[error("located: {0}")]
+    Located(#[from] backerror::LocatedError<std::io::Error>),
+    #[error("parse: {0}")]
+    Parse(
+        #[from]
+        #[backerror(skip)]
+        std::num::ParseIntError,
+    ),
+    #[error("upstream: {0}")]
+    Upstream(#[from] Upstream),
+}
+
+#[test]
+fn test_skip() {
+    let located = backerror::LocatedError::from(std::io::Error::other("boom"));
+    let SkipError::Located(located) = SkipError::from(located) else {
+        unreachable!()
+    };
+    let _: std::io::Error = located.into_inner();
+
+    let err = SkipError::from("x".parse::<u32>().unwrap_err());
+    let SkipError::Parse(source) = err else {
+        unreachable!()
+    };
+    let _: std::num::ParseIntError = source;
+}
+
+#[backerror(backtrace = false)]
+#[derive(Debug, Error)]
+pub enum HotError {
+    #[error("parse: {0}")]
+    Parse(#[from] std::num::ParseIntError),
+    #[backerror(backtrace = true)]
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_without_backtrace() {
+    let source = "x".parse::<u32>().unwrap_err();
+    let (err, line) = (HotError::from(source), line!());
+    let HotError::Parse(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+    #[cfg(capture_backtrace)]
+    assert!(located.backtrace().is_none());
+
+    let HotError::Io(located) = HotError::from(std::io::Error::other("boom")) else {
+        unreachable!()
+    };
+    #[cfg(all(capture_backtrace, feature = "force_backtrace"))]
+    assert!(located.backtrace().is_some());
+    let _ = located;
+}
+
+/// an in-house wrapper with its own fields, instead of `LocatedError`
+#[derive(Debug)]
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+pub struct Traced<E> {
+    error: E,
+    location: &'static std::panic::Location<'static>,
+    trace_id: u64,
+}
+
+impl<E> From<E> for Traced<E> {
+    #[track_caller]
+    fn from(error: E) -> Self {
+        Traced {
+            error,
+            location: std::panic::Location::caller(),
+            trace_id: 42,
+        }
+    }
+}
+
+impl<E: std::fmt::Display> std::fmt::Display for Traced<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [trace {}]", self.error, self.trace_id)
+    }
+}
+
+impl<E: std::error::Error> std::error::Error for Traced<E> {}
+
+#[backerror(wrap = "Traced")]
+#[derive(Debug, Error)]
+pub enum TracedError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_wrap() {
+    let (err, line) = (TracedError::from(std::io::Error::other("boom")), line!());
+    let TracedError::Io(traced) = err;
+    let traced: Traced<std::io::Error> = traced;
+    assert_eq!(traced.location.line(), line);
+    assert_eq!(traced.to_string(), "boom [trace 42]");
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum CopyError {
+    #[error("read: {0}")]
+    Read(#[from] std::io::Error),
+    #[backerror(skip_from)]
+    #[error("write: {0}")]
+    Write(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_skip_from() {
+    let (err, line) = (CopyError::from(std::io::Error::other("boom")), line!());
+    let CopyError::Read(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+
+    let (err, line) = (CopyError::new_write(std::io::Error::other("full")), line!());
+    let CopyError::Write(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+    assert!(std::error::Error::source(&CopyError::Write(located)).is_some());
+}
+
+#[backerror(no_from(std::io::Error))]
+#[derive(Debug, Error)]
+pub enum ManualError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("parse: {0}")]
+    Parse(#[from] std::num::ParseIntError),
+}
+
+/// hand-written, e.g. to log the error
+impl From<std::io::Error> for ManualError {
+    #[track_caller]
+    // `e` is wrapped into a `LocatedError` unless `#[backerror]` is off
+    #[allow(clippy::useless_conversion)]
+    fn from(e: std::io::Error) -> Self {
+        MANUAL.store(true, std::sync::atomic::Ordering::Relaxed);
+        ManualError::Io(e.into())
+    }
+}
+
+static MANUAL: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_no_from() {
+    let (err, line) = (ManualError::from(std::io::Error::other("boom")), line!());
+    let ManualError::Io(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+    assert!(MANUAL.load(std::sync::atomic::Ordering::Relaxed));
+
+    let source = "x".parse::<u32>().unwrap_err();
+    let (err, line) = (ManualError::from(source), line!());
+    let ManualError::Parse(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+}
+
+#[backerror(accessors)]
+#[derive(Debug, Error)]
+pub enum QueryError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid limit: {source}")]
+    Limit {
+        #[source]
+        source: std::num::ParseIntError,
+    },
+    #[error("empty query")]
+    Empty,
+    #[error("other: {0}")]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[test]
+fn test_accessors() {
+    let err = QueryError::from(std::io::Error::other("boom"));
+    assert!(err.is_io());
+    assert!(!err.is_empty());
+    let io: Option<&std::io::Error> = err.as_io();
+    assert_eq!(io.unwrap().to_string(), "boom");
+    assert!(err.as_limit().is_none());
+
+    let source = "x".parse::<u32>().unwrap_err();
+    let err = QueryError::Limit { source };
+    assert!(err.is_limit());
+    assert!(err.as_io().is_none());
+    assert!(err.as_limit().is_some());
+    assert!(QueryError::Empty.is_empty());
+
+    let err = QueryError::from(Box::<dyn std::error::Error + Send + Sync>::from("plain"));
+    let other: Option<&(dyn std::error::Error + Send + Sync)> = err.as_other();
+    assert_eq!(other.unwrap().to_string(), "plain");
+}
+
+#[backerror(result_alias = "LookupResult")]
+#[derive(Debug, Error)]
+pub enum LookupError<K: std::fmt::Debug> {
+    #[error("missing key {0:?}")]
+    Missing(K),
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+fn lookup(key: &str) -> LookupResult<u32, &str> {
+    match key {
+        "one" => Ok(1),
+        "io" => Err(std::io::Error::other("boom"))?,
+        key => Err(LookupError::Missing(key)),
+    }
+}
+
+#[test]
+fn test_result_alias() {
+    assert_eq!(lookup("one").unwrap(), 1);
+    assert!(matches!(lookup("two"), Err(LookupError::Missing("two"))));
+    assert!(matches!(lookup("io"), Err(LookupError::Io(_))));
+}
+
+#[backerror(release = "on")]
+#[derive(Debug, Error)]
+pub enum PaymentError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+// located in release builds too, unless `LocatedError` itself is a passthrough
+#[cfg(any(debug_assertions, not(feature = "release_passthrough")))]
+#[test]
+fn test_release_on() {
+    let io = std::io::Error::other("declined");
+    let (err, line) = (PaymentError::from(io), line!());
+    let PaymentError::Io(located) = err;
+    assert_eq!(located.site_key().1, line);
+}
+
+#[backerror(located_variants, accessors)]
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("missing key {key}")]
+    Missing { key: String },
+
+    #[error("invalid value {0}")]
+    Invalid(i64),
+
+    #[error("empty config")]
+    Empty,
+}
+
+#[test]
+fn test_located_variants() {
+    let (err, line) = (ConfigError::missing_at("name".to_string()), line!());
+    let ConfigError::Missing { key, location } = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(key, "name");
+    assert_eq!((location.file(), location.line()), (file!(), line));
+    assert_eq!(err.to_string(), "missing key name");
+
+    let (err, line) = (ConfigError::invalid_at(-1), line!());
+    let ConfigError::Invalid(value, location) = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!((*value, location.line()), (-1, line));
+    assert_eq!(err.as_invalid(), Some(&-1));
+
+    let err = ConfigError::empty_at();
+    assert!(err.is_empty());
+    assert_eq!(err.to_string(), "empty config");
+}
+
+#[backerror(accessors)]
+pub mod errors {
+    use thiserror::Error;
+
+    #[derive(Debug, Error)]
+    pub enum ReadError {
+        #[error("io: {0}")]
+        Io(#[from] std::io::Error),
+    }
+
+    /// keeps its own arguments, no accessors
+    #[backerror::backerror(backtrace = false)]
+    #[derive(Debug, Error)]
+    #[error(transparent)]
+    pub struct WriteError(#[from] pub std::io::Error);
+
+    pub mod nested {
+        use thiserror::Error;
+
+        #[derive(Debug, Error)]
+        pub enum ParseError {
+            #[error("parse: {0}")]
+            Int(#[from] std::num::ParseIntError),
+        }
+    }
+}
+
+#[test]
+fn test_module() {
+    let err = errors::ReadError::from(std::io::Error::other("boom"));
+    assert!(err.is_io());
+    let err = errors::nested::ParseError::from("x".parse::<u32>().unwrap_err());
+    assert!(err.as_int().is_some());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_module_located() {
+    let io = std::io::Error::other("boom");
+    let (err, line) = (errors::ReadError::from(io), line!());
+    let errors::ReadError::Io(located) = err;
+    assert_eq!(located.site_key(), (file!(), line));
+
+    // `backtrace = false` of its own
+    let err = errors::WriteError::from(std::io::Error::other("boom"));
+    #[cfg(capture_backtrace)]
+    assert!(err.0.backtrace().is_none());
+    let _ = err;
+}
+
+#[cfg(debug_assertions)]
+mod aliased {
+    use backerror::backerror;
+    use thiserror::Error as TError;
+
+    #[backerror(force)]
+    #[derive(Debug, TError)]
+    pub enum SendError {
+        #[error("io: {0}")]
+        Io(#[from] std::io::Error),
+    }
+
+    #[backerror]
+    pub mod errors {
+        use thiserror::Error as ThisError;
+
+        #[derive(Debug, ThisError)]
+        pub enum RecvError {
+            #[error("io: {0}")]
+            Io(#[from] std::io::Error),
+        }
+    }
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_aliased_derive() {
+    let io = std::io::Error::other("boom");
+    let (err, line) = (aliased::SendError::from(io), line!());
+    let aliased::SendError::Io(located) = err;
+    assert_eq!(located.site_key(), (file!(), line));
+
+    let io = std::io::Error::other("boom");
+    let (err, line) = (aliased::errors::RecvError::from(io), line!());
+    let aliased::errors::RecvError::Io(located) = err;
+    assert_eq!(located.site_key(), (file!(), line));
+}
+
+#[backerror(auto_from)]
+#[derive(Debug, Error)]
+pub enum SyncError {
+    #[error("io: {0}")]
+    Io(std::io::Error),
+    #[error("invalid count: {0}")]
+    Count(std::num::ParseIntError),
+    #[error("conflict: {0}")]
+    Conflict(String),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_auto_from() {
+    let (err, line) = (SyncError::from(std::io::Error::other("boom")), line!());
+    let SyncError::Io(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+
+    let source = "x".parse::<u32>().unwrap_err();
+    let (err, line) = (SyncError::from(source), line!());
+    let SyncError::Count(located) = err else {
+        unreachable!()
+    };
+    located.assert_located_at(file!(), line);
+    assert_eq!(SyncError::Conflict("a".into()).to_string(), "conflict: a");
+}
+
+#[backerror(name = "Quota")]
+#[derive(Debug, Error)]
+#[error("quota of {0} exceeded")]
+pub struct QuotaExceeded<T: std::fmt::Debug + std::fmt::Display>(T);
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("upload failed: {0}")]
+    Quota(#[from] QuotaExceeded<u64>),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_error_name() {
+    let UploadError::Quota(located) = UploadError::from(QuotaExceeded(5));
+    let display = located.to_string();
+    assert!(display.contains("; Caused by Quota("), "{display}");
+    assert!(!display.contains("QuotaExceeded"), "{display}");
+}
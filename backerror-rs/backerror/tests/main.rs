@@ -0,0 +1,40 @@
+use backerror::backerror;
+use std::process::ExitCode;
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("failed to read input")]
+    Read(#[from] std::io::Error),
+}
+
+#[backerror::main]
+fn succeed() -> Result<(), CliError> {
+    Ok(())
+}
+
+#[backerror::main]
+fn fail() -> Result<(), CliError> {
+    Err(std::io::Error::other("boom"))?;
+    Ok(())
+}
+
+#[backerror::main]
+fn fail_erased() -> Result<(), backerror::Error> {
+    Err(std::io::Error::other("boom"))?;
+    Ok(())
+}
+
+#[backerror::main]
+fn exit_code() -> Result<ExitCode, CliError> {
+    Ok(ExitCode::from(3))
+}
+
+#[test]
+fn test_main() {
+    assert_eq!(succeed(), ExitCode::SUCCESS);
+    assert_eq!(fail(), ExitCode::FAILURE);
+    assert_eq!(fail_erased(), ExitCode::FAILURE);
+    assert_eq!(exit_code(), ExitCode::from(3));
+}
@@ -0,0 +1,156 @@
+use backerror::backerror;
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum MixedError {
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("not found")]
+    NotFound,
+
+    #[error("timeout after {0}ms")]
+    Timeout(u64),
+
+    #[error("parse: {0}")]
+    Parse(#[source] std::num::ParseIntError),
+
+    #[error("denied: {reason}")]
+    Denied { reason: String },
+
+    /// catch-all, wrapped into `LocatedError<BoxedError<_>>`
+    #[error("other: {0}")]
+    Other(#[from] Box<dyn std::error::Error + Send + Sync>),
+}
+
+fn parse(s: &str) -> Result<u64, MixedError> {
+    s.parse().map_err(MixedError::Parse)
+}
+
+#[test]
+fn test_non_from_variants_untouched() {
+    // fields of variants without `#[from]` keep their original types
+    let err = MixedError::Timeout(30u64);
+    assert_eq!(err.to_string(), "timeout after 30ms");
+
+    let err = parse("x").unwrap_err();
+    let MixedError::Parse(source) = &err else {
+        panic!("{err:?}");
+    };
+    let _: &std::num::ParseIntError = source;
+
+    let err = MixedError::Denied {
+        reason: "nope".to_string(),
+    };
+    assert_eq!(err.to_string(), "denied: nope");
+
+    assert_eq!(MixedError::NotFound.to_string(), "not found");
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_from_variant_located() {
+    let (err, line) = (MixedError::from(std::io::Error::other("boom")), line!());
+    let MixedError::Io(located) = err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(located.site_key(), (file!(), line));
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+fn load(s: &str) -> Result<u64, MixedError> {
+    let n: u64 = s.parse().map_err(|e| Box::new(e) as BoxError)?;
+    if n == 0 {
+        return Err(BoxError::from("zero").into());
+    }
+    Ok(n)
+}
+
+#[test]
+fn test_catch_all_variant() {
+    let err = load("0").unwrap_err();
+    let MixedError::Other(other) = &err else {
+        panic!("{err:?}");
+    };
+    assert!(other.to_string().starts_with("zero"));
+
+    let err = load("x").unwrap_err();
+    let MixedError::Other(other) = &err else {
+        panic!("{err:?}");
+    };
+    assert!(other.downcast_ref::<std::num::ParseIntError>().is_some());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_catch_all_located() {
+    let boxed: BoxError = Box::new(std::io::Error::other("boom"));
+    let (err, line) = (MixedError::from(boxed), line!());
+    let MixedError::Other(located) = err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(located.site_key(), (file!(), line));
+}
+
+/// `cfg(any())` is never set, `cfg(not(any()))` always is
+#[backerror(also_from(Box), accessors)]
+#[derive(Debug, Error)]
+pub enum GatedError {
+    #[cfg(not(any()))]
+    #[error("io: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[cfg(any())]
+    #[error("fmt: {0}")]
+    Fmt(#[from] std::fmt::Error),
+
+    #[cfg(any())]
+    #[backerror(skip_from)]
+    #[error("copy: {0}")]
+    Copy(#[from] std::io::Error),
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_cfg_variants() {
+    // the impls of `Fmt` and `Copy` are configured out along with the variants
+    let err = GatedError::from(Box::new(std::io::Error::other("boom")));
+    assert!(err.is_io());
+    assert!(err.as_io().is_some());
+}
+
+fn fmt_io(err: &std::io::Error, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "io ({:?}): {err}", err.kind())
+}
+
+fn fmt_retry(attempt: &u32, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(f, "retry #{attempt}")
+}
+
+/// `#[error(fmt = ...)]` of thiserror 2
+#[backerror(located_variants)]
+#[derive(Debug, Error)]
+pub enum FmtError {
+    #[error(fmt = fmt_io)]
+    Io(#[from] std::io::Error),
+
+    #[error(fmt = fmt_retry)]
+    Retry(u32),
+
+    #[error("gave up after {0}")]
+    GaveUp(u32),
+}
+
+#[test]
+fn test_error_fmt() {
+    let err = FmtError::from(std::io::Error::other("boom"));
+    assert!(err.to_string().starts_with("io (Other): boom"), "{err}");
+
+    // left as it is by `located_variants`
+    let err = FmtError::Retry(2);
+    assert_eq!(err.to_string(), "retry #2");
+    let err = FmtError::gave_up_at(3);
+    assert_eq!(err.to_string(), "gave up after 3");
+}
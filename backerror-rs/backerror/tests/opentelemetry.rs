@@ -0,0 +1,65 @@
+#![cfg(all(feature = "opentelemetry", debug_assertions))]
+
+use backerror::LocatedError;
+use opentelemetry::trace::{Tracer, TracerProvider};
+use opentelemetry::{KeyValue, Value};
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("wrapped")]
+pub struct Wrapped(#[source] LocatedError<std::io::Error>);
+
+fn attribute<'a>(attributes: &'a [KeyValue], key: &str) -> Option<&'a Value> {
+    attributes
+        .iter()
+        .find(|kv| kv.key.as_str() == key)
+        .map(|kv| &kv.value)
+}
+
+#[test]
+fn test_exception_event() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let tracer = provider.tracer("backerror");
+
+    // no active span, nothing is recorded
+    let _ = LocatedError::from(std::io::Error::other("outside")).to_string();
+
+    let line = tracer.in_span("load", |_cx| {
+        let (err, line) = (LocatedError::from(std::io::Error::other("boom")), line!());
+        // re-wrapping doesn't record the same error again
+        let _ = LocatedError::from(Wrapped(err)).to_string();
+        line
+    });
+
+    let spans = exporter.get_finished_spans().unwrap();
+    assert_eq!(spans.len(), 1);
+    let events = &spans[0].events.events;
+    assert_eq!(events.len(), 1);
+
+    let event = &events[0];
+    assert_eq!(event.name, "exception");
+    let attributes = &event.attributes;
+    assert_eq!(
+        attribute(attributes, "exception.type"),
+        Some(&Value::from("std::io::error::Error"))
+    );
+    assert_eq!(
+        attribute(attributes, "exception.message"),
+        Some(&Value::from("boom"))
+    );
+    assert_eq!(
+        attribute(attributes, "code.filepath"),
+        Some(&Value::from(file!()))
+    );
+    assert_eq!(
+        attribute(attributes, "code.lineno"),
+        Some(&Value::from(i64::from(line)))
+    );
+    if cfg!(feature = "force_backtrace") {
+        assert!(attribute(attributes, "exception.stacktrace").is_some());
+    }
+}
@@ -0,0 +1,54 @@
+use backerror::backerror;
+use snafu::{ResultExt, Snafu};
+
+#[backerror]
+#[derive(Debug, Snafu)]
+pub enum ConfigError {
+    #[snafu(display("could not read {path}"))]
+    Read {
+        source: std::io::Error,
+        path: String,
+    },
+
+    #[snafu(display("invalid port"))]
+    Port {
+        #[snafu(source)]
+        cause: std::num::ParseIntError,
+    },
+
+    #[snafu(display("missing key {key}"))]
+    Missing { key: String },
+}
+
+#[cfg(debug_assertions)]
+const READ_LINE: u32 = line!() + 3;
+
+fn read(path: &str) -> Result<String, ConfigError> {
+    std::fs::read_to_string(path).context(ReadSnafu { path })
+}
+
+fn port(s: &str) -> Result<u16, ConfigError> {
+    s.parse().context(PortSnafu)
+}
+
+#[test]
+fn test_snafu() {
+    let err = read("/nonexistent/backerror").unwrap_err();
+    assert_eq!(err.to_string(), "could not read /nonexistent/backerror");
+    let ConfigError::Read { source, .. } = &err else {
+        panic!("{err:?}");
+    };
+    assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+    #[cfg(debug_assertions)]
+    {
+        let source: &backerror::LocatedError<std::io::Error> = source;
+        assert_eq!(source.site_key(), (file!(), READ_LINE));
+    }
+
+    let err = port("x").unwrap_err();
+    assert!(matches!(err, ConfigError::Port { .. }), "{err:?}");
+    assert!(std::error::Error::source(&err).is_some());
+
+    let err = MissingSnafu { key: "name" }.build();
+    assert_eq!(err.to_string(), "missing key name");
+}
@@ -0,0 +1,83 @@
+use backerror::backerror;
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum Level1 {
+    #[error("level1: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum Level2 {
+    #[error("level2: {0}")]
+    L1(#[from] Level1),
+}
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum Level3 {
+    #[error("level3: {0}")]
+    L2(#[from] Level2),
+}
+
+#[test]
+#[cfg(all(debug_assertions, feature = "force_backtrace"))]
+fn test_trail_in_debug() {
+    let (err, io_line) = (Level1::from(std::io::Error::other("boom")), line!());
+    let (err, level1_line) = (Level2::from(err), line!());
+    let (err, level2_line) = (Level3::from(err), line!());
+
+    let Level3::L2(located) = &err;
+    let trail: Vec<u32> = located
+        .trail()
+        .iter()
+        .map(|location| location.line())
+        .collect();
+    assert_eq!(trail, [level2_line, level1_line, io_line]);
+
+    // the hops follow the header of the outermost `LocatedError`, which has its own location
+    let debug = format!("{err:?}");
+    let lines: Vec<&str> = debug.lines().collect();
+    let header = lines
+        .iter()
+        .position(|line| line.starts_with("Caused by: "))
+        .unwrap();
+    assert!(
+        lines[header].contains(&format!("{}:{level2_line}:", file!())),
+        "{debug}"
+    );
+    assert_eq!(
+        lines[header + 1..header + 3],
+        [
+            format!("-> {}:{level1_line}", file!()),
+            format!("-> {}:{io_line}", file!())
+        ],
+        "{debug}"
+    );
+    assert_eq!(
+        lines.iter().filter(|line| line.starts_with("-> ")).count(),
+        2,
+        "{debug}"
+    );
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_locations() {
+    let (err, io_line) = (Level1::from(std::io::Error::other("boom")), line!());
+    let (err, level1_line) = (Level2::from(err), line!());
+    let (err, level2_line) = (Level3::from(err), line!());
+
+    let Level3::L2(located) = &err;
+    let path: Vec<String> = located
+        .locations()
+        .iter()
+        .map(|location| location.line().to_string())
+        .collect();
+    assert_eq!(
+        path.join(" <- "),
+        format!("{level2_line} <- {level1_line} <- {io_line}")
+    );
+}
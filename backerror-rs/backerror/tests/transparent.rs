@@ -0,0 +1,130 @@
+use backerror::backerror;
+use thiserror::Error;
+
+/// genuine transparency, the `#[from]` field is wrapped into `LocatedError`
+#[backerror]
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct Transparent(#[from] std::io::Error);
+
+/// display attribute, the `#[from]` field is wrapped all the same
+#[backerror]
+#[derive(Debug, Error)]
+#[error("display {0}")]
+pub struct WithMessage(#[from] std::io::Error);
+
+/// a literal `"transparent"` is a display attribute too
+#[backerror]
+#[derive(Debug, Error)]
+#[error("transparent")]
+pub struct Literal(#[from] std::io::Error);
+
+/// catch-all for boxed errors, wrapped into `LocatedError<BoxedError<_>>`
+#[backerror]
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct CatchAll(#[from] Box<dyn std::error::Error + Send + Sync>);
+
+/// brace-style struct, the named `#[from]` field is wrapped too
+#[backerror]
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct Named {
+    #[from]
+    source: std::io::Error,
+}
+
+/// named field of an enum variant
+#[backerror]
+#[derive(Debug, Error)]
+pub enum NamedVariant {
+    #[error("io: {source}")]
+    Io {
+        #[from]
+        source: std::io::Error,
+    },
+}
+
+#[cfg(debug_assertions)]
+fn io_error() -> std::io::Error {
+    std::io::Error::other("boom")
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_transparent() {
+    let (err, line) = (Transparent::from(io_error()), line!());
+    assert_eq!(err.0.site_key(), (file!(), line));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_named_fields() {
+    let (err, line) = (Named::from(io_error()), line!());
+    assert_eq!(err.source.site_key(), (file!(), line));
+
+    let (err, line) = (NamedVariant::from(io_error()), line!());
+    let NamedVariant::Io { source } = err;
+    assert_eq!(source.site_key(), (file!(), line));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_boxed_dyn() {
+    let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(io_error());
+    let (err, line) = (CatchAll::from(boxed), line!());
+    assert_eq!(err.0.site_key(), (file!(), line));
+    assert!(err.to_string().starts_with("boom; Caused by "));
+    assert!(err.0.downcast_ref::<std::io::Error>().is_some());
+
+    let err = CatchAll::from(Box::<dyn std::error::Error + Send + Sync>::from("plain"));
+    assert!(err.to_string().starts_with("plain; Caused by "));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_not_transparent() {
+    let (err, line) = (WithMessage::from(io_error()), line!());
+    assert_eq!(err.0.site_key(), (file!(), line));
+    assert!(err.to_string().starts_with("display boom"), "{err}");
+
+    let (err, line) = (Literal::from(io_error()), line!());
+    assert_eq!(err.0.site_key(), (file!(), line));
+    assert_eq!(err.to_string(), "transparent");
+}
+
+/// a `#[from]` field with context fields, which `thiserror` doesn't allow
+#[backerror]
+#[derive(Debug, Error)]
+#[error("failed to read {path}: {source}")]
+pub struct ReadError {
+    #[from]
+    source: std::io::Error,
+    path: String,
+}
+
+/// `#[source]` fields with `wrap_source`
+#[backerror(wrap_source)]
+#[derive(Debug, Error)]
+#[error("invalid port {port}")]
+pub struct PortError {
+    #[source]
+    cause: std::num::ParseIntError,
+    port: String,
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_struct_constructor() {
+    let (err, line) = (ReadError::new(io_error(), "a.toml".to_string()), line!());
+    assert_eq!(err.source.site_key(), (file!(), line));
+    assert!(
+        err.to_string().starts_with("failed to read a.toml: boom"),
+        "{err}"
+    );
+
+    let cause = "x".parse::<u16>().unwrap_err();
+    let (err, line) = (PortError::new(cause, "x".to_string()), line!());
+    assert_eq!(err.cause.site_key(), (file!(), line));
+    assert_eq!(err.to_string(), "invalid port x");
+}
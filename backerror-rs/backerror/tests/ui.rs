@@ -0,0 +1,7 @@
+/// compile errors reported by `#[backerror]`, in debug builds where the macro is active
+#[test]
+#[cfg(debug_assertions)]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
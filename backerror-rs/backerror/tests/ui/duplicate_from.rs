@@ -0,0 +1,13 @@
+use backerror::backerror;
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("read: {0}")]
+    Read(#[from] std::io::Error),
+    #[error("write: {0}")]
+    Write(#[from] std::io::Error),
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use backerror::backerror;
+use thiserror::Error;
+
+#[backerror]
+#[derive(Debug, Error)]
+pub enum BatchError {
+    #[error("batch failed")]
+    Batch(#[from] Vec<std::io::Error>),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use backerror::backerror;
+
+#[backerror]
+#[derive(Debug)]
+pub enum AppError {
+    Io(std::io::Error),
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use backerror::backerror;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum InnerError {
+    #[error("io")]
+    Io(#[from] std::io::Error),
+}
+
+#[backerror]
+pub type ApiError = InnerError;
+
+fn main() {}